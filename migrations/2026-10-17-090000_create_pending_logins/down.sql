@@ -0,0 +1 @@
+DROP TABLE IF EXISTS `pending_logins`;
\ No newline at end of file
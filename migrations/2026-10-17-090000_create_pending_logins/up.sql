@@ -0,0 +1,28 @@
+-- Represent an `OAuth2` login that was started from Discord but not finished yet.
+CREATE TABLE `pending_logins`
+(
+    -- The CSRF token sent to the provider and returned in the callback.
+    `csrf_state`         VARCHAR(64)     NOT NULL,
+
+    `guild_id`           BIGINT UNSIGNED NOT NULL,
+    `discord_id`         BIGINT UNSIGNED NOT NULL,
+    -- A Discord username is limited to 32 characters.
+    `username`           VARCHAR(32)     NOT NULL,
+    `guild_image_source` TEXT            NOT NULL,
+    -- The locale of the interaction, used to answer the member once the flow is done.
+    `locale`             VARCHAR(16)     NOT NULL,
+    -- Used to edit the ephemeral login message, even after a restart.
+    -- An interaction token stays valid for 15 minutes.
+    `interaction_token`  TEXT            NOT NULL,
+
+    `class_id`           INTEGER         NOT NULL,
+
+    `expires_at`         DATETIME        NOT NULL,
+
+    PRIMARY KEY (`csrf_state`),
+
+    FOREIGN KEY (`guild_id`) REFERENCES `guilds` (`id`)
+        ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (`class_id`) REFERENCES `classes` (`id`)
+        ON DELETE CASCADE ON UPDATE CASCADE
+);
@@ -0,0 +1,6 @@
+ALTER TABLE `pending_logins`
+    DROP COLUMN `nonce`,
+    DROP COLUMN `identity_provider`;
+
+ALTER TABLE `guilds`
+    DROP COLUMN `identity_provider`;
@@ -0,0 +1,11 @@
+-- The identity provider used to verify members, `NULL` means the default one.
+ALTER TABLE `guilds`
+    ADD COLUMN `identity_provider` VARCHAR(32) NULL AFTER `verified_role_id`;
+
+-- Logins started before this migration can't be finished anymore.
+DELETE FROM `pending_logins`;
+
+ALTER TABLE `pending_logins`
+    ADD COLUMN `identity_provider` VARCHAR(32) NOT NULL AFTER `interaction_token`,
+    -- Sent to the provider and checked in the returned ID token.
+    ADD COLUMN `nonce`             VARCHAR(64) NOT NULL AFTER `identity_provider`;
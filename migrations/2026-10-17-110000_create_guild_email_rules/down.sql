@@ -0,0 +1,14 @@
+ALTER TABLE `guilds`
+    ADD COLUMN `verification_email_domain` VARCHAR(100) NULL AFTER `owner_id`;
+
+-- Only a single domain can be kept per guild.
+UPDATE `guilds`
+SET `verification_email_domain` = (SELECT `pattern`
+                                   FROM `guild_email_rules`
+                                   WHERE `guild_email_rules`.`guild_id` = `guilds`.`id`
+                                     AND `kind` = 'domain'
+                                     AND `pattern` NOT LIKE '*.%'
+                                   ORDER BY `id`
+                                   LIMIT 1);
+
+DROP TABLE IF EXISTS `guild_email_rules`;
@@ -0,0 +1,29 @@
+-- Represent a rule that allows some emails to verify themselves in a guild.
+CREATE TABLE `guild_email_rules`
+(
+    `id`       INTEGER         NOT NULL AUTO_INCREMENT,
+
+    `guild_id` BIGINT UNSIGNED NOT NULL,
+    -- How the pattern is matched: `domain`, `glob` or `regex`.
+    `kind`     VARCHAR(16)     NOT NULL,
+    `pattern`  VARCHAR(255)    NOT NULL,
+    -- The role to give instead of the guild verified role, if any.
+    `role_id`  BIGINT UNSIGNED,
+
+    PRIMARY KEY (`id`),
+
+    -- Guarantee that the same rule isn't added twice.
+    UNIQUE (`guild_id`, `kind`, `pattern`),
+
+    FOREIGN KEY (`guild_id`) REFERENCES `guilds` (`id`)
+        ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- Keep the domains that were already configured.
+INSERT INTO `guild_email_rules` (`guild_id`, `kind`, `pattern`)
+SELECT `id`, 'domain', `verification_email_domain`
+FROM `guilds`
+WHERE `verification_email_domain` IS NOT NULL;
+
+ALTER TABLE `guilds`
+    DROP COLUMN `verification_email_domain`;
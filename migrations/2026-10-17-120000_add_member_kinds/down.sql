@@ -0,0 +1,23 @@
+-- Members without a class cannot be represented anymore.
+DELETE `groups_of_verified_members`
+FROM `groups_of_verified_members`
+         INNER JOIN `verified_members`
+                    ON `verified_members`.`member_id` = `groups_of_verified_members`.`verified_member_id`
+WHERE `verified_members`.`class_id` IS NULL;
+DELETE FROM `verified_members` WHERE `class_id` IS NULL;
+DELETE FROM `pending_logins` WHERE `class_id` IS NULL;
+
+ALTER TABLE `verified_members`
+    DROP COLUMN `member_kind`,
+    MODIFY `class_id` INTEGER NOT NULL;
+
+ALTER TABLE `pending_logins`
+    DROP COLUMN `member_kind`,
+    MODIFY `class_id` INTEGER NOT NULL;
+
+ALTER TABLE `guild_email_rules`
+    DROP COLUMN `member_kind`;
+
+ALTER TABLE `guilds`
+    DROP COLUMN `teacher_role_id`,
+    DROP COLUMN `staff_role_id`;
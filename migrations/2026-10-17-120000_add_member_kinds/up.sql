@@ -0,0 +1,19 @@
+-- The roles given to the members that are not students.
+ALTER TABLE `guilds`
+    ADD COLUMN `teacher_role_id` BIGINT UNSIGNED NULL AFTER `verified_role_id`,
+    ADD COLUMN `staff_role_id`   BIGINT UNSIGNED NULL AFTER `teacher_role_id`;
+
+-- The kind of member an email rule verifies: `student`, `teacher` or `staff`.
+-- When not set, the member chooses it.
+ALTER TABLE `guild_email_rules`
+    ADD COLUMN `member_kind` VARCHAR(16) NULL AFTER `role_id`;
+
+-- The kind the member chose, before the rule is known.
+ALTER TABLE `pending_logins`
+    ADD COLUMN `member_kind` VARCHAR(16) NOT NULL DEFAULT 'student' AFTER `nonce`,
+    MODIFY `class_id` INTEGER NULL;
+
+-- Only students belong to a class.
+ALTER TABLE `verified_members`
+    ADD COLUMN `member_kind` VARCHAR(16) NOT NULL DEFAULT 'student' AFTER `last_name`,
+    MODIFY `class_id` INTEGER NULL;
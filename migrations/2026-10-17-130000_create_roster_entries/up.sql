@@ -0,0 +1,23 @@
+-- Represent a student imported from the school roster, bound to a class before logging in.
+CREATE TABLE `roster_entries`
+(
+    `id`         INTEGER         NOT NULL AUTO_INCREMENT,
+
+    `guild_id`   BIGINT UNSIGNED NOT NULL,
+    -- Stored in lower case.
+    `mail`       VARCHAR(256)    NOT NULL,
+    `first_name` TEXT            NOT NULL,
+    `last_name`  TEXT            NOT NULL,
+
+    `class_id`   INTEGER         NOT NULL,
+
+    PRIMARY KEY (`id`),
+
+    -- Guarantee that a student is only listed once per guild.
+    UNIQUE (`guild_id`, `mail`),
+
+    FOREIGN KEY (`guild_id`) REFERENCES `guilds` (`id`)
+        ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (`class_id`) REFERENCES `classes` (`id`)
+        ON DELETE CASCADE ON UPDATE CASCADE
+);
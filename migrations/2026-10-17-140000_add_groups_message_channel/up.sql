@@ -0,0 +1,3 @@
+-- Needed to edit the groups message when groups change.
+ALTER TABLE `guilds`
+    ADD COLUMN `groups_message_channel_id` BIGINT UNSIGNED NULL AFTER `groups_message_id`;
@@ -0,0 +1,4 @@
+ALTER TABLE `levels`
+    DROP FOREIGN KEY `levels_next_level_id_fk`,
+    DROP COLUMN `graduating`,
+    DROP COLUMN `next_level_id`;
@@ -0,0 +1,7 @@
+-- Where the students of a level go when the school year rolls over.
+-- A level without a next level keeps its students, unless they graduate.
+ALTER TABLE `levels`
+    ADD COLUMN `next_level_id` INTEGER NULL AFTER `role_id`,
+    ADD COLUMN `graduating`    BOOLEAN NOT NULL DEFAULT FALSE AFTER `next_level_id`,
+    ADD CONSTRAINT `levels_next_level_id_fk` FOREIGN KEY (`next_level_id`) REFERENCES `levels` (`id`)
+        ON DELETE SET NULL ON UPDATE CASCADE;
@@ -0,0 +1,4 @@
+ALTER TABLE `guilds`
+    DROP COLUMN `level_up_channel_id`;
+
+DROP TABLE `xp_rewards`;
@@ -0,0 +1,22 @@
+-- Roles granted to the members once they earned enough XP.
+CREATE TABLE `xp_rewards`
+(
+    `id`        INTEGER         NOT NULL AUTO_INCREMENT,
+
+    `guild_id`  BIGINT UNSIGNED NOT NULL,
+    -- The total of message and voice XP needed to get the role.
+    `threshold` INTEGER         NOT NULL,
+    `role_id`   BIGINT UNSIGNED NOT NULL,
+
+    PRIMARY KEY (`id`),
+
+    -- Guarantee that a role is only granted by one reward.
+    UNIQUE (`guild_id`, `role_id`),
+
+    FOREIGN KEY (`guild_id`) REFERENCES `guilds` (`id`)
+        ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- Where the rewards are announced, not announced when null.
+ALTER TABLE `guilds`
+    ADD COLUMN `level_up_channel_id` BIGINT UNSIGNED NULL AFTER `groups_message_channel_id`;
@@ -0,0 +1,10 @@
+ALTER TABLE `guilds`
+    DROP COLUMN `verified_role_policy`,
+    DROP COLUMN `log_channel_id`;
+
+ALTER TABLE `groups`
+    DROP COLUMN `broken`;
+ALTER TABLE `classes`
+    DROP COLUMN `broken`;
+ALTER TABLE `levels`
+    DROP COLUMN `broken`;
@@ -0,0 +1,13 @@
+-- A level, class or group whose role was deleted on Discord cannot be joined until it gets a new role.
+ALTER TABLE `levels`
+    ADD COLUMN `broken` BOOLEAN NOT NULL DEFAULT FALSE AFTER `graduating`;
+ALTER TABLE `classes`
+    ADD COLUMN `broken` BOOLEAN NOT NULL DEFAULT FALSE AFTER `role_id`;
+ALTER TABLE `groups`
+    ADD COLUMN `broken` BOOLEAN NOT NULL DEFAULT FALSE AFTER `role_id`;
+
+-- Where the bot tells the admins about what it did on its own,
+-- and what to do when a verified member loses the verified role by hand.
+ALTER TABLE `guilds`
+    ADD COLUMN `log_channel_id`       BIGINT UNSIGNED NULL AFTER `level_up_channel_id`,
+    ADD COLUMN `verified_role_policy` VARCHAR(16) NOT NULL DEFAULT 'ignore' AFTER `log_channel_id`;
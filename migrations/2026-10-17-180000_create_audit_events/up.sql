@@ -0,0 +1,23 @@
+-- What happened in a guild and who did it, shown to the admins with `/audit search`.
+CREATE TABLE `audit_events`
+(
+    `id`         INTEGER         NOT NULL AUTO_INCREMENT,
+
+    `guild_id`   BIGINT UNSIGNED NOT NULL,
+    `kind`       VARCHAR(32)     NOT NULL,
+    -- Who did the action, null when the bot did it on its own.
+    `actor_id`   BIGINT UNSIGNED NULL,
+    -- Who the action was done to, null for configuration changes.
+    `target_id`  BIGINT UNSIGNED NULL,
+    `details`    TEXT            NULL,
+    `created_at` DATETIME        NOT NULL,
+
+    PRIMARY KEY (`id`),
+
+    INDEX (`guild_id`, `created_at`),
+    INDEX (`guild_id`, `target_id`),
+    INDEX (`guild_id`, `actor_id`),
+
+    FOREIGN KEY (`guild_id`) REFERENCES `guilds` (`id`)
+        ON DELETE CASCADE ON UPDATE CASCADE
+);
@@ -0,0 +1,6 @@
+ALTER TABLE `verified_members`
+    DROP COLUMN `reminded_at`,
+    DROP COLUMN `verified_at`;
+
+ALTER TABLE `guilds`
+    DROP COLUMN `reverification_days`;
@@ -0,0 +1,9 @@
+-- Members must verify themselves again after this number of days, never when null.
+ALTER TABLE `guilds`
+    ADD COLUMN `reverification_days` INTEGER NULL AFTER `verified_role_policy`;
+
+-- Existing members start a full period now.
+ALTER TABLE `verified_members`
+    ADD COLUMN `verified_at` DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP AFTER `class_id`,
+    -- When the member was told that their verification expires soon.
+    ADD COLUMN `reminded_at` DATETIME NULL AFTER `verified_at`;
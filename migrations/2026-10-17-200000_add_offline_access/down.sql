@@ -0,0 +1,6 @@
+ALTER TABLE `verified_members`
+    DROP COLUMN `token_provider`,
+    DROP COLUMN `refresh_token`;
+
+ALTER TABLE `guilds`
+    DROP COLUMN `offline_access`;
@@ -0,0 +1,8 @@
+-- Whether members are asked for offline access, to notice deactivated accounts.
+ALTER TABLE `guilds`
+    ADD COLUMN `offline_access` BOOL NOT NULL DEFAULT FALSE AFTER `reverification_days`;
+
+-- The refresh token is encrypted with the key of the bot.
+ALTER TABLE `verified_members`
+    ADD COLUMN `refresh_token`  BLOB        NULL AFTER `reminded_at`,
+    ADD COLUMN `token_provider` VARCHAR(32) NULL AFTER `refresh_token`;
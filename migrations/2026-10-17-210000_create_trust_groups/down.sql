@@ -0,0 +1,11 @@
+ALTER TABLE `verified_members`
+    DROP FOREIGN KEY `verified_members_identity_id_fk`,
+    DROP COLUMN `identity_id`;
+
+DROP TABLE `identities`;
+
+ALTER TABLE `guilds`
+    DROP FOREIGN KEY `guilds_trust_group_id_fk`,
+    DROP COLUMN `trust_group_id`;
+
+DROP TABLE `trust_groups`;
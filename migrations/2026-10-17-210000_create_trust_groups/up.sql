@@ -0,0 +1,46 @@
+-- Guilds that reuse the verifications of each other, joined with the invite code.
+CREATE TABLE `trust_groups`
+(
+    `id`          INTEGER      NOT NULL AUTO_INCREMENT,
+
+    `name`        VARCHAR(100) NOT NULL,
+    `invite_code` VARCHAR(32)  NOT NULL,
+
+    PRIMARY KEY (`id`),
+
+    UNIQUE (`invite_code`)
+);
+
+ALTER TABLE `guilds`
+    ADD COLUMN `trust_group_id` INTEGER NULL AFTER `offline_access`,
+    ADD CONSTRAINT `guilds_trust_group_id_fk` FOREIGN KEY (`trust_group_id`) REFERENCES `trust_groups` (`id`)
+        ON DELETE SET NULL ON UPDATE CASCADE;
+
+-- An account proven once by a user, reusable in every guild of the trust group.
+CREATE TABLE `identities`
+(
+    `id`                INTEGER         NOT NULL AUTO_INCREMENT,
+
+    `trust_group_id`    INTEGER         NOT NULL,
+    `discord_id`        BIGINT UNSIGNED NOT NULL,
+    `mail`              VARCHAR(255)    NOT NULL,
+    `first_name`        TEXT            NOT NULL,
+    `last_name`         TEXT            NOT NULL,
+    -- The domain the provider vouched for, the email rules of each guild are checked against it.
+    `domain`            VARCHAR(255)    NULL,
+    `provider`          VARCHAR(32)     NOT NULL,
+    `verified_at`       DATETIME        NOT NULL,
+
+    PRIMARY KEY (`id`),
+
+    UNIQUE (`trust_group_id`, `discord_id`),
+
+    FOREIGN KEY (`trust_group_id`) REFERENCES `trust_groups` (`id`)
+        ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- The identity the verification comes from, kept when the identity is deleted.
+ALTER TABLE `verified_members`
+    ADD COLUMN `identity_id` INTEGER NULL AFTER `token_provider`,
+    ADD CONSTRAINT `verified_members_identity_id_fk` FOREIGN KEY (`identity_id`) REFERENCES `identities` (`id`)
+        ON DELETE SET NULL ON UPDATE CASCADE;
@@ -0,0 +1,4 @@
+DROP TABLE `mail_claims`;
+
+ALTER TABLE `guilds`
+    DROP COLUMN `duplicate_mail_policy`;
@@ -0,0 +1,20 @@
+-- What happens when another account verifies itself with a mail already verified in the guild.
+ALTER TABLE `guilds`
+    ADD COLUMN `duplicate_mail_policy` VARCHAR(16) NOT NULL DEFAULT 'allow' AFTER `trust_group_id`;
+
+-- The mails verified in the guilds that block duplicates, a mail is claimed by one member at most.
+-- A claim of a member that is not verified anymore is taken over by the next member verifying the mail.
+CREATE TABLE `mail_claims`
+(
+    `guild_id`  BIGINT UNSIGNED NOT NULL,
+    -- Stored in lower case.
+    `mail`      VARCHAR(255)    NOT NULL,
+    `member_id` INTEGER         NOT NULL,
+
+    PRIMARY KEY (`guild_id`, `mail`),
+
+    FOREIGN KEY (`guild_id`) REFERENCES `guilds` (`id`)
+        ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (`member_id`) REFERENCES `members` (`id`)
+        ON DELETE CASCADE ON UPDATE CASCADE
+);
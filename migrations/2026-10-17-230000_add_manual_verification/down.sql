@@ -0,0 +1,2 @@
+ALTER TABLE `verified_members`
+    DROP COLUMN `verified_by`;
@@ -0,0 +1,4 @@
+-- The admin who verified the member by hand, null when the member verified themselves.
+-- Manual verifications have no mail until the member verifies themselves with their account.
+ALTER TABLE `verified_members`
+    ADD COLUMN `verified_by` BIGINT UNSIGNED NULL AFTER `identity_id`;
@@ -0,0 +1,4 @@
+DROP TABLE `class_change_requests`;
+
+ALTER TABLE `guilds`
+    DROP COLUMN `class_change_approval`;
@@ -0,0 +1,17 @@
+-- Whether the students changing their class wait for the approval of an admin.
+ALTER TABLE `guilds`
+    ADD COLUMN `class_change_approval` BOOL NOT NULL DEFAULT FALSE AFTER `duplicate_mail_policy`;
+
+-- The class changes waiting for the approval of an admin, one per member at most.
+-- A new request of the member replaces the previous one.
+CREATE TABLE `class_change_requests`
+(
+    `member_id`    INTEGER  NOT NULL PRIMARY KEY,
+    `class_id`     INTEGER  NOT NULL,
+    `requested_at` DATETIME NOT NULL,
+
+    FOREIGN KEY (`member_id`) REFERENCES `members` (`id`)
+        ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (`class_id`) REFERENCES `classes` (`id`)
+        ON DELETE CASCADE ON UPDATE CASCADE
+);
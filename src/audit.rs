@@ -0,0 +1,295 @@
+//! Record what happens in the guilds, in the database and in their log channel
+//!
+//! Recording never fails the action it describes, errors are only logged
+
+use crate::{
+	database::{
+		models::{Guild, NewAuditEvent},
+		prelude::*,
+		schema::guilds,
+	},
+	states::Data,
+	translation::Translate,
+};
+use anyhow::anyhow;
+use chrono::{TimeDelta, Utc};
+use poise::serenity_prelude::{ChannelId, CreateEmbed, CreateMessage, GuildId, Timestamp, UserId};
+use std::{fmt, str::FromStr};
+
+/// What happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AuditKind {
+	/// A member verified themselves
+	Login,
+	/// A member disconnected their accounts
+	Logout,
+	/// An owner forced a member to log out
+	ForcedLogout,
+	/// A member joined a group
+	GroupJoined,
+	/// A member left a group
+	GroupLeft,
+	/// An admin changed the configuration of the bot
+	ConfigChanged,
+	/// A role used by the bot was deleted
+	RoleDeleted,
+	/// A verified member lost the verified role by hand
+	VerifiedRoleRemoved,
+	/// A verified member verified themselves again before their verification expired
+	Reverified,
+	/// A verified member did not verify themselves again in time
+	VerificationExpired,
+	/// The account of a verified member was deleted or suspended by its organization
+	AccountDeactivated,
+	/// The verification of a member was deleted with the roles and groups it gave
+	DataDeleted,
+	/// A user erased everything stored about them in every guild
+	DataErased,
+	/// A member reused the verification of another guild of the trust group
+	VerificationShared,
+	/// A member verified themselves with the mail of other accounts
+	DuplicateMail,
+	/// An admin verified a member by hand
+	ManualVerification,
+	/// An owner forced the verification of a member
+	ForcedLogin,
+	/// A verified student was moved to another class
+	ClassChanged,
+}
+
+impl AuditKind {
+	/// The name stored in the database
+	pub(crate) const fn as_str(self) -> &'static str {
+		match self {
+			Self::Login => "login",
+			Self::Logout => "logout",
+			Self::ForcedLogout => "forced_logout",
+			Self::GroupJoined => "group_joined",
+			Self::GroupLeft => "group_left",
+			Self::ConfigChanged => "config_changed",
+			Self::RoleDeleted => "role_deleted",
+			Self::VerifiedRoleRemoved => "verified_role_removed",
+			Self::Reverified => "reverified",
+			Self::VerificationExpired => "verification_expired",
+			Self::AccountDeactivated => "account_deactivated",
+			Self::DataDeleted => "data_deleted",
+			Self::DataErased => "data_erased",
+			Self::VerificationShared => "verification_shared",
+			Self::DuplicateMail => "duplicate_mail",
+			Self::ManualVerification => "manual_verification",
+			Self::ForcedLogin => "forced_login",
+			Self::ClassChanged => "class_changed",
+		}
+	}
+
+	/// The translation key of the kind name
+	pub(crate) const fn translation_key(self) -> &'static str {
+		match self {
+			Self::Login => "audit-login",
+			Self::Logout => "audit-logout",
+			Self::ForcedLogout => "audit-forced-logout",
+			Self::GroupJoined => "audit-group-joined",
+			Self::GroupLeft => "audit-group-left",
+			Self::ConfigChanged => "audit-config-changed",
+			Self::RoleDeleted => "audit-role-deleted",
+			Self::VerifiedRoleRemoved => "audit-verified-role-removed",
+			Self::Reverified => "audit-reverified",
+			Self::VerificationExpired => "audit-verification-expired",
+			Self::AccountDeactivated => "audit-account-deactivated",
+			Self::DataDeleted => "audit-data-deleted",
+			Self::DataErased => "audit-data-erased",
+			Self::VerificationShared => "audit-verification-shared",
+			Self::DuplicateMail => "audit-duplicate-mail",
+			Self::ManualVerification => "audit-manual-verification",
+			Self::ForcedLogin => "audit-forced-login",
+			Self::ClassChanged => "audit-class-changed",
+		}
+	}
+
+	/// The color of the embeds posted in the log channel
+	const fn color(self) -> u32 {
+		match self {
+			Self::Login | Self::GroupJoined | Self::Reverified | Self::VerificationShared => {
+				0x0000_FF00
+			}
+			Self::Logout
+			| Self::GroupLeft
+			| Self::VerificationExpired
+			| Self::DataDeleted
+			| Self::DataErased => 0x00FF_A500,
+			Self::ForcedLogout
+			| Self::RoleDeleted
+			| Self::VerifiedRoleRemoved
+			| Self::AccountDeactivated
+			| Self::DuplicateMail => 0x00FF_0000,
+			Self::ConfigChanged
+			| Self::ManualVerification
+			| Self::ForcedLogin
+			| Self::ClassChanged => 0x0000_00FF,
+		}
+	}
+}
+
+impl fmt::Display for AuditKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+impl FromStr for AuditKind {
+	type Err = anyhow::Error;
+
+	fn from_str(kind: &str) -> Result<Self, Self::Err> {
+		match kind {
+			"login" => Ok(Self::Login),
+			"logout" => Ok(Self::Logout),
+			"forced_logout" => Ok(Self::ForcedLogout),
+			"group_joined" => Ok(Self::GroupJoined),
+			"group_left" => Ok(Self::GroupLeft),
+			"config_changed" => Ok(Self::ConfigChanged),
+			"role_deleted" => Ok(Self::RoleDeleted),
+			"verified_role_removed" => Ok(Self::VerifiedRoleRemoved),
+			"reverified" => Ok(Self::Reverified),
+			"verification_expired" => Ok(Self::VerificationExpired),
+			"account_deactivated" => Ok(Self::AccountDeactivated),
+			"data_deleted" => Ok(Self::DataDeleted),
+			"data_erased" => Ok(Self::DataErased),
+			"verification_shared" => Ok(Self::VerificationShared),
+			"duplicate_mail" => Ok(Self::DuplicateMail),
+			"manual_verification" => Ok(Self::ManualVerification),
+			"forced_login" => Ok(Self::ForcedLogin),
+			"class_changed" => Ok(Self::ClassChanged),
+			_ => Err(anyhow!("unknown audit kind `{kind}`")),
+		}
+	}
+}
+
+/// An event to record
+#[derive(Debug)]
+pub(crate) struct AuditEntry {
+	/// What happened
+	kind: AuditKind,
+	/// Who did it, the bot itself when not set
+	actor_id: Option<UserId>,
+	/// Who it was done to
+	target_id: Option<UserId>,
+	/// Free text about the event
+	details: Option<String>,
+}
+
+impl AuditEntry {
+	/// Start an entry of the given kind
+	pub(crate) const fn new(kind: AuditKind) -> Self {
+		Self {
+			kind,
+			actor_id: None,
+			target_id: None,
+			details: None,
+		}
+	}
+
+	/// Set who did it
+	pub(crate) const fn actor(mut self, user_id: UserId) -> Self {
+		self.actor_id = Some(user_id);
+		self
+	}
+
+	/// Set who it was done to
+	pub(crate) const fn target(mut self, user_id: UserId) -> Self {
+		self.target_id = Some(user_id);
+		self
+	}
+
+	/// Set what else there is to know
+	pub(crate) fn details(mut self, details: impl Into<String>) -> Self {
+		self.details = Some(details.into());
+		self
+	}
+
+	/// The embed posted in the log channel
+	fn embed(&self, data: &Data) -> CreateEmbed {
+		let mut embed = CreateEmbed::new()
+			.title(data.translate(self.kind.translation_key(), None))
+			.color(self.kind.color())
+			.timestamp(Timestamp::now());
+
+		if let Some(actor_id) = self.actor_id {
+			embed = embed.field(
+				data.translate("audit-actor", None),
+				format!("<@{actor_id}>"),
+				true,
+			);
+		}
+		if let Some(target_id) = self.target_id {
+			embed = embed.field(
+				data.translate("audit-target", None),
+				format!("<@{target_id}>"),
+				true,
+			);
+		}
+		if let Some(details) = &self.details {
+			embed = embed.description(details);
+		}
+
+		embed
+	}
+}
+
+/// Save the event and post it in the log channel of the guild, if any
+pub(crate) async fn record(data: &Data, guild_id: GuildId, entry: AuditEntry) {
+	if let Err(error) = try_record(data, guild_id, &entry).await {
+		tracing::error!(
+			guild_id = guild_id.get(),
+			entry = ?entry,
+			error = ?error,
+			"could not record the audit event"
+		);
+	}
+}
+
+/// Save the event and post it in the log channel of the guild, if any
+async fn try_record(data: &Data, guild_id: GuildId, entry: &AuditEntry) -> anyhow::Result<()> {
+	let mut connection = data.database.get().await?;
+
+	NewAuditEvent {
+		guild_id: guild_id.get(),
+		kind: entry.kind.as_str(),
+		actor_id: entry.actor_id.map(UserId::get),
+		target_id: entry.target_id.map(UserId::get),
+		details: entry.details.as_deref(),
+		created_at: Utc::now().naive_utc(),
+	}
+	.insert()
+	.execute(&mut connection)
+	.await?;
+
+	let Some(channel_id) = Guild::with_id(guild_id)
+		.select(guilds::log_channel_id)
+		.first::<Option<u64>>(&mut connection)
+		.await?
+	else {
+		return Ok(());
+	};
+
+	ChannelId::new(channel_id)
+		.send_message(&data.http, CreateMessage::new().embed(entry.embed(data)))
+		.await?;
+
+	Ok(())
+}
+
+/// Parse a period like `30m`, `12h`, `7d` or `2w`
+pub(crate) fn parse_period(period: &str) -> Option<TimeDelta> {
+	let period = period.trim();
+	let unit_index = period.len().checked_sub(1)?;
+	let amount = period.get(..unit_index)?.parse::<i64>().ok()?;
+
+	match period.get(unit_index..)? {
+		"m" => TimeDelta::try_minutes(amount),
+		"h" => TimeDelta::try_hours(amount),
+		"d" => TimeDelta::try_days(amount),
+		"w" => TimeDelta::try_weeks(amount),
+		_ => None,
+	}
+	.filter(|period| *period > TimeDelta::zero())
+}
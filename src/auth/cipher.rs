@@ -0,0 +1,79 @@
+//! Encrypt the refresh tokens before they are stored in the database
+
+use anyhow::{anyhow, Context as _};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use ring::{
+	aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+	rand::{SecureRandom, SystemRandom},
+};
+use secrecy::SecretString;
+use std::fmt;
+
+/// Seals and opens tokens with `AES-256-GCM`, the random nonce is stored before the ciphertext
+pub(crate) struct TokenCipher {
+	/// The key given in the config
+	key: LessSafeKey,
+	/// Generates the nonces
+	random: SystemRandom,
+}
+
+impl fmt::Debug for TokenCipher {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("TokenCipher").finish_non_exhaustive()
+	}
+}
+
+impl TokenCipher {
+	/// Create a new [`TokenCipher`] from a base64 encoded 32 bytes key
+	pub(crate) fn new(key: &str) -> anyhow::Result<Self> {
+		let key = STANDARD
+			.decode(key)
+			.context("the token encryption key must be base64 encoded")?;
+		let key = UnboundKey::new(&AES_256_GCM, &key)
+			.map_err(|_| anyhow!("the token encryption key must be 32 bytes long"))?;
+
+		Ok(Self {
+			key: LessSafeKey::new(key),
+			random: SystemRandom::new(),
+		})
+	}
+
+	/// Encrypt a token
+	pub(crate) fn seal(&self, token: &str) -> anyhow::Result<Vec<u8>> {
+		let mut nonce = [0; NONCE_LEN];
+		self.random
+			.fill(&mut nonce)
+			.map_err(|_| anyhow!("could not generate a nonce"))?;
+
+		let mut sealed = token.as_bytes().to_vec();
+		self.key
+			.seal_in_place_append_tag(
+				Nonce::assume_unique_for_key(nonce),
+				Aad::empty(),
+				&mut sealed,
+			)
+			.map_err(|_| anyhow!("could not encrypt the token"))?;
+
+		Ok([nonce.as_slice(), &sealed].concat())
+	}
+
+	/// Decrypt a token sealed with [`TokenCipher::seal`]
+	pub(crate) fn open(&self, sealed: &[u8]) -> anyhow::Result<SecretString> {
+		if sealed.len() < NONCE_LEN {
+			return Err(anyhow!("the sealed token is too short"));
+		}
+		let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+		let nonce = Nonce::try_assume_unique_for_key(nonce)
+			.map_err(|_| anyhow!("the sealed token nonce is invalid"))?;
+
+		let mut ciphertext = ciphertext.to_vec();
+		let token = self
+			.key
+			.open_in_place(nonce, Aad::empty(), &mut ciphertext)
+			.map_err(|_| anyhow!("could not decrypt the token, was the key changed?"))?;
+
+		Ok(SecretString::from(
+			String::from_utf8(token.to_vec()).context("the decrypted token is not UTF-8")?,
+		))
+	}
+}
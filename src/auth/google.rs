@@ -0,0 +1,155 @@
+//! `Google` identity provider, user metadata is read from the signed ID token
+
+use super::{
+	http_client,
+	oidc::{refresh_outcome, IdTokenValidator, OidcClient},
+	AuthentificationError, AuthorizationRequest, IdentityProvider, RefreshOutcome, UserMetadata,
+};
+use crate::constants::{scopes, urls};
+use anyhow::{anyhow, Context as _};
+use oauth2::{
+	AuthUrl, AuthorizationCode, ClientId, ClientSecret, CsrfToken, EndpointSet, RedirectUrl,
+	RefreshToken, RevocationUrl, Scope, StandardRevocableToken, TokenResponse, TokenUrl,
+};
+use poise::async_trait;
+use reqwest::Client;
+use secrecy::{ExposeSecret, SecretString};
+
+/// A manager to get redirect urls and tokens
+#[derive(Debug)]
+pub(crate) struct GoogleProvider {
+	/// The inner client used to manage the flow
+	pub(crate) client: OidcClient<EndpointSet>,
+	/// Validates the ID tokens returned by `Google`
+	validator: IdTokenValidator,
+}
+
+impl GoogleProvider {
+	/// Create a new [`GoogleProvider`]
+	pub(crate) fn new(
+		(client_id, client_secret): (ClientId, ClientSecret),
+		redirect_url: RedirectUrl,
+	) -> anyhow::Result<Self> {
+		let auth_url = AuthUrl::new(urls::GOOGLE_AUTH_ENDPOINT.into())?;
+		let token_url = TokenUrl::new(urls::GOOGLE_TOKEN_ENDPOINT.into())?;
+		let revocation_url = RevocationUrl::new(urls::GOOGLE_REVOKE_ENDPOINT.into())?;
+
+		let validator = IdTokenValidator::new(
+			urls::GOOGLE_ISSUERS.map(ToOwned::to_owned).to_vec(),
+			client_id.to_string(),
+			urls::GOOGLE_JWKS_ENDPOINT.into(),
+			Client::default(),
+		);
+
+		let oauth_client = oauth2::Client::new(client_id)
+			.set_client_secret(client_secret)
+			.set_auth_uri(auth_url)
+			.set_token_uri(token_url)
+			.set_redirect_uri(redirect_url)
+			.set_revocation_url(revocation_url);
+
+		Ok(Self {
+			client: oauth_client,
+			validator,
+		})
+	}
+}
+
+#[async_trait]
+impl IdentityProvider for GoogleProvider {
+	async fn authorize_url(
+		&self,
+		offline_access: bool,
+	) -> Result<AuthorizationRequest, AuthentificationError> {
+		let nonce = CsrfToken::new_random();
+		let mut request = self
+			.client
+			.authorize_url(CsrfToken::new_random)
+			.add_scopes([
+				Scope::new(scopes::OPENID.into()),
+				Scope::new(scopes::EMAIL.into()),
+				Scope::new(scopes::PROFILE.into()),
+			])
+			.add_extra_param("nonce", nonce.secret());
+		// `Google` only gives a refresh token on the first consent unless asked again
+		if offline_access {
+			request = request
+				.add_extra_param("access_type", "offline")
+				.add_extra_param("prompt", "consent");
+		}
+		let (url, csrf_state) = request.url();
+
+		Ok(AuthorizationRequest {
+			url,
+			csrf_state,
+			nonce,
+		})
+	}
+
+	async fn user_metadata(
+		&self,
+		code: String,
+		nonce: &str,
+	) -> Result<UserMetadata, AuthentificationError> {
+		let token_response = self
+			.client
+			.exchange_code(AuthorizationCode::new(code))
+			.request_async(&http_client()?)
+			.await
+			.context("could not get oauth2 token")?;
+
+		let claims = self
+			.validator
+			.validate(&token_response.extra_fields().id_token, nonce)
+			.await?;
+
+		// `Google` accounts can be created with any address, only trust verified ones
+		if claims.email_verified != Some(true) {
+			return Err(anyhow!("google did not verify the user email").into());
+		}
+
+		Ok(UserMetadata {
+			mail: claims
+				.email
+				.context("the ID token does not contain an email")?,
+			first_name: claims
+				.given_name
+				.context("the ID token does not contain a first name")?,
+			last_name: claims
+				.family_name
+				.context("the ID token does not contain a last name")?,
+			// Only `Google Workspace` accounts are vouched for by their domain
+			domain: claims.hd,
+			refresh_token: token_response
+				.refresh_token()
+				.map(|token| SecretString::from(token.secret().clone())),
+		})
+	}
+
+	async fn refresh(
+		&self,
+		refresh_token: &SecretString,
+	) -> Result<RefreshOutcome, AuthentificationError> {
+		let refresh_token = RefreshToken::new(refresh_token.expose_secret().to_owned());
+		let result = self
+			.client
+			.exchange_refresh_token(&refresh_token)
+			.request_async(&http_client()?)
+			.await;
+
+		refresh_outcome(result)
+	}
+
+	async fn revoke(&self, refresh_token: SecretString) -> Result<(), AuthentificationError> {
+		self.client
+			.revoke_token(StandardRevocableToken::RefreshToken(RefreshToken::new(
+				refresh_token.expose_secret().to_owned(),
+			)))
+			.context("the revocation url is not set")?
+			.request_async(&http_client()?)
+			.await
+			.context("could not revoke the oauth2 token")?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,161 @@
+//! `OAuth2` flow with users, through one of the configured identity providers
+
+use crate::{constants, states::Config};
+use anyhow::{anyhow, Context as _};
+use oauth2::{url::Url, CsrfToken, RedirectUrl};
+use poise::async_trait;
+use secrecy::SecretString;
+use std::{collections::HashMap, fmt};
+use thiserror::Error;
+
+mod cipher;
+mod google;
+mod oidc;
+
+pub(crate) use cipher::TokenCipher;
+pub(crate) use google::GoogleProvider;
+pub(crate) use oidc::OidcProvider;
+
+/// The information returned by an identity provider
+pub(crate) struct UserMetadata {
+	/// The user's mail
+	pub(crate) mail: String,
+	/// The user's first name
+	pub(crate) first_name: String,
+	/// The user's last name
+	pub(crate) last_name: String,
+	/// The domain the provider vouches the user belongs to, from signed claims
+	///
+	/// `None` if the provider does not vouch for any, e.g. `Google` consumer accounts
+	pub(crate) domain: Option<String>,
+	/// The token to check later that the account still exists, only given with offline access
+	pub(crate) refresh_token: Option<SecretString>,
+}
+
+/// A url to send to the user with the values needed to recognize the callback
+pub(crate) struct AuthorizationRequest {
+	/// The url of the provider consent page
+	pub(crate) url: Url,
+	/// The code to recognize the request
+	pub(crate) csrf_state: CsrfToken,
+	/// The value the provider must put back in the ID token
+	pub(crate) nonce: CsrfToken,
+}
+
+/// What the provider answered to a refresh
+pub(crate) enum RefreshOutcome {
+	/// The account still exists, the provider may have rotated the refresh token
+	Valid(Option<SecretString>),
+	/// The grant was revoked, e.g. the account was deleted or suspended
+	Revoked,
+}
+
+/// A provider able to authenticate a user and give us his [`UserMetadata`]
+#[async_trait]
+pub(crate) trait IdentityProvider: fmt::Debug + Send + Sync {
+	/// Gets a url to send to the user and the values to persist to finish the flow
+	///
+	/// With `offline_access`, the provider is asked for a refresh token
+	async fn authorize_url(
+		&self,
+		offline_access: bool,
+	) -> Result<AuthorizationRequest, AuthentificationError>;
+
+	/// Exchange the code returned in the callback and query the user's metadata
+	async fn user_metadata(
+		&self,
+		code: String,
+		nonce: &str,
+	) -> Result<UserMetadata, AuthentificationError>;
+
+	/// Use a refresh token to check that the account still exists
+	async fn refresh(
+		&self,
+		refresh_token: &SecretString,
+	) -> Result<RefreshOutcome, AuthentificationError>;
+
+	/// Revoke a refresh token, providers without a revocation endpoint let it expire
+	async fn revoke(&self, _refresh_token: SecretString) -> Result<(), AuthentificationError> {
+		Ok(())
+	}
+}
+
+/// Build a HTTP client to exchange `OAuth2` codes
+fn http_client() -> anyhow::Result<reqwest::Client> {
+	reqwest::ClientBuilder::new()
+		// Following redirects opens the client up to SSRF vulnerabilities.
+		.redirect(reqwest::redirect::Policy::none())
+		.build()
+		.context("could not build http client")
+}
+
+/// The identity providers guilds can choose from
+#[derive(Debug)]
+pub(crate) struct IdentityProviders {
+	/// Providers by the name guilds use to select them
+	providers: HashMap<String, Box<dyn IdentityProvider>>,
+}
+
+impl IdentityProviders {
+	/// Create the `Google` provider and the `OpenID Connect` providers listed in the config
+	pub(crate) fn new(config: &Config) -> anyhow::Result<Self> {
+		let redirect_url = RedirectUrl::new(format!("https://{}/oauth2", config.server_url))?;
+
+		let mut providers = HashMap::<String, Box<dyn IdentityProvider>>::new();
+
+		providers.insert(
+			constants::DEFAULT_IDENTITY_PROVIDER.into(),
+			Box::new(GoogleProvider::new(
+				config.google_client.clone(),
+				redirect_url.clone(),
+			)?),
+		);
+
+		for provider in &config.oidc_providers {
+			if providers.contains_key(&provider.name) {
+				return Err(anyhow!(
+					"the identity provider `{}` is defined twice",
+					provider.name
+				));
+			}
+
+			providers.insert(
+				provider.name.clone(),
+				Box::new(OidcProvider::new(provider, redirect_url.clone())),
+			);
+		}
+
+		Ok(Self { providers })
+	}
+
+	/// Get a provider from its name
+	pub(crate) fn get(&self, name: &str) -> Option<&dyn IdentityProvider> {
+		self.providers.get(name).map(AsRef::as_ref)
+	}
+
+	/// The names of the available providers
+	pub(crate) fn names(&self) -> impl Iterator<Item = &str> {
+		self.providers.keys().map(String::as_str)
+	}
+}
+
+/// Errors that can happen during the authentification process
+#[derive(Error, Debug)]
+pub(crate) enum AuthentificationError {
+	/// An error while fetching the provider
+	#[error("Could not fetch the provider API: {0}")]
+	Fetch(reqwest::Error),
+	/// An error while fetching the provider
+	#[error("The provider answered with a non Ok status code")]
+	NonOkResponse,
+	/// The API response from the provider does not contain required data
+	#[error("The returned response could not be parsed")]
+	MalformedResponse(serde_json::Error),
+	/// The ID token could not be verified
+	#[error("The ID token is invalid: {0}")]
+	InvalidIdToken(jsonwebtoken::errors::Error),
+
+	/// Other miscellaneous errors
+	#[error(transparent)]
+	Other(#[from] anyhow::Error),
+}
@@ -0,0 +1,414 @@
+//! Generic `OpenID Connect` identity provider, configured through discovery
+//!
+//! See <https://openid.net/specs/openid-connect-discovery-1_0.html>
+
+use super::{
+	http_client, AuthentificationError, AuthorizationRequest, IdentityProvider, RefreshOutcome,
+	UserMetadata,
+};
+use crate::{constants::scopes, states::OidcProviderConfig};
+use anyhow::{anyhow, Context as _};
+use jsonwebtoken::{
+	jwk::{Jwk, JwkSet},
+	Algorithm, DecodingKey, Validation,
+};
+use oauth2::{
+	basic::{
+		BasicErrorResponse, BasicErrorResponseType, BasicRevocationErrorResponse,
+		BasicTokenIntrospectionResponse, BasicTokenType,
+	},
+	AuthUrl, AuthorizationCode, ClientId, ClientSecret, CsrfToken, EndpointNotSet, EndpointSet,
+	ExtraTokenFields, HttpClientError, RedirectUrl, RefreshToken, RequestTokenError, Scope,
+	StandardRevocableToken, StandardTokenResponse, TokenResponse, TokenUrl,
+};
+use poise::async_trait;
+use reqwest::{Client, StatusCode};
+use secrecy::{ExposeSecret, SecretString};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::str::FromStr;
+use tokio::sync::{OnceCell, RwLock};
+
+/// The extra fields returned by an `OpenID Connect` token endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct IdTokenFields {
+	/// The signed `JWT` that identifies the user
+	///
+	/// Some providers leave it out of refresh responses, it is then empty and fails validation
+	#[serde(default)]
+	pub(crate) id_token: String,
+}
+
+impl ExtraTokenFields for IdTokenFields {}
+
+/// The type of an `OpenID Connect` token response
+pub(crate) type OidcTokenResponse = StandardTokenResponse<IdTokenFields, BasicTokenType>;
+
+/// An `OAuth2` client that expects an ID token in the token response
+pub(crate) type OidcClient<HasRevocationUrl = EndpointNotSet> = oauth2::Client<
+	BasicErrorResponse,
+	OidcTokenResponse,
+	BasicTokenIntrospectionResponse,
+	StandardRevocableToken,
+	BasicRevocationErrorResponse,
+	EndpointSet,
+	EndpointNotSet,
+	EndpointNotSet,
+	HasRevocationUrl,
+	EndpointSet,
+>;
+
+/// The error of a token request made with [`http_client`]
+pub(crate) type TokenRequestError =
+	RequestTokenError<HttpClientError<reqwest::Error>, BasicErrorResponse>;
+
+/// Turn the answer to a refresh into a [`RefreshOutcome`], an `invalid_grant` error means the grant was revoked
+pub(crate) fn refresh_outcome(
+	result: Result<OidcTokenResponse, TokenRequestError>,
+) -> Result<RefreshOutcome, AuthentificationError> {
+	match result {
+		Ok(response) => Ok(RefreshOutcome::Valid(
+			response
+				.refresh_token()
+				.map(|token| SecretString::from(token.secret().clone())),
+		)),
+		Err(RequestTokenError::ServerResponse(response))
+			if *response.error() == BasicErrorResponseType::InvalidGrant =>
+		{
+			Ok(RefreshOutcome::Revoked)
+		}
+		Err(error) => Err(anyhow::Error::new(error)
+			.context("could not refresh the oauth2 token")
+			.into()),
+	}
+}
+
+/// The part of the provider metadata we use
+///
+/// See <https://openid.net/specs/openid-connect-discovery-1_0.html#ProviderMetadata>
+#[derive(Debug, Deserialize)]
+struct ProviderMetadata {
+	/// Must be the same as the configured issuer
+	issuer: String,
+	/// Where to redirect the user
+	authorization_endpoint: String,
+	/// Where to exchange the code
+	token_endpoint: String,
+	/// Where to get the keys that sign ID tokens
+	jwks_uri: String,
+}
+
+/// The standard claims of an ID token we use
+///
+/// See <https://openid.net/specs/openid-connect-core-1_0.html#StandardClaims>
+#[derive(Debug, Deserialize)]
+pub(crate) struct IdTokenClaims {
+	/// The user's mail
+	pub(crate) email: Option<String>,
+	/// Whether the provider checked that the user owns the mail
+	pub(crate) email_verified: Option<bool>,
+	/// The user's first name
+	pub(crate) given_name: Option<String>,
+	/// The user's last name
+	pub(crate) family_name: Option<String>,
+	/// The value sent in the authorization request, to prevent replay attacks
+	pub(crate) nonce: Option<String>,
+	/// The `Google Workspace` domain of the user, absent for consumer accounts
+	pub(crate) hd: Option<String>,
+}
+
+/// Validates ID tokens with the keys published by a provider
+#[derive(Debug)]
+pub(crate) struct IdTokenValidator {
+	/// The accepted `iss` claims
+	issuers: Vec<String>,
+	/// The expected `aud` claim, our client id
+	audience: String,
+	/// Where to fetch the keys
+	jwks_uri: String,
+	/// The last fetched keys
+	keys: RwLock<JwkSet>,
+	/// A Reqwest HTTPS client to fetch the keys
+	http: Client,
+}
+
+impl IdTokenValidator {
+	/// Create a new [`IdTokenValidator`], keys are fetched on first use
+	pub(crate) fn new(
+		issuers: Vec<String>,
+		audience: String,
+		jwks_uri: String,
+		http: Client,
+	) -> Self {
+		Self {
+			issuers,
+			audience,
+			jwks_uri,
+			keys: RwLock::new(JwkSet { keys: Vec::new() }),
+			http,
+		}
+	}
+
+	/// Get the key with the given id and its algorithm, fetching the keys again if it is unknown
+	///
+	/// Providers rotate their keys, so an unknown key id is expected from time to time
+	async fn decoding_key(
+		&self,
+		kid: &str,
+	) -> Result<(DecodingKey, Algorithm), AuthentificationError> {
+		if let Some(jwk) = self.keys.read().await.find(kid) {
+			return key_with_algorithm(jwk);
+		}
+
+		let keys = fetch_json::<JwkSet>(&self.http, &self.jwks_uri).await?;
+
+		let key = keys
+			.find(kid)
+			.ok_or_else(|| anyhow!("no key with id `{kid}` in the provider key set"))
+			.map(key_with_algorithm);
+
+		*self.keys.write().await = keys;
+
+		key?
+	}
+
+	/// Check the signature, audience, issuer, expiry and nonce of an ID token and return its claims
+	///
+	/// Provider specific claims like `email_verified` or `hd` must be checked by the caller
+	pub(crate) async fn validate(
+		&self,
+		id_token: &str,
+		nonce: &str,
+	) -> Result<IdTokenClaims, AuthentificationError> {
+		let header =
+			jsonwebtoken::decode_header(id_token).map_err(AuthentificationError::InvalidIdToken)?;
+		let kid = header
+			.kid
+			.context("the ID token header does not contain a key id")?;
+
+		let (key, algorithm) = self.decoding_key(&kid).await?;
+
+		// The algorithm of the header is chosen by whoever made the token, the one of the key is not
+		let mut validation = Validation::new(algorithm);
+		validation.set_audience(&[&self.audience]);
+		validation.set_issuer(&self.issuers);
+		validation.set_required_spec_claims(&["exp", "aud", "iss"]);
+
+		let claims = jsonwebtoken::decode::<IdTokenClaims>(id_token, &key, &validation)
+			.map_err(AuthentificationError::InvalidIdToken)?
+			.claims;
+
+		if claims.nonce.as_deref() != Some(nonce) {
+			return Err(anyhow!("the ID token nonce does not match the request").into());
+		}
+
+		Ok(claims)
+	}
+}
+
+/// The decoding key of a `JWK` and the algorithm it signs with, `RS256` when the key does not say
+fn key_with_algorithm(jwk: &Jwk) -> Result<(DecodingKey, Algorithm), AuthentificationError> {
+	let algorithm = match jwk.common.key_algorithm {
+		Some(key_algorithm) => Algorithm::from_str(&key_algorithm.to_string())
+			.map_err(AuthentificationError::InvalidIdToken)?,
+		None => Algorithm::RS256,
+	};
+
+	let key = DecodingKey::from_jwk(jwk).map_err(AuthentificationError::InvalidIdToken)?;
+
+	Ok((key, algorithm))
+}
+
+/// Fetch and parse a JSON document
+async fn fetch_json<T: DeserializeOwned>(
+	http: &Client,
+	url: &str,
+) -> Result<T, AuthentificationError> {
+	let response = http
+		.get(url)
+		.send()
+		.await
+		.map_err(AuthentificationError::Fetch)?;
+
+	if response.status() != StatusCode::OK {
+		return Err(AuthentificationError::NonOkResponse);
+	}
+
+	let body = response
+		.bytes()
+		.await
+		.context("could not get response bytes")?;
+
+	serde_json::from_slice::<T>(&body).map_err(AuthentificationError::MalformedResponse)
+}
+
+/// What we learn from the discovery document
+#[derive(Debug)]
+struct Discovered {
+	/// The client built with the discovered endpoints
+	client: OidcClient,
+	/// The validator built with the discovered issuer and keys
+	validator: IdTokenValidator,
+}
+
+/// A provider like `Microsoft Entra ID` or `Keycloak`
+#[derive(Debug)]
+pub(crate) struct OidcProvider {
+	/// The issuer url, used to find the discovery document
+	issuer_url: String,
+	/// The `OAuth2` client id and secret pair
+	client: (ClientId, ClientSecret),
+	/// The url of our `OAuth2` callback
+	redirect_url: RedirectUrl,
+	/// The discovery is done on first use, so that an unreachable provider does not prevent startup
+	discovered: OnceCell<Discovered>,
+	/// Whether to accept mails the provider does not flag as verified, see [`OidcProviderConfig`]
+	trust_unverified_email: bool,
+	/// A Reqwest HTTPS client to query the provider
+	http: Client,
+}
+
+impl OidcProvider {
+	/// Create a new [`OidcProvider`]
+	pub(crate) fn new(config: &OidcProviderConfig, redirect_url: RedirectUrl) -> Self {
+		Self {
+			issuer_url: config.issuer_url.trim_end_matches('/').to_owned(),
+			client: config.client.clone(),
+			redirect_url,
+			discovered: OnceCell::new(),
+			trust_unverified_email: config.trust_unverified_email,
+			http: Client::default(),
+		}
+	}
+
+	/// Get the discovered endpoints, fetching the discovery document if needed
+	async fn discovered(&self) -> Result<&Discovered, AuthentificationError> {
+		self.discovered
+			.get_or_try_init(|| async {
+				let metadata = fetch_json::<ProviderMetadata>(
+					&self.http,
+					&format!("{}/.well-known/openid-configuration", self.issuer_url),
+				)
+				.await?;
+
+				if metadata.issuer.trim_end_matches('/') != self.issuer_url {
+					return Err(anyhow!(
+						"the discovered issuer `{}` does not match the configured one",
+						metadata.issuer
+					)
+					.into());
+				}
+
+				let (client_id, client_secret) = self.client.clone();
+				let client = oauth2::Client::new(client_id.clone())
+					.set_client_secret(client_secret)
+					.set_auth_uri(
+						AuthUrl::new(metadata.authorization_endpoint)
+							.context("invalid authorization endpoint")?,
+					)
+					.set_token_uri(
+						TokenUrl::new(metadata.token_endpoint).context("invalid token endpoint")?,
+					)
+					.set_redirect_uri(self.redirect_url.clone());
+
+				let validator = IdTokenValidator::new(
+					vec![metadata.issuer],
+					client_id.to_string(),
+					metadata.jwks_uri,
+					self.http.clone(),
+				);
+
+				Ok(Discovered { client, validator })
+			})
+			.await
+	}
+}
+
+#[async_trait]
+impl IdentityProvider for OidcProvider {
+	async fn authorize_url(
+		&self,
+		offline_access: bool,
+	) -> Result<AuthorizationRequest, AuthentificationError> {
+		let discovered = self.discovered().await?;
+
+		let nonce = CsrfToken::new_random();
+		let mut request = discovered
+			.client
+			.authorize_url(CsrfToken::new_random)
+			.add_scopes([
+				Scope::new(scopes::OPENID.into()),
+				Scope::new(scopes::EMAIL.into()),
+				Scope::new(scopes::PROFILE.into()),
+			])
+			.add_extra_param("nonce", nonce.secret());
+		if offline_access {
+			request = request.add_scope(Scope::new(scopes::OFFLINE_ACCESS.into()));
+		}
+		let (url, csrf_state) = request.url();
+
+		Ok(AuthorizationRequest {
+			url,
+			csrf_state,
+			nonce,
+		})
+	}
+
+	async fn user_metadata(
+		&self,
+		code: String,
+		nonce: &str,
+	) -> Result<UserMetadata, AuthentificationError> {
+		let discovered = self.discovered().await?;
+
+		let token_response = discovered
+			.client
+			.exchange_code(AuthorizationCode::new(code))
+			.request_async(&http_client()?)
+			.await
+			.context("could not get oauth2 token")?;
+
+		let claims = discovered
+			.validator
+			.validate(&token_response.extra_fields().id_token, nonce)
+			.await?;
+
+		// Providers like `Microsoft Entra ID` leave the claim out, they must be trusted explicitly
+		if !claims.email_verified.unwrap_or(self.trust_unverified_email) {
+			return Err(anyhow!("the provider did not verify the user email").into());
+		}
+
+		// `preferred_username` can be changed by the user, it is never used as a mail
+		let mail = claims
+			.email
+			.context("the ID token does not contain an email")?;
+
+		let domain = mail.rsplit_once('@').map(|(_, domain)| domain.to_owned());
+
+		Ok(UserMetadata {
+			// The mail is verified, so its domain can be trusted by the email rules
+			domain,
+			mail,
+			first_name: claims.given_name.unwrap_or_default(),
+			last_name: claims.family_name.unwrap_or_default(),
+			refresh_token: token_response
+				.refresh_token()
+				.map(|token| SecretString::from(token.secret().clone())),
+		})
+	}
+
+	async fn refresh(
+		&self,
+		refresh_token: &SecretString,
+	) -> Result<RefreshOutcome, AuthentificationError> {
+		let discovered = self.discovered().await?;
+
+		let refresh_token = RefreshToken::new(refresh_token.expose_secret().to_owned());
+		let result = discovered
+			.client
+			.exchange_refresh_token(&refresh_token)
+			.request_async(&http_client()?)
+			.await;
+
+		refresh_outcome(result)
+	}
+}
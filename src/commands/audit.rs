@@ -0,0 +1,129 @@
+//! Search what happened in the guild
+
+use crate::{
+	audit::{parse_period, AuditKind},
+	constants,
+	database::{models::AuditEvent, prelude::*, schema::audit_events},
+	states::{ApplicationContext, ApplicationContextPolyfill, InteractionResult},
+	translation::Translate,
+};
+use chrono::Utc;
+use fluent::fluent_args;
+use poise::{
+	command,
+	serenity_prelude::{CreateEmbed, User},
+	CreateReply,
+};
+use std::fmt::Write as _;
+
+/// Search the audit log of the guild
+#[allow(clippy::unused_async)]
+#[command(
+	slash_command,
+	subcommands("audit_search"),
+	default_member_permissions = "VIEW_AUDIT_LOG"
+)]
+pub(crate) async fn audit(_: ApplicationContext<'_>) -> InteractionResult {
+	Ok(())
+}
+
+/// Show the latest events, done by or to a member
+#[command(slash_command, guild_only, rename = "search")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn audit_search(
+	ctx: ApplicationContext<'_>,
+	user: Option<User>,
+	since: Option<String>,
+) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+	let since = since.unwrap_or_else(|| constants::audit::DEFAULT_SEARCH_PERIOD.to_owned());
+
+	// A huge period would go further back than the dates stored by the database
+	let Some(start) = parse_period(&since)
+		.and_then(|period| Utc::now().naive_utc().checked_sub_signed(period))
+		.filter(|start| start.and_utc().timestamp() >= 0)
+	else {
+		ctx.shout(ctx.translate(
+			"audit_search-invalid-period",
+			Some(fluent_args!["period" => since]),
+		))
+		.await?;
+
+		return Ok(());
+	};
+
+	let mut query = AuditEvent::all_from_guild(guild_id)
+		.filter(audit_events::created_at.ge(start))
+		.order_by(audit_events::created_at.desc())
+		.limit(constants::audit::SEARCH_LIMIT)
+		.into_boxed();
+
+	if let Some(user) = &user {
+		query = query.filter(
+			audit_events::actor_id
+				.eq(user.id.get())
+				.or(audit_events::target_id.eq(user.id.get())),
+		);
+	}
+
+	let events = query
+		.load::<AuditEvent>(&mut ctx.data.database.get().await?)
+		.await?;
+
+	if events.is_empty() {
+		ctx.shout(ctx.translate("audit_search-none", None)).await?;
+
+		return Ok(());
+	}
+
+	let mut description = String::new();
+	let mut description_length = 0;
+	for event in events {
+		let kind = event.kind.parse::<AuditKind>().map_or_else(
+			|_| event.kind.clone(),
+			|kind| ctx.translate(kind.translation_key(), None),
+		);
+
+		let mut row = String::new();
+		let _ = write!(
+			row,
+			"<t:{}:f> **{kind}**",
+			event.created_at.and_utc().timestamp()
+		);
+		if let Some(actor_id) = event.actor_id {
+			let _ = write!(row, " <@{actor_id}>");
+		}
+		if let Some(target_id) = event.target_id {
+			let _ = write!(row, " → <@{target_id}>");
+		}
+		if let Some(details) = event.details {
+			let details = details
+				.chars()
+				.take(constants::audit::MAX_DETAILS_LENGTH)
+				.collect::<String>()
+				.replace('\n', " ");
+			let _ = write!(row, ": {details}");
+		}
+		row.push('\n');
+
+		// The oldest events are left out when the embed would be too long
+		let row_length = row.chars().count();
+		if description_length + row_length > constants::audit::MAX_DESCRIPTION_LENGTH {
+			break;
+		}
+		description_length += row_length;
+		description.push_str(&row);
+	}
+
+	ctx.send(
+		CreateReply::default().ephemeral(true).embed(
+			CreateEmbed::new()
+				.title(ctx.translate("audit_search-title", Some(fluent_args!["period" => since])))
+				.description(description)
+				.color(0x0000_00FF),
+		),
+	)
+	.await?;
+
+	Ok(())
+}
@@ -0,0 +1,45 @@
+//! The roles bound to the classes, levels and groups of a guild, shared by their edit commands
+
+use crate::database::{
+	models::{Class, Group, Level},
+	prelude::*,
+	schema::{classes, groups, levels},
+	DatabasePooledConnection,
+};
+use diesel::dsl;
+use poise::serenity_prelude::{self as serenity, EditRole, GuildId, Http, Role, RoleId};
+
+/// Whether a role is already bound to a class, a level or a group of the guild
+pub(super) async fn is_bound(
+	connection: &mut DatabasePooledConnection,
+	guild_id: GuildId,
+	role_id: RoleId,
+) -> Result<bool, DieselError> {
+	dsl::select(
+		dsl::exists(Class::all_from_guild(guild_id).filter(classes::role_id.eq(role_id.get())))
+			.or(dsl::exists(
+				Level::all_from_guild(guild_id).filter(levels::role_id.eq(role_id.get())),
+			))
+			.or(dsl::exists(
+				Group::all_from_guild(guild_id).filter(groups::role_id.eq(role_id.get())),
+			)),
+	)
+	.get_result::<bool>(connection)
+	.await
+}
+
+/// Keep the name of the role in sync with a renamed class, level or group, unless another role was chosen
+pub(super) async fn rename_role(
+	http: &Http,
+	guild_id: GuildId,
+	role_id: RoleId,
+	(new_name, new_role): (&str, Option<&Role>),
+) -> Result<(), serenity::Error> {
+	if new_role.is_none() {
+		guild_id
+			.edit_role(http, role_id, EditRole::new().name(new_name))
+			.await?;
+	}
+
+	Ok(())
+}
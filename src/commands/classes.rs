@@ -1,28 +1,31 @@
 //! Setup messages for roles interactions
 
 use crate::{
-	commands::levels::autocomplete_levels,
+	commands::{bound_roles, levels::autocomplete_levels, removal},
 	constants,
 	database::{
 		models::{Class, Level, NewClass},
 		prelude::*,
 		schema,
 	},
-	states::{ApplicationContext, ApplicationContextPolyfill, InteractionResult},
+	states::{ApplicationContext, ApplicationContextPolyfill, InteractionError, InteractionResult},
 	translation::Translate,
+	verification::swap_role,
 };
+use diesel_async::{scoped_futures::ScopedFutureExt, AsyncConnection};
 use fluent::fluent_args;
 use poise::{
 	command,
-	serenity_prelude::{self as serenity, EditRole, Permissions, Role, RoleId},
+	serenity_prelude::{EditRole, Permissions, Role, RoleId, UserId},
 };
+use std::collections::HashMap;
 
-// TODO: possibility to modify a class, show specific informations on a role
-/// Add or delete a [`Class`]
+// TODO: show specific informations on a role
+/// Add, edit or delete a [`Class`]
 #[allow(clippy::unused_async)]
 #[command(
 	slash_command,
-	subcommands("classes_add", "classes_remove", "classes_list"),
+	subcommands("classes_add", "classes_edit", "classes_remove", "classes_list"),
 	default_member_permissions = "MANAGE_ROLES",
 	required_bot_permissions = "MANAGE_ROLES"
 )]
@@ -108,7 +111,7 @@ pub(crate) async fn classes_add(
 /// Autocompletes parameter for `classes` available in `Guild`.
 #[allow(clippy::unwrap_used)]
 #[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
-async fn autocomplete_classes<'a>(
+pub(super) async fn autocomplete_classes<'a>(
 	ctx: ApplicationContext<'_>,
 	partial: &'a str,
 ) -> impl Iterator<Item = String> + 'a {
@@ -124,20 +127,223 @@ async fn autocomplete_classes<'a>(
 		.filter(move |level| level.contains(partial))
 }
 
-/// Delete a class role
+/// Rename a class, change its role or move it to another level, its members get the new roles
+#[allow(clippy::too_many_lines)]
+#[command(slash_command, guild_only, rename = "edit")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn classes_edit(
+	ctx: ApplicationContext<'_>,
+	#[autocomplete = "autocomplete_classes"] name: String,
+	new_name: Option<String>,
+	#[autocomplete = "autocomplete_levels"] level: Option<String>,
+	role: Option<Role>,
+) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+	let mut connection = ctx.data.database.get().await?;
+
+	let Some(class) = Class::all_from_guild(guild_id)
+		.filter(schema::classes::name.eq(&name))
+		.first::<Class>(&mut connection)
+		.await
+		.optional()?
+	else {
+		ctx.shout(ctx.translate("classes_remove-not-found", None))
+			.await?;
+
+		return Ok(());
+	};
+
+	if new_name.is_none() && level.is_none() && role.is_none() {
+		ctx.shout(ctx.translate("classes_edit-nothing-to-do", None))
+			.await?;
+
+		return Ok(());
+	}
+
+	if let Some(new_name) = &new_name {
+		if db_dsl::select(db_dsl::exists(
+			Class::all_from_guild(guild_id).filter(schema::classes::name.eq(new_name)),
+		))
+		.get_result::<bool>(&mut connection)
+		.await?
+		{
+			ctx.shout(ctx.translate(
+				"classes_edit-already-exists",
+				Some(fluent_args!["class" => new_name.as_str()]),
+			))
+			.await?;
+
+			return Ok(());
+		}
+	}
+
+	let new_level = if let Some(level) = &level {
+		let Some(new_level) = Level::all_from_guild(guild_id)
+			.filter(schema::levels::name.eq(level))
+			.first::<Level>(&mut connection)
+			.await
+			.optional()?
+		else {
+			ctx.shout(ctx.translate(
+				"classes_add-no-such-level",
+				Some(fluent_args! { "level" => level.as_str() }),
+			))
+			.await?;
+
+			return Ok(());
+		};
+
+		let nb_of_classes: i64 = Class::all_from_level(new_level.id)
+			.count()
+			.get_result(&mut connection)
+			.await?;
+
+		if new_level.id != class.level_id
+			&& nb_of_classes >= i64::from(constants::limits::MAX_CLASSES_PER_LEVEL)
+		{
+			ctx.shout(ctx.translate("classes_add-too-many-classes", None))
+				.await?;
+
+			return Ok(());
+		}
+
+		Some(new_level)
+	} else {
+		None
+	};
+
+	if let Some(role) = &role {
+		if role.has_permission(Permissions::ADMINISTRATOR) {
+			ctx.shout(ctx.translate("setup_role-role-admin", None))
+				.await?;
+
+			return Ok(());
+		}
+
+		if role.id.get() != class.role_id
+			&& bound_roles::is_bound(&mut connection, guild_id, role.id).await?
+		{
+			ctx.shout(ctx.translate(
+				"classes_edit-role-in-use",
+				Some(fluent_args!["role" => role.name.as_str()]),
+			))
+			.await?;
+
+			return Ok(());
+		}
+	}
+
+	ctx.defer_ephemeral().await?;
+
+	let user_ids = schema::verified_members::table
+		.inner_join(schema::members::table)
+		.filter(schema::verified_members::class_id.eq(class.id))
+		.select(schema::members::discord_id)
+		.load::<u64>(&mut connection)
+		.await?
+		.into_iter()
+		.map(UserId::new)
+		.collect::<Vec<_>>();
+
+	let level_change = match new_level.filter(|new_level| new_level.id != class.level_id) {
+		Some(new_level) => {
+			let old_level_role_id: u64 = Level::with_id(class.level_id)
+				.select(schema::levels::role_id)
+				.first(&mut connection)
+				.await?;
+
+			Some((new_level, RoleId::new(old_level_role_id)))
+		}
+		None => None,
+	};
+
+	// The roles are changed last, a failure rolls the database changes back
+	connection
+		.transaction::<_, InteractionError, _>(|connection| {
+			async {
+				if let Some(role) = &role {
+					diesel::update(Class::with_id(class.id))
+						.set((
+							schema::classes::role_id.eq(role.id.get()),
+							schema::classes::broken.eq(false),
+						))
+						.execute(connection)
+						.await?;
+				}
+				if let Some((new_level, _)) = &level_change {
+					diesel::update(Class::with_id(class.id))
+						.set(schema::classes::level_id.eq(new_level.id))
+						.execute(connection)
+						.await?;
+				}
+				if let Some(new_name) = &new_name {
+					diesel::update(Class::with_id(class.id))
+						.set(schema::classes::name.eq(new_name))
+						.execute(connection)
+						.await?;
+				}
+
+				if let Some(role) = &role {
+					swap_role(
+						ctx.http(),
+						guild_id,
+						&user_ids,
+						(Some(RoleId::new(class.role_id)), Some(role.id)),
+					)
+					.await?;
+				}
+				if let Some((new_level, old_level_role_id)) = &level_change {
+					swap_role(
+						ctx.http(),
+						guild_id,
+						&user_ids,
+						(
+							Some(*old_level_role_id),
+							Some(RoleId::new(new_level.role_id)),
+						),
+					)
+					.await?;
+				}
+				if let Some(new_name) = &new_name {
+					bound_roles::rename_role(
+						ctx.http(),
+						guild_id,
+						RoleId::new(class.role_id),
+						(new_name, role.as_ref()),
+					)
+					.await?;
+				}
+
+				Ok(())
+			}
+			.scope_boxed()
+		})
+		.await?;
+
+	ctx.shout(ctx.translate(
+		"classes_edit-success",
+		Some(fluent_args!["class" => new_name.unwrap_or(name)]),
+	))
+	.await?;
+
+	Ok(())
+}
+
+/// Delete a class role, its members are moved to another class or unverified after a confirmation
+#[allow(clippy::too_many_lines)]
 #[command(slash_command, guild_only, rename = "remove")]
 #[tracing::instrument(skip(ctx))]
 pub(crate) async fn classes_remove(
 	ctx: ApplicationContext<'_>,
 	#[autocomplete = "autocomplete_classes"] name: String,
+	#[autocomplete = "autocomplete_classes"] move_to: Option<String>,
 ) -> InteractionResult {
 	let guild_id = ctx.guild_only_id();
 	let mut connection = ctx.data.database.get().await?;
 
-	let Some((id, role_id)) = Class::all_from_guild(guild_id)
+	let Some(class) = Class::all_from_guild(guild_id)
 		.filter(schema::classes::name.eq(&name))
-		.select((schema::classes::id, schema::classes::role_id))
-		.first::<(i32, u64)>(&mut connection)
+		.first::<Class>(&mut connection)
 		.await
 		.optional()?
 	else {
@@ -147,20 +353,150 @@ pub(crate) async fn classes_remove(
 		return Ok(());
 	};
 
-	match guild_id
-		.delete_role(&ctx.serenity_context, RoleId::new(role_id))
-		.await
-	{
-		Ok(()) |
-		// Ignore the error if the role is already deleted
-		Err(serenity::Error::Http(_)) => {}
-		Err(error) => return Err(error.into()),
+	let target = if let Some(move_to) = &move_to {
+		let Some(target) = Class::all_from_guild(guild_id)
+			.filter(schema::classes::name.eq(move_to))
+			.filter(schema::classes::id.ne(class.id))
+			.first::<Class>(&mut connection)
+			.await
+			.optional()?
+		else {
+			ctx.shout(ctx.translate(
+				"classes_remove-no-such-target",
+				Some(fluent_args!["class" => move_to.as_str()]),
+			))
+			.await?;
+
+			return Ok(());
+		};
+
+		Some(target)
+	} else {
+		None
+	};
+
+	let members = removal::affected_members(&mut connection, &[class.id]).await?;
+
+	if members.is_empty() {
+		ctx.defer_ephemeral().await?;
+	} else {
+		let warning = target.as_ref().map_or_else(
+			|| {
+				ctx.translate(
+					"classes_remove-confirm-unverify",
+					Some(fluent_args!["class" => name.as_str(), "count" => members.len()]),
+				)
+			},
+			|target| {
+				ctx.translate(
+					"classes_remove-confirm-move",
+					Some(fluent_args![
+						"class" => name.as_str(),
+						"count" => members.len(),
+						"target" => target.name.as_str(),
+					]),
+				)
+			},
+		);
+
+		if !removal::confirm(
+			&ctx,
+			format!("{warning}\n{}", removal::preview(&ctx, &members)),
+		)
+		.await?
+		{
+			return Ok(());
+		}
 	}
 
-	diesel::delete(Class::with_id(id))
-		.execute(&mut connection)
+	let level_role_id: u64 = Level::with_id(class.level_id)
+		.select(schema::levels::role_id)
+		.first(&mut connection)
+		.await?;
+	let verified_roles = if target.is_none() {
+		removal::verified_roles(&mut connection, guild_id, &members).await?
+	} else {
+		HashMap::new()
+	};
+
+	// The role is deleted last, a failure rolls the database changes back
+	let refresh_tokens = connection
+		.transaction::<_, InteractionError, _>(|connection| {
+			async {
+				let mut refresh_tokens = Vec::new();
+				if let Some(target) = &target {
+					diesel::update(
+						schema::verified_members::table
+							.filter(schema::verified_members::class_id.eq(class.id)),
+					)
+					.set(schema::verified_members::class_id.eq(target.id))
+					.execute(connection)
+					.await?;
+				} else {
+					refresh_tokens = removal::unverify(connection, &members).await?;
+				}
+
+				diesel::delete(Class::with_id(class.id))
+					.execute(connection)
+					.await?;
+
+				removal::delete_role(ctx.http(), guild_id, RoleId::new(class.role_id)).await?;
+
+				Ok(refresh_tokens)
+			}
+			.scope_boxed()
+		})
 		.await?;
 
+	let user_ids = members
+		.iter()
+		.map(|member| member.user_id)
+		.collect::<Vec<_>>();
+
+	if let Some(target) = &target {
+		let target_level_role_id: u64 = Level::with_id(target.level_id)
+			.select(schema::levels::role_id)
+			.first(&mut connection)
+			.await?;
+
+		swap_role(
+			ctx.http(),
+			guild_id,
+			&user_ids,
+			(None, Some(RoleId::new(target.role_id))),
+		)
+		.await?;
+
+		if target.level_id != class.level_id {
+			swap_role(
+				ctx.http(),
+				guild_id,
+				&user_ids,
+				(
+					Some(RoleId::new(level_role_id)),
+					Some(RoleId::new(target_level_role_id)),
+				),
+			)
+			.await?;
+		}
+	} else {
+		swap_role(
+			ctx.http(),
+			guild_id,
+			&user_ids,
+			(Some(RoleId::new(level_role_id)), None),
+		)
+		.await?;
+		removal::strip_roles(ctx.http(), guild_id, verified_roles).await?;
+		removal::unverified(&ctx, &members, refresh_tokens, &name).await;
+	}
+
+	ctx.shout(ctx.translate(
+		"classes_remove-success",
+		Some(fluent_args!["class" => name, "count" => members.len()]),
+	))
+	.await?;
+
 	Ok(())
 }
 
@@ -1,27 +1,31 @@
 //! Setup messages for roles interactions
 
 use crate::{
+	commands::{bound_roles, setup::refresh_groups_message},
 	constants,
 	database::{
 		models::{Group, NewGroup},
 		prelude::*,
 		schema,
 	},
-	states::{ApplicationContext, ApplicationContextPolyfill, InteractionResult},
+	states::{ApplicationContext, ApplicationContextPolyfill, InteractionError, InteractionResult},
 	translation::Translate,
+	verification::swap_role,
 };
+use diesel_async::{scoped_futures::ScopedFutureExt, AsyncConnection};
 use fluent::fluent_args;
 use poise::{
 	command,
-	serenity_prelude::{self as serenity, EditRole, Permissions, ReactionType, Role, RoleId},
+	serenity_prelude::{
+		self as serenity, EditRole, Permissions, ReactionType, Role, RoleId, UserId,
+	},
 };
 
-// TODO: possibility to modify a group
-/// Add or delete a [`Group`]
+/// Add, edit or delete a [`Group`]
 #[allow(clippy::unused_async)]
 #[command(
 	slash_command,
-	subcommands("groups_add", "groups_remove", "groups_list"),
+	subcommands("groups_add", "groups_edit", "groups_remove", "groups_list"),
 	default_member_permissions = "MANAGE_ROLES",
 	required_bot_permissions = "MANAGE_ROLES"
 )]
@@ -78,10 +82,11 @@ pub(crate) async fn groups_add(
 		role_id: role.id.get(),
 	};
 
-	new_group
-		.insert()
-		.execute(&mut ctx.data.database.get().await?)
-		.await?;
+	let mut connection = ctx.data.database.get().await?;
+
+	new_group.insert().execute(&mut connection).await?;
+
+	refresh_groups_message(&ctx, &mut connection).await?;
 
 	ctx.shout(ctx.translate("groups_add-success", Some(fluent_args! { "group" => name })))
 		.await?;
@@ -93,7 +98,7 @@ pub(crate) async fn groups_add(
 /// Autocompletes parameter for `groups` available in `Guild`.
 #[allow(clippy::unwrap_used)]
 #[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
-async fn autocomplete_groups<'a>(
+pub(super) async fn autocomplete_groups<'a>(
 	ctx: ApplicationContext<'_>,
 	partial: &'a str,
 ) -> impl Iterator<Item = String> + 'a {
@@ -109,6 +114,165 @@ async fn autocomplete_groups<'a>(
 		.filter(move |group| group.contains(partial))
 }
 
+/// Rename a group, change its emoji or its role, its members get the new role
+#[allow(clippy::too_many_lines)]
+#[command(slash_command, guild_only, rename = "edit")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn groups_edit(
+	ctx: ApplicationContext<'_>,
+	#[autocomplete = "autocomplete_groups"] name: String,
+	new_name: Option<String>,
+	emoji: Option<String>,
+	role: Option<Role>,
+) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+	let mut connection = ctx.data.database.get().await?;
+
+	let Some((id, role_id)) = Group::all_from_guild(guild_id)
+		.filter(schema::groups::name.eq(&name))
+		.select((schema::groups::id, schema::groups::role_id))
+		.first::<(i32, u64)>(&mut connection)
+		.await
+		.optional()?
+	else {
+		ctx.shout(ctx.translate("groups_remove-not-found", None))
+			.await?;
+
+		return Ok(());
+	};
+
+	if new_name.is_none() && emoji.is_none() && role.is_none() {
+		ctx.shout(ctx.translate("groups_edit-nothing-to-do", None))
+			.await?;
+
+		return Ok(());
+	}
+
+	let emoji = if let Some(emoji) = emoji {
+		if let Ok(emoji) = emoji.parse::<ReactionType>() {
+			Some(emoji)
+		} else {
+			ctx.shout(ctx.translate("groups_add-invalid-emoji", None))
+				.await?;
+
+			return Ok(());
+		}
+	} else {
+		None
+	};
+
+	if let Some(new_name) = &new_name {
+		if db_dsl::select(db_dsl::exists(
+			Group::all_from_guild(guild_id).filter(schema::groups::name.eq(new_name)),
+		))
+		.get_result::<bool>(&mut connection)
+		.await?
+		{
+			ctx.shout(ctx.translate(
+				"groups_edit-already-exists",
+				Some(fluent_args!["group" => new_name.as_str()]),
+			))
+			.await?;
+
+			return Ok(());
+		}
+	}
+
+	if let Some(role) = &role {
+		if role.has_permission(Permissions::ADMINISTRATOR) {
+			ctx.shout(ctx.translate("setup_role-role-admin", None))
+				.await?;
+
+			return Ok(());
+		}
+
+		if role.id.get() != role_id
+			&& bound_roles::is_bound(&mut connection, guild_id, role.id).await?
+		{
+			ctx.shout(ctx.translate(
+				"groups_edit-role-in-use",
+				Some(fluent_args!["role" => role.name.as_str()]),
+			))
+			.await?;
+
+			return Ok(());
+		}
+	}
+
+	ctx.defer_ephemeral().await?;
+
+	let user_ids = schema::groups_of_verified_members::table
+		.inner_join(schema::verified_members::table.inner_join(schema::members::table))
+		.filter(schema::groups_of_verified_members::group_id.eq(id))
+		.select(schema::members::discord_id)
+		.load::<u64>(&mut connection)
+		.await?
+		.into_iter()
+		.map(UserId::new)
+		.collect::<Vec<_>>();
+
+	// The roles are changed last, a failure rolls the database changes back
+	connection
+		.transaction::<_, InteractionError, _>(|connection| {
+			async {
+				if let Some(role) = &role {
+					diesel::update(Group::with_id(id))
+						.set((
+							schema::groups::role_id.eq(role.id.get()),
+							schema::groups::broken.eq(false),
+						))
+						.execute(connection)
+						.await?;
+				}
+				if let Some(emoji) = &emoji {
+					diesel::update(Group::with_id(id))
+						.set(schema::groups::emoji.eq(emoji.to_string()))
+						.execute(connection)
+						.await?;
+				}
+				if let Some(new_name) = &new_name {
+					diesel::update(Group::with_id(id))
+						.set(schema::groups::name.eq(new_name))
+						.execute(connection)
+						.await?;
+				}
+
+				if let Some(role) = &role {
+					swap_role(
+						ctx.http(),
+						guild_id,
+						&user_ids,
+						(Some(RoleId::new(role_id)), Some(role.id)),
+					)
+					.await?;
+				}
+				if let Some(new_name) = &new_name {
+					bound_roles::rename_role(
+						ctx.http(),
+						guild_id,
+						RoleId::new(role_id),
+						(new_name, role.as_ref()),
+					)
+					.await?;
+				}
+
+				Ok(())
+			}
+			.scope_boxed()
+		})
+		.await?;
+
+	refresh_groups_message(&ctx, &mut connection).await?;
+
+	ctx.shout(ctx.translate(
+		"groups_edit-success",
+		Some(fluent_args!["group" => new_name.unwrap_or(name)]),
+	))
+	.await?;
+
+	Ok(())
+}
+
 /// Delete a group tag role
 #[command(slash_command, guild_only, rename = "remove")]
 #[tracing::instrument(skip(ctx))]
@@ -141,10 +305,14 @@ pub(crate) async fn groups_remove(
 		Err(error) => return Err(error.into()),
 	}
 
+	let mut connection = ctx.data.database.get().await?;
+
 	diesel::delete(Group::with_id(id))
-		.execute(&mut ctx.data.database.get().await?)
+		.execute(&mut connection)
 		.await?;
 
+	refresh_groups_message(&ctx, &mut connection).await?;
+
 	Ok(())
 }
 
@@ -1,15 +1,31 @@
 //! A set of commands to force actions like login or logout
 
 use crate::{
+	audit::{self, AuditEntry, AuditKind},
+	auth::UserMetadata,
+	commands::{classes::autocomplete_classes, levels::autocomplete_levels},
 	database::{
+		models::{Guild, Member, VerifiedMember},
 		prelude::*,
-		schema::{members, verified_members},
+		schema::{guilds, members, verified_members},
 	},
 	states::{ApplicationContext, ApplicationContextPolyfill, InteractionResult},
 	translation::Translate,
+	verification::{
+		apply_changes,
+		class_change::{change_class, find_class},
+		duplicates::{claim_mail, flag_duplicate, other_accounts, DuplicateMailPolicy},
+		member_kind::MemberKind,
+		unlink::unlink,
+		VerifiedRole,
+	},
 };
+use diesel::dsl;
 use fluent::fluent_args;
-use poise::{command, serenity_prelude as serenity};
+use poise::{
+	command,
+	serenity_prelude::{self as serenity, RoleId},
+};
 
 /// A set of commands to force actions like login or logout
 #[allow(clippy::unused_async)]
@@ -18,33 +34,188 @@ use poise::{command, serenity_prelude as serenity};
 	owners_only,
 	hide_in_help,
 	rename = "force",
-	subcommands("debug_force_logout")
+	subcommands("debug_force_login", "debug_force_logout", "debug_force_move")
 )]
 pub(super) async fn debug_force(_: ApplicationContext<'_>) -> InteractionResult {
 	Ok(())
 }
 
-/// Force logout a member
-#[command(slash_command, owners_only, hide_in_help, rename = "logout")]
+/// Force the verification of a member, the roles are given as if they logged in
+#[allow(clippy::too_many_arguments, clippy::too_many_lines)]
+#[command(slash_command, owners_only, hide_in_help, rename = "login")]
 #[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
-pub(super) async fn debug_force_logout(
+pub(super) async fn debug_force_login(
 	ctx: ApplicationContext<'_>,
 	user: serenity::Member,
+	kind: MemberKind,
+	mail: String,
+	first_name: String,
+	last_name: String,
+	#[autocomplete = "autocomplete_levels"] level: Option<String>,
+	#[autocomplete = "autocomplete_classes"] class: Option<String>,
 ) -> InteractionResult {
 	let mut connection = ctx.data.database.get().await?;
 
-	if let Ok(member_id) = verified_members::table
-		.inner_join(members::table)
-		.filter(members::discord_id.eq(user.user.id.get()))
-		.filter(members::guild_id.eq(user.guild_id.get()))
-		.select(verified_members::member_id)
+	let (verified_role_id, duplicate_mail_policy) = Guild::with_id(user.guild_id)
+		.select((guilds::verified_role_id, guilds::duplicate_mail_policy))
+		.first::<(Option<u64>, String)>(&mut connection)
+		.await?;
+	let Some(verified_role_id) = verified_role_id else {
+		ctx.shout(ctx.translate("debug_force_login-not-set-up", None))
+			.await?;
+
+		return Ok(());
+	};
+
+	let Some(member_id) = Member::with_ids(user.user.id, user.guild_id)
+		.select(members::id)
 		.first::<i32>(&mut connection)
 		.await
+		.optional()?
+	else {
+		ctx.shout(ctx.translate(
+			"error-member-not-registered",
+			Some(fluent_args!["user" => user.user.name]),
+		))
+		.await?;
+
+		return Ok(());
+	};
+
+	if dsl::select(dsl::exists(VerifiedMember::from_member_id(member_id)))
+		.get_result::<bool>(&mut connection)
+		.await?
 	{
-		diesel::delete(verified_members::table.filter(verified_members::member_id.eq(member_id)))
-			.execute(&mut connection)
+		ctx.shout(ctx.translate(
+			"debug_force_login-already-verified",
+			Some(fluent_args!["user" => user.user.name]),
+		))
+		.await?;
+
+		return Ok(());
+	}
+
+	let class_id = if kind.has_class() {
+		let (Some(level), Some(class)) = (&level, &class) else {
+			ctx.shout(ctx.translate("debug_force_login-class-required", None))
+				.await?;
+
+			return Ok(());
+		};
+
+		let Some(class_id) = find_class(&mut connection, user.guild_id, (level, class)).await?
+		else {
+			ctx.shout(ctx.translate(
+				"debug_force_login-class-not-found",
+				Some(fluent_args!["level" => level.as_str(), "class" => class.as_str()]),
+			))
 			.await?;
 
+			return Ok(());
+		};
+
+		Some(class_id)
+	} else {
+		None
+	};
+
+	// The mail is held like after a login, so that no one logs in with it afterwards
+	let duplicates = match duplicate_mail_policy.parse::<DuplicateMailPolicy>()? {
+		DuplicateMailPolicy::Block => {
+			if !claim_mail(&mut connection, (user.guild_id, member_id), &mail).await? {
+				ctx.shout(ctx.translate(
+					"debug_force_login-duplicate-mail",
+					Some(fluent_args!["mail" => mail]),
+				))
+				.await?;
+
+				return Ok(());
+			}
+
+			Vec::new()
+		}
+		DuplicateMailPolicy::Allow => Vec::new(),
+		DuplicateMailPolicy::Flag => {
+			other_accounts(&mut connection, (user.guild_id, member_id), &mail).await?
+		}
+	};
+
+	ctx.defer_ephemeral().await?;
+
+	let user_data = UserMetadata {
+		mail,
+		first_name,
+		last_name,
+		domain: None,
+		refresh_token: None,
+	};
+
+	apply_changes(
+		&ctx.data.http,
+		&mut connection,
+		(user.guild_id, user.user.id),
+		&user_data,
+		member_id,
+		VerifiedRole::Guild(RoleId::new(verified_role_id)),
+		kind,
+		class_id,
+		Some(ctx.interaction.user.id),
+	)
+	.await?;
+
+	if !duplicates.is_empty() {
+		flag_duplicate(
+			ctx.data,
+			(user.guild_id, user.user.id),
+			&user_data.mail,
+			&duplicates,
+		)
+		.await;
+	}
+
+	audit::record(
+		ctx.data,
+		user.guild_id,
+		AuditEntry::new(AuditKind::ForcedLogin)
+			.actor(ctx.interaction.user.id)
+			.target(user.user.id)
+			.details(format!("{} ({kind})", user_data.mail)),
+	)
+	.await;
+
+	ctx.shout(ctx.translate(
+		"debug_force_login-done",
+		Some(fluent_args!["user" => user.user.name]),
+	))
+	.await?;
+
+	Ok(())
+}
+
+/// Force logout a member, the roles given by the verification are taken back
+#[command(slash_command, owners_only, hide_in_help, rename = "logout")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(super) async fn debug_force_logout(
+	ctx: ApplicationContext<'_>,
+	user: serenity::Member,
+) -> InteractionResult {
+	if unlink(
+		ctx.data,
+		&mut ctx.data.database.get().await?,
+		(user.guild_id, user.user.id),
+		Some(ctx.interaction.user.id),
+	)
+	.await?
+	{
+		audit::record(
+			ctx.data,
+			user.guild_id,
+			AuditEntry::new(AuditKind::ForcedLogout)
+				.actor(ctx.interaction.user.id)
+				.target(user.user.id),
+		)
+		.await;
+
 		ctx.shout(ctx.translate(
 			"debug_force_logout-done",
 			Some(fluent_args!["user" => user.user.name]),
@@ -60,3 +231,79 @@ pub(super) async fn debug_force_logout(
 
 	Ok(())
 }
+
+/// Move a verified student to another class, without making them log out
+#[command(slash_command, owners_only, hide_in_help, rename = "move")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(super) async fn debug_force_move(
+	ctx: ApplicationContext<'_>,
+	user: serenity::Member,
+	#[autocomplete = "autocomplete_levels"] level: String,
+	#[autocomplete = "autocomplete_classes"] class: String,
+) -> InteractionResult {
+	let mut connection = ctx.data.database.get().await?;
+
+	let Some((member_id, member_kind)) = VerifiedMember::with_ids(user.user.id, user.guild_id)
+		.select((verified_members::member_id, verified_members::member_kind))
+		.first::<(i32, String)>(&mut connection)
+		.await
+		.optional()?
+	else {
+		ctx.shout(ctx.translate(
+			"error-member-not-verified",
+			Some(fluent_args!["user" => user.user.name]),
+		))
+		.await?;
+
+		return Ok(());
+	};
+
+	if !member_kind.parse::<MemberKind>()?.has_class() {
+		ctx.shout(ctx.translate(
+			"debug_force_move-not-a-student",
+			Some(fluent_args!["user" => user.user.name]),
+		))
+		.await?;
+
+		return Ok(());
+	}
+
+	let Some(class_id) = find_class(&mut connection, user.guild_id, (&level, &class)).await? else {
+		ctx.shout(ctx.translate(
+			"debug_force_move-class-not-found",
+			Some(fluent_args!["level" => level.as_str(), "class" => class.as_str()]),
+		))
+		.await?;
+
+		return Ok(());
+	};
+
+	ctx.defer_ephemeral().await?;
+
+	change_class(
+		&ctx.data.http,
+		&mut connection,
+		(user.guild_id, user.user.id),
+		member_id,
+		class_id,
+	)
+	.await?;
+
+	audit::record(
+		ctx.data,
+		user.guild_id,
+		AuditEntry::new(AuditKind::ClassChanged)
+			.actor(ctx.interaction.user.id)
+			.target(user.user.id)
+			.details(format!("{level}, {class}")),
+	)
+	.await;
+
+	ctx.shout(ctx.translate(
+		"debug_force_move-done",
+		Some(fluent_args!["user" => user.user.name, "class" => class]),
+	))
+	.await?;
+
+	Ok(())
+}
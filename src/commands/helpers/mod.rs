@@ -7,10 +7,12 @@ use poise::{
 };
 
 mod force;
+mod reconcile;
 mod refresh;
 mod register;
 
 use force::debug_force;
+use reconcile::debug_reconcile;
 use refresh::debug_refresh;
 use register::debug_register;
 
@@ -21,7 +23,7 @@ use register::debug_register;
 	slash_command,
 	owners_only,
 	hide_in_help,
-	subcommands("debug_force", "debug_refresh", "debug_register")
+	subcommands("debug_force", "debug_reconcile", "debug_refresh", "debug_register")
 )]
 pub(crate) async fn debug(_: ApplicationContext<'_>) -> InteractionResult {
 	Ok(())
@@ -0,0 +1,51 @@
+//! Bring the roles of the guild members back in line with the database
+
+use crate::{
+	states::{ApplicationContext, ApplicationContextPolyfill, InteractionResult},
+	translation::Translate,
+	verification::reconcile::reconcile,
+};
+use fluent::fluent_args;
+use poise::command;
+use std::fmt::Write as _;
+
+/// Give back the missing roles, take the extra ones and report the deleted roles
+#[command(
+	slash_command,
+	owners_only,
+	hide_in_help,
+	guild_only,
+	rename = "reconcile"
+)]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(super) async fn debug_reconcile(ctx: ApplicationContext<'_>) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+
+	ctx.defer_ephemeral().await?;
+
+	let report = reconcile(ctx.http(), &mut ctx.data.database.get().await?, guild_id).await?;
+
+	let mut message = ctx.translate(
+		"debug_reconcile-success",
+		Some(fluent_args!["given" => report.given, "taken" => report.taken]),
+	);
+
+	if !report.orphans.is_empty() {
+		let _ = write!(
+			message,
+			"\n\n**{}**:",
+			ctx.translate("debug_reconcile-orphans", None)
+		);
+		for (kind, name) in report.orphans {
+			let _ = write!(
+				message,
+				"\n- {}",
+				ctx.translate(kind.translation_key(), Some(fluent_args!["name" => name]))
+			);
+		}
+	}
+
+	ctx.shout(message).await?;
+
+	Ok(())
+}
@@ -33,19 +33,31 @@ pub(crate) async fn information(ctx: ApplicationContext<'_>, user: User) -> Inte
 		return Ok(());
 	};
 
-	ctx.send(
-		CreateReply::default().ephemeral(true).embed(
-			CreateEmbed::new()
-				.title(format!(
-					"{} {}",
-					verified_member.first_name, verified_member.last_name
-				))
-				.field("Mail", verified_member.mail, false)
-				.color(0x0000_FF00)
-				.footer(CreateEmbedFooter::new("Discord SMP Link © 2023")),
+	let embed = CreateEmbed::new()
+		.title(format!(
+			"{} {}",
+			verified_member.first_name, verified_member.last_name
+		))
+		.color(0x0000_FF00)
+		.footer(CreateEmbedFooter::new("Discord SMP Link © 2023"));
+
+	// Manual verifications have no mail until the member verifies themselves
+	let embed = if verified_member.mail.is_empty() {
+		embed
+	} else {
+		embed.field("Mail", verified_member.mail, false)
+	};
+	let embed = match verified_member.verified_by {
+		Some(admin_id) => embed.field(
+			ctx.translate("information-verified-by", None),
+			format!("<@{admin_id}>"),
+			false,
 		),
-	)
-	.await?;
+		None => embed,
+	};
+
+	ctx.send(CreateReply::default().ephemeral(true).embed(embed))
+		.await?;
 
 	Ok(())
 }
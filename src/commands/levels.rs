@@ -1,27 +1,30 @@
 //! Setup messages for roles interactions
 
 use crate::{
+	commands::{bound_roles, removal},
 	constants,
 	database::{
-		models::{Level, NewLevel},
+		models::{Class, Level, NewLevel},
 		prelude::*,
 		schema,
 	},
-	states::{ApplicationContext, ApplicationContextPolyfill, InteractionResult},
+	states::{ApplicationContext, ApplicationContextPolyfill, InteractionError, InteractionResult},
 	translation::Translate,
+	verification::swap_role,
 };
+use diesel_async::{scoped_futures::ScopedFutureExt, AsyncConnection};
 use fluent::fluent_args;
 use poise::{
 	command,
-	serenity_prelude::{self as serenity, EditRole, Permissions, Role, RoleId},
+	serenity_prelude::{EditRole, Permissions, Role, RoleId, UserId},
 };
+use std::collections::HashMap;
 
-/// TODO: possibility to modify a level
-/// Add or delete a [`Level`]
+/// Add, edit or delete a [`Level`]
 #[allow(clippy::unused_async)]
 #[command(
 	slash_command,
-	subcommands("levels_add", "levels_remove", "levels_list"),
+	subcommands("levels_add", "levels_edit", "levels_remove", "levels_list"),
 	default_member_permissions = "MANAGE_ROLES",
 	required_bot_permissions = "MANAGE_ROLES"
 )]
@@ -91,19 +94,22 @@ pub(super) async fn autocomplete_levels<'a>(
 		.filter(move |level| level.contains(partial))
 }
 
-/// Delete a level tag role
-#[command(slash_command, guild_only, rename = "remove")]
-#[tracing::instrument(skip(ctx))]
-pub(crate) async fn levels_remove(
+/// Rename a level or change its role, the members of the level get the new role
+#[command(slash_command, guild_only, rename = "edit")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn levels_edit(
 	ctx: ApplicationContext<'_>,
 	#[autocomplete = "autocomplete_levels"] name: String,
+	new_name: Option<String>,
+	role: Option<Role>,
 ) -> InteractionResult {
 	let guild_id = ctx.guild_only_id();
+	let mut connection = ctx.data.database.get().await?;
 
 	let Some((id, role_id)) = Level::all_from_guild(guild_id)
 		.filter(schema::levels::name.eq(&name))
 		.select((schema::levels::id, schema::levels::role_id))
-		.first::<(i32, u64)>(&mut ctx.data.database.get().await?)
+		.first::<(i32, u64)>(&mut connection)
 		.await
 		.optional()?
 	else {
@@ -113,20 +119,292 @@ pub(crate) async fn levels_remove(
 		return Ok(());
 	};
 
-	match guild_id
-		.delete_role(&ctx.serenity_context, RoleId::new(role_id))
+	if new_name.is_none() && role.is_none() {
+		ctx.shout(ctx.translate("levels_edit-nothing-to-do", None))
+			.await?;
+
+		return Ok(());
+	}
+
+	if let Some(new_name) = &new_name {
+		if db_dsl::select(db_dsl::exists(
+			Level::all_from_guild(guild_id).filter(schema::levels::name.eq(new_name)),
+		))
+		.get_result::<bool>(&mut connection)
+		.await?
+		{
+			ctx.shout(ctx.translate(
+				"levels_edit-already-exists",
+				Some(fluent_args!["level" => new_name.as_str()]),
+			))
+			.await?;
+
+			return Ok(());
+		}
+	}
+
+	if let Some(role) = &role {
+		if role.has_permission(Permissions::ADMINISTRATOR) {
+			ctx.shout(ctx.translate("setup_role-role-admin", None))
+				.await?;
+
+			return Ok(());
+		}
+
+		if role.id.get() != role_id
+			&& bound_roles::is_bound(&mut connection, guild_id, role.id).await?
+		{
+			ctx.shout(ctx.translate(
+				"levels_edit-role-in-use",
+				Some(fluent_args!["role" => role.name.as_str()]),
+			))
+			.await?;
+
+			return Ok(());
+		}
+	}
+
+	ctx.defer_ephemeral().await?;
+
+	let user_ids = schema::verified_members::table
+		.inner_join(schema::members::table)
+		.inner_join(schema::classes::table)
+		.filter(schema::classes::level_id.eq(id))
+		.select(schema::members::discord_id)
+		.load::<u64>(&mut connection)
+		.await?
+		.into_iter()
+		.map(UserId::new)
+		.collect::<Vec<_>>();
+
+	// The roles are changed last, a failure rolls the database changes back
+	connection
+		.transaction::<_, InteractionError, _>(|connection| {
+			async {
+				if let Some(role) = &role {
+					diesel::update(Level::with_id(id))
+						.set((
+							schema::levels::role_id.eq(role.id.get()),
+							schema::levels::broken.eq(false),
+						))
+						.execute(connection)
+						.await?;
+				}
+				if let Some(new_name) = &new_name {
+					diesel::update(Level::with_id(id))
+						.set(schema::levels::name.eq(new_name))
+						.execute(connection)
+						.await?;
+				}
+
+				if let Some(role) = &role {
+					swap_role(
+						ctx.http(),
+						guild_id,
+						&user_ids,
+						(Some(RoleId::new(role_id)), Some(role.id)),
+					)
+					.await?;
+				}
+				if let Some(new_name) = &new_name {
+					bound_roles::rename_role(
+						ctx.http(),
+						guild_id,
+						RoleId::new(role_id),
+						(new_name, role.as_ref()),
+					)
+					.await?;
+				}
+
+				Ok(())
+			}
+			.scope_boxed()
+		})
+		.await?;
+
+	ctx.shout(ctx.translate(
+		"levels_edit-success",
+		Some(fluent_args!["level" => new_name.unwrap_or(name)]),
+	))
+	.await?;
+
+	Ok(())
+}
+
+/// Delete a level tag role, its classes are moved to another level or deleted after a confirmation
+#[allow(clippy::too_many_lines)]
+#[command(slash_command, guild_only, rename = "remove")]
+#[tracing::instrument(skip(ctx))]
+pub(crate) async fn levels_remove(
+	ctx: ApplicationContext<'_>,
+	#[autocomplete = "autocomplete_levels"] name: String,
+	#[autocomplete = "autocomplete_levels"] move_to: Option<String>,
+) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+	let mut connection = ctx.data.database.get().await?;
+
+	let Some(level) = Level::all_from_guild(guild_id)
+		.filter(schema::levels::name.eq(&name))
+		.first::<Level>(&mut connection)
 		.await
-	{
-		Ok(()) |
-		// Ignore the error if the role is already deleted
-		Err(serenity::Error::Http(_)) => {}
-		Err(error) => return Err(error.into()),
+		.optional()?
+	else {
+		ctx.shout(ctx.translate("levels_remove-not-found", None))
+			.await?;
+
+		return Ok(());
+	};
+
+	let classes: Vec<Class> = Class::all_from_level(level.id)
+		.load(&mut connection)
+		.await?;
+
+	let target = if let Some(move_to) = &move_to {
+		let Some(target) = Level::all_from_guild(guild_id)
+			.filter(schema::levels::name.eq(move_to))
+			.filter(schema::levels::id.ne(level.id))
+			.first::<Level>(&mut connection)
+			.await
+			.optional()?
+		else {
+			ctx.shout(ctx.translate(
+				"levels_remove-no-such-target",
+				Some(fluent_args!["level" => move_to.as_str()]),
+			))
+			.await?;
+
+			return Ok(());
+		};
+
+		let nb_of_classes: i64 = Class::all_from_level(target.id)
+			.count()
+			.get_result(&mut connection)
+			.await?;
+
+		if nb_of_classes + i64::try_from(classes.len()).unwrap_or(i64::MAX)
+			> i64::from(constants::limits::MAX_CLASSES_PER_LEVEL)
+		{
+			ctx.shout(ctx.translate("classes_add-too-many-classes", None))
+				.await?;
+
+			return Ok(());
+		}
+
+		Some(target)
+	} else {
+		None
+	};
+
+	let class_ids = classes.iter().map(|class| class.id).collect::<Vec<_>>();
+	let members = removal::affected_members(&mut connection, &class_ids).await?;
+
+	if classes.is_empty() {
+		ctx.defer_ephemeral().await?;
+	} else {
+		let warning = target.as_ref().map_or_else(
+			|| {
+				ctx.translate(
+					"levels_remove-confirm-delete",
+					Some(fluent_args![
+						"level" => name.as_str(),
+						"classes" => classes.len(),
+						"count" => members.len(),
+					]),
+				)
+			},
+			|target| {
+				ctx.translate(
+					"levels_remove-confirm-move",
+					Some(fluent_args![
+						"level" => name.as_str(),
+						"classes" => classes.len(),
+						"count" => members.len(),
+						"target" => target.name.as_str(),
+					]),
+				)
+			},
+		);
+
+		if !removal::confirm(
+			&ctx,
+			format!("{warning}\n{}", removal::preview(&ctx, &members)),
+		)
+		.await?
+		{
+			return Ok(());
+		}
 	}
 
-	diesel::delete(Level::with_id(id))
-		.execute(&mut ctx.data.database.get().await?)
+	let verified_roles = if target.is_none() {
+		removal::verified_roles(&mut connection, guild_id, &members).await?
+	} else {
+		HashMap::new()
+	};
+
+	// The roles are deleted last, a failure rolls the database changes back
+	let refresh_tokens = connection
+		.transaction::<_, InteractionError, _>(|connection| {
+			async {
+				let mut refresh_tokens = Vec::new();
+				if let Some(target) = &target {
+					diesel::update(Class::all_from_level(level.id))
+						.set(schema::classes::level_id.eq(target.id))
+						.execute(connection)
+						.await?;
+				} else {
+					refresh_tokens = removal::unverify(connection, &members).await?;
+
+					diesel::delete(Class::all_from_level(level.id))
+						.execute(connection)
+						.await?;
+				}
+
+				diesel::delete(Level::with_id(level.id))
+					.execute(connection)
+					.await?;
+
+				if target.is_none() {
+					for class in &classes {
+						removal::delete_role(ctx.http(), guild_id, RoleId::new(class.role_id))
+							.await?;
+					}
+				}
+				removal::delete_role(ctx.http(), guild_id, RoleId::new(level.role_id)).await?;
+
+				Ok(refresh_tokens)
+			}
+			.scope_boxed()
+		})
 		.await?;
 
+	if let Some(target) = &target {
+		let user_ids = members
+			.iter()
+			.map(|member| member.user_id)
+			.collect::<Vec<_>>();
+
+		swap_role(
+			ctx.http(),
+			guild_id,
+			&user_ids,
+			(None, Some(RoleId::new(target.role_id))),
+		)
+		.await?;
+	} else {
+		removal::strip_roles(ctx.http(), guild_id, verified_roles).await?;
+		removal::unverified(&ctx, &members, refresh_tokens, &name).await;
+	}
+
+	ctx.shout(ctx.translate(
+		"levels_remove-success",
+		Some(fluent_args![
+			"level" => name,
+			"classes" => classes.len(),
+			"count" => members.len(),
+		]),
+	))
+	.await?;
+
 	Ok(())
 }
 
@@ -0,0 +1,307 @@
+//! Get the verified members out of the bot
+
+use crate::{
+	commands::{
+		classes::autocomplete_classes, groups::autocomplete_groups, levels::autocomplete_levels,
+	},
+	csv,
+	database::{
+		models::{Group, VerifiedMember},
+		prelude::*,
+		schema::{classes, groups, groups_of_verified_members, levels, members, verified_members},
+	},
+	states::{ApplicationContext, ApplicationContextPolyfill, InteractionResult},
+	translation::Translate,
+};
+use anyhow::Context as _;
+use fluent::fluent_args;
+use poise::{
+	command,
+	serenity_prelude::{CreateAttachment, CreateEmbed},
+	ChoiceParameter, CreateReply,
+};
+use serde::Serialize;
+use std::{collections::BTreeMap, collections::HashMap, fmt::Write as _};
+
+/// The length after which the list of duplicates is cut, embed descriptions are limited to 4096 characters
+const MAX_DUPLICATES_LENGTH: usize = 4000;
+
+/// Export the verified members
+#[allow(clippy::unused_async)]
+#[command(
+	slash_command,
+	subcommands("members_export", "members_duplicates"),
+	default_member_permissions = "MANAGE_GUILD"
+)]
+pub(crate) async fn members(_: ApplicationContext<'_>) -> InteractionResult {
+	Ok(())
+}
+
+/// The format of an export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ChoiceParameter)]
+pub(crate) enum ExportFormat {
+	/// Opened by spreadsheets
+	#[default]
+	#[name = "csv"]
+	Csv,
+	/// Read by scripts
+	#[name = "json"]
+	Json,
+}
+
+/// A verified member as exported
+#[derive(Debug, Serialize)]
+struct ExportedMember {
+	/// The `Discord` user id
+	discord_id: String,
+	/// The `Discord` username
+	username: String,
+	/// The verified mail
+	mail: String,
+	/// The first name given by the identity provider or the roster
+	first_name: String,
+	/// The last name given by the identity provider or the roster
+	last_name: String,
+	/// `student`, `teacher` or `staff`
+	member_kind: String,
+	/// The level name, students only
+	level: Option<String>,
+	/// The class name, students only
+	class: Option<String>,
+	/// The names of the groups the member joined
+	groups: Vec<String>,
+	/// The `Discord` user id of the admin who verified the member by hand
+	verified_by: Option<String>,
+}
+
+impl ExportedMember {
+	/// The CSV header, in the same order as [`Self::write_csv_record`]
+	const CSV_HEADER: [&'static str; 10] = [
+		"discord_id",
+		"username",
+		"mail",
+		"first_name",
+		"last_name",
+		"member_kind",
+		"level",
+		"class",
+		"groups",
+		"verified_by",
+	];
+
+	/// Write the member as a CSV record, groups are separated by `;`
+	fn write_csv_record(&self, output: &mut String) {
+		let groups = self.groups.join(";");
+
+		csv::write_record(
+			output,
+			[
+				self.discord_id.as_str(),
+				&self.username,
+				&self.mail,
+				&self.first_name,
+				&self.last_name,
+				&self.member_kind,
+				self.level.as_deref().unwrap_or_default(),
+				self.class.as_deref().unwrap_or_default(),
+				&groups,
+				self.verified_by.as_deref().unwrap_or_default(),
+			],
+		);
+	}
+}
+
+/// Export the verified members as a CSV or JSON file.
+#[command(slash_command, guild_only, rename = "export")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn members_export(
+	ctx: ApplicationContext<'_>,
+	format: Option<ExportFormat>,
+	#[autocomplete = "autocomplete_levels"] level: Option<String>,
+	#[autocomplete = "autocomplete_classes"] class: Option<String>,
+	#[autocomplete = "autocomplete_groups"] group: Option<String>,
+) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+	let mut connection = ctx.data.database.get().await?;
+
+	ctx.defer_ephemeral().await?;
+
+	let mut query = verified_members::table
+		.inner_join(members::table)
+		.left_join(classes::table.inner_join(levels::table))
+		.filter(members::guild_id.eq(guild_id.get()))
+		.select((
+			verified_members::member_id,
+			members::discord_id,
+			members::username,
+			VerifiedMember::as_select(),
+			levels::name.nullable(),
+			classes::name.nullable(),
+		))
+		.order_by(members::username)
+		.into_boxed();
+
+	if let Some(level) = &level {
+		query = query.filter(levels::name.nullable().eq(level));
+	}
+	if let Some(class) = &class {
+		query = query.filter(classes::name.nullable().eq(class));
+	}
+	if let Some(group) = &group {
+		let Some(group_id) = Group::all_from_guild(guild_id)
+			.filter(groups::name.eq(group))
+			.select(groups::id)
+			.first::<i32>(&mut connection)
+			.await
+			.optional()?
+		else {
+			ctx.shout(ctx.translate(
+				"members_export-no-such-group",
+				Some(fluent_args!["group" => group.as_str()]),
+			))
+			.await?;
+
+			return Ok(());
+		};
+
+		query = query.filter(
+			verified_members::member_id.eq_any(
+				groups_of_verified_members::table
+					.filter(groups_of_verified_members::group_id.eq(group_id))
+					.select(groups_of_verified_members::verified_member_id),
+			),
+		);
+	}
+
+	let rows = query
+		.load::<(
+			i32,
+			u64,
+			String,
+			VerifiedMember,
+			Option<String>,
+			Option<String>,
+		)>(&mut connection)
+		.await?;
+
+	let mut groups_of_members = HashMap::<i32, Vec<String>>::new();
+	for (verified_member_id, group_name) in groups_of_verified_members::table
+		.inner_join(groups::table)
+		.filter(groups::guild_id.eq(guild_id.get()))
+		.select((groups_of_verified_members::verified_member_id, groups::name))
+		.load::<(i32, String)>(&mut connection)
+		.await?
+	{
+		groups_of_members
+			.entry(verified_member_id)
+			.or_default()
+			.push(group_name);
+	}
+
+	let exported_members = rows
+		.into_iter()
+		.map(
+			|(member_id, discord_id, username, verified_member, level, class)| ExportedMember {
+				discord_id: discord_id.to_string(),
+				username,
+				mail: verified_member.mail,
+				first_name: verified_member.first_name,
+				last_name: verified_member.last_name,
+				member_kind: verified_member.member_kind,
+				level,
+				class,
+				groups: groups_of_members.remove(&member_id).unwrap_or_default(),
+				verified_by: verified_member.verified_by.map(|id| id.to_string()),
+			},
+		)
+		.collect::<Vec<_>>();
+
+	let attachment = match format.unwrap_or_default() {
+		ExportFormat::Csv => {
+			let mut output = String::new();
+			csv::write_record(&mut output, ExportedMember::CSV_HEADER);
+			for exported_member in &exported_members {
+				exported_member.write_csv_record(&mut output);
+			}
+
+			CreateAttachment::bytes(output, "members.csv")
+		}
+		ExportFormat::Json => CreateAttachment::bytes(
+			serde_json::to_vec_pretty(&exported_members).context("could not serialize members")?,
+			"members.json",
+		),
+	};
+
+	ctx.send(
+		CreateReply::default()
+			.ephemeral(true)
+			.content(ctx.translate(
+				"members_export-done",
+				Some(fluent_args!["count" => exported_members.len()]),
+			))
+			.attachment(attachment),
+	)
+	.await?;
+
+	Ok(())
+}
+
+/// List the Discord accounts verified with the same mail.
+#[command(slash_command, guild_only, rename = "duplicates")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn members_duplicates(ctx: ApplicationContext<'_>) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+
+	let mut accounts_by_mail = BTreeMap::<String, Vec<u64>>::new();
+	for (mail, discord_id) in verified_members::table
+		.inner_join(members::table)
+		.filter(members::guild_id.eq(guild_id.get()))
+		// Manual verifications have no mail yet
+		.filter(verified_members::mail.ne(""))
+		.select((verified_members::mail, members::discord_id))
+		.load::<(String, u64)>(&mut ctx.data.database.get().await?)
+		.await?
+	{
+		accounts_by_mail
+			.entry(mail.to_lowercase())
+			.or_default()
+			.push(discord_id);
+	}
+	accounts_by_mail.retain(|_, accounts| accounts.len() > 1);
+
+	if accounts_by_mail.is_empty() {
+		ctx.shout(ctx.translate("members_duplicates-none", None))
+			.await?;
+
+		return Ok(());
+	}
+
+	let mut description = String::new();
+	for (mail, accounts) in &accounts_by_mail {
+		let line = accounts.iter().fold(format!("`{mail}`:"), |mut line, id| {
+			let _ = write!(line, " <@{id}>");
+			line
+		});
+
+		if description.len() + line.len() >= MAX_DUPLICATES_LENGTH {
+			description.push('…');
+			break;
+		}
+		let _ = writeln!(description, "{line}");
+	}
+
+	ctx.send(
+		CreateReply::default().ephemeral(true).embed(
+			CreateEmbed::new()
+				.title(ctx.translate(
+					"members_duplicates-title",
+					Some(fluent_args!["count" => accounts_by_mail.len()]),
+				))
+				.description(description)
+				.color(0x00FF_0000),
+		),
+	)
+	.await?;
+
+	Ok(())
+}
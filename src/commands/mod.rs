@@ -9,17 +9,39 @@ use fluent::fluent_args;
 use poise::{serenity_prelude, BoxFuture};
 use uuid::Uuid;
 
+mod audit;
+mod bound_roles;
 mod classes;
 mod groups;
 mod information;
 mod levels;
+mod members;
+mod privacy;
+mod profile;
+mod removal;
+mod rewards;
+mod roster;
 mod setup;
+mod trust;
+mod verify;
+mod xp;
+mod year;
 
+pub(crate) use audit::audit;
 pub(crate) use classes::classes;
 pub(crate) use groups::groups;
 pub(crate) use information::information;
 pub(crate) use levels::levels;
+pub(crate) use members::members;
+pub(crate) use privacy::privacy;
+pub(crate) use profile::profile;
+pub(crate) use rewards::rewards;
+pub(crate) use roster::roster;
 pub(crate) use setup::setup;
+pub(crate) use trust::trust;
+pub(crate) use verify::verify;
+pub(crate) use xp::{leaderboard, rank};
+pub(crate) use year::year;
 pub(crate) mod helpers;
 
 /// Execute before each command
@@ -0,0 +1,413 @@
+//! Let users see and delete what the bot stores about them
+
+use super::removal::confirm;
+use crate::{
+	audit::{self, AuditEntry, AuditKind},
+	database::{
+		models::{AuditEvent, Identity, Member, VerifiedMember},
+		prelude::*,
+		schema::{
+			audit_events, class_change_requests, classes, groups, groups_of_verified_members,
+			guilds, identities, levels, mail_claims, members, pending_logins, trust_groups,
+		},
+	},
+	states::{ApplicationContext, ApplicationContextPolyfill, InteractionResult},
+	translation::Translate,
+	verification::unlink::unlink,
+};
+use chrono::NaiveDateTime;
+use fluent::fluent_args;
+use poise::{
+	command,
+	serenity_prelude::{CreateAttachment, CreateMessage, GuildId},
+};
+use serde::Serialize;
+
+/// Everything stored about a user
+#[derive(Debug, Serialize)]
+struct Export {
+	/// The `Discord` id of the user
+	user_id: u64,
+	/// The guilds the user is a member of
+	guilds: Vec<GuildExport>,
+	/// The accounts shared with the guilds of a trust group
+	identities: Vec<IdentityExport>,
+	/// The logins started but not finished yet
+	pending_logins: Vec<PendingLoginExport>,
+}
+
+/// What is stored about a user in a guild
+#[derive(Debug, Serialize)]
+struct GuildExport {
+	/// The id of the guild
+	guild_id: u64,
+	/// The name of the guild
+	guild_name: String,
+	/// The username when the user joined
+	username: String,
+	/// The XP earned with messages
+	message_xp: i32,
+	/// The XP earned in voice channels
+	vocal_xp: i32,
+	/// The verification, if the user is verified
+	verification: Option<VerificationExport>,
+	/// The mails claimed in the guild, so that no one else uses them
+	mail_claims: Vec<String>,
+	/// The events of the audit log done by or to the user
+	audit_events: Vec<AuditEventExport>,
+}
+
+/// What is stored about a verified member
+#[derive(Debug, Serialize)]
+struct VerificationExport {
+	/// The mail of the account used to verify
+	mail: String,
+	/// The first name given by the provider or the roster
+	first_name: String,
+	/// The last name given by the provider or the roster
+	last_name: String,
+	/// Whether the member is a student, a teacher or a staff member
+	member_kind: String,
+	/// The level and class of a student
+	class: Option<(String, String)>,
+	/// The level and class asked for, waiting for the approval of an admin
+	requested_class: Option<(String, String)>,
+	/// The groups joined
+	groups: Vec<String>,
+	/// When the member last verified themselves, in RFC 3339
+	verified_at: String,
+	/// Whether an encrypted refresh token is stored, the token itself is never exported
+	has_refresh_token: bool,
+	/// The `Discord` id of the admin who verified the member by hand
+	verified_by: Option<u64>,
+}
+
+/// What is stored about an account shared in a trust group
+#[derive(Debug, Serialize)]
+struct IdentityExport {
+	/// The name of the trust group
+	trust_group_name: String,
+	/// The mail of the account
+	mail: String,
+	/// The first name given by the provider or the roster
+	first_name: String,
+	/// The last name given by the provider or the roster
+	last_name: String,
+	/// The domain the provider vouched for
+	domain: Option<String>,
+	/// The provider of the account
+	provider: String,
+	/// When the account was last proven, in RFC 3339
+	verified_at: String,
+}
+
+/// What is stored about an event of the audit log
+#[derive(Debug, Serialize)]
+struct AuditEventExport {
+	/// What happened
+	kind: String,
+	/// The `Discord` id of the user who did it
+	actor_id: Option<u64>,
+	/// The `Discord` id of the user it was done to
+	target_id: Option<u64>,
+	/// What the event is about
+	details: Option<String>,
+	/// When it happened, in RFC 3339
+	created_at: String,
+}
+
+/// What is stored about a login that is not finished yet
+///
+/// The state, the nonce and the interaction token are secrets of the login and are not exported
+#[derive(Debug, Serialize)]
+struct PendingLoginExport {
+	/// The id of the guild
+	guild_id: u64,
+	/// The username when the login started
+	username: String,
+	/// The provider of the account
+	identity_provider: String,
+	/// Whether the user said they are a student, a teacher or a staff member
+	member_kind: String,
+	/// When the login expires, in RFC 3339
+	expires_at: String,
+}
+
+/// See or delete what the bot stores about you
+#[allow(clippy::unused_async)]
+#[command(slash_command, subcommands("privacy_export", "privacy_erase"))]
+pub(crate) async fn privacy(_: ApplicationContext<'_>) -> InteractionResult {
+	Ok(())
+}
+
+/// Receive in direct message everything the bot stores about you
+#[command(slash_command, rename = "export")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn privacy_export(ctx: ApplicationContext<'_>) -> InteractionResult {
+	let user_id = ctx.interaction.user.id;
+
+	ctx.defer_ephemeral().await?;
+
+	let mut connection = ctx.data.database.get().await?;
+
+	let mut export = Export {
+		user_id: user_id.get(),
+		guilds: Vec::new(),
+		identities: Vec::new(),
+		pending_logins: Vec::new(),
+	};
+
+	for (member, guild_name) in members::table
+		.inner_join(guilds::table)
+		.filter(members::discord_id.eq(user_id.get()))
+		.select((members::all_columns, guilds::name))
+		.load::<(Member, String)>(&mut connection)
+		.await?
+	{
+		let verified_member = VerifiedMember::from_member_id(member.id)
+			.first::<VerifiedMember>(&mut connection)
+			.await
+			.optional()?;
+
+		let verification = match verified_member {
+			Some(verified_member) => {
+				let class = match verified_member.class_id {
+					Some(class_id) => Some(
+						classes::table
+							.inner_join(levels::table)
+							.filter(classes::id.eq(class_id))
+							.select((levels::name, classes::name))
+							.first::<(String, String)>(&mut connection)
+							.await?,
+					),
+					None => None,
+				};
+
+				let requested_class = class_change_requests::table
+					.inner_join(classes::table.inner_join(levels::table))
+					.filter(class_change_requests::member_id.eq(member.id))
+					.select((levels::name, classes::name))
+					.first::<(String, String)>(&mut connection)
+					.await
+					.optional()?;
+
+				let groups = groups_of_verified_members::table
+					.inner_join(groups::table)
+					.filter(groups_of_verified_members::verified_member_id.eq(member.id))
+					.select(groups::name)
+					.load::<String>(&mut connection)
+					.await?;
+
+				Some(VerificationExport {
+					mail: verified_member.mail,
+					first_name: verified_member.first_name,
+					last_name: verified_member.last_name,
+					member_kind: verified_member.member_kind,
+					class,
+					requested_class,
+					groups,
+					verified_at: verified_member.verified_at.and_utc().to_rfc3339(),
+					has_refresh_token: verified_member.refresh_token.is_some(),
+					verified_by: verified_member.verified_by,
+				})
+			}
+			None => None,
+		};
+
+		let mail_claims = mail_claims::table
+			.filter(mail_claims::member_id.eq(member.id))
+			.select(mail_claims::mail)
+			.load::<String>(&mut connection)
+			.await?;
+
+		let audit_events = AuditEvent::all_from_guild(GuildId::new(member.guild_id))
+			.filter(
+				audit_events::actor_id
+					.eq(user_id.get())
+					.or(audit_events::target_id.eq(user_id.get())),
+			)
+			.order_by(audit_events::created_at)
+			.load::<AuditEvent>(&mut connection)
+			.await?
+			.into_iter()
+			.map(|event| AuditEventExport {
+				kind: event.kind,
+				actor_id: event.actor_id,
+				target_id: event.target_id,
+				details: event.details,
+				created_at: event.created_at.and_utc().to_rfc3339(),
+			})
+			.collect();
+
+		export.guilds.push(GuildExport {
+			guild_id: member.guild_id,
+			guild_name,
+			username: member.username,
+			message_xp: member.message_xp,
+			vocal_xp: member.vocal_xp,
+			verification,
+			mail_claims,
+			audit_events,
+		});
+	}
+
+	for (identity, trust_group_name) in identities::table
+		.inner_join(trust_groups::table)
+		.filter(identities::discord_id.eq(user_id.get()))
+		.select((identities::all_columns, trust_groups::name))
+		.load::<(Identity, String)>(&mut connection)
+		.await?
+	{
+		export.identities.push(IdentityExport {
+			trust_group_name,
+			mail: identity.mail,
+			first_name: identity.first_name,
+			last_name: identity.last_name,
+			domain: identity.domain,
+			provider: identity.provider,
+			verified_at: identity.verified_at.and_utc().to_rfc3339(),
+		});
+	}
+
+	for (guild_id, username, identity_provider, member_kind, expires_at) in pending_logins::table
+		.filter(pending_logins::discord_id.eq(user_id.get()))
+		.select((
+			pending_logins::guild_id,
+			pending_logins::username,
+			pending_logins::identity_provider,
+			pending_logins::member_kind,
+			pending_logins::expires_at,
+		))
+		.load::<(u64, String, String, String, NaiveDateTime)>(&mut connection)
+		.await?
+	{
+		export.pending_logins.push(PendingLoginExport {
+			guild_id,
+			username,
+			identity_provider,
+			member_kind,
+			expires_at: expires_at.and_utc().to_rfc3339(),
+		});
+	}
+
+	let json = serde_json::to_vec_pretty(&export).map_err(anyhow::Error::from)?;
+
+	let message = CreateMessage::new()
+		.content(ctx.translate("privacy_export-message", None))
+		.add_file(CreateAttachment::bytes(json, "data.json"));
+
+	let key = match user_id.direct_message(ctx, message).await {
+		Ok(_) => "privacy_export-done",
+		Err(error) => {
+			tracing::warn!(error = ?error, "could not send the export");
+
+			"privacy_export-dm-closed"
+		}
+	};
+
+	ctx.shout(ctx.translate(key, None)).await?;
+
+	Ok(())
+}
+
+/// Delete your verifications, groups and XP in every guild
+#[command(slash_command, rename = "erase")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn privacy_erase(ctx: ApplicationContext<'_>) -> InteractionResult {
+	let user_id = ctx.interaction.user.id;
+
+	if !confirm(&ctx, ctx.translate("privacy_erase-confirm", None)).await? {
+		return Ok(());
+	}
+
+	let mut connection = ctx.data.database.get().await?;
+
+	let guild_ids = members::table
+		.filter(members::discord_id.eq(user_id.get()))
+		.select(members::guild_id)
+		.load::<u64>(&mut connection)
+		.await?;
+
+	// A guild that fails must not keep the data of the other guilds
+	let mut failed_guilds = 0;
+	for guild_id in guild_ids.into_iter().map(GuildId::new) {
+		if let Err(error) = unlink(
+			ctx.data,
+			&mut connection,
+			(guild_id, user_id),
+			Some(user_id),
+		)
+		.await
+		{
+			tracing::warn!(
+				guild_id = guild_id.get(),
+				error = ?error,
+				"could not erase the verification"
+			);
+			failed_guilds += 1;
+
+			continue;
+		}
+
+		audit::record(
+			ctx.data,
+			guild_id,
+			AuditEntry::new(AuditKind::DataErased)
+				.actor(user_id)
+				.target(user_id),
+		)
+		.await;
+	}
+
+	// Claims left by logins that failed after the mail was claimed
+	diesel::delete(
+		mail_claims::table.filter(
+			mail_claims::member_id.eq_any(
+				members::table
+					.filter(members::discord_id.eq(user_id.get()))
+					.select(members::id),
+			),
+		),
+	)
+	.execute(&mut connection)
+	.await?;
+
+	diesel::delete(identities::table.filter(identities::discord_id.eq(user_id.get())))
+		.execute(&mut connection)
+		.await?;
+
+	diesel::delete(pending_logins::table.filter(pending_logins::discord_id.eq(user_id.get())))
+		.execute(&mut connection)
+		.await?;
+
+	// The events are kept for the admins, but their details can hold the mail or the name,
+	// even in the events of other accounts, e.g. when they share the mail of the user
+	diesel::update(
+		audit_events::table.filter(
+			audit_events::target_id
+				.eq(user_id.get())
+				.or(audit_events::details.like(format!("%<@{user_id}>%"))),
+		),
+	)
+	.set(audit_events::details.eq(None::<String>))
+	.execute(&mut connection)
+	.await?;
+
+	// The membership itself is kept, it is needed to verify again
+	diesel::update(members::table.filter(members::discord_id.eq(user_id.get())))
+		.set((members::message_xp.eq(0), members::vocal_xp.eq(0)))
+		.execute(&mut connection)
+		.await?;
+
+	if failed_guilds == 0 {
+		ctx.shout(ctx.translate("privacy_erase-done", None)).await?;
+	} else {
+		ctx.shout(ctx.translate(
+			"privacy_erase-partial",
+			Some(fluent_args!["count" => failed_guilds]),
+		))
+		.await?;
+	}
+
+	Ok(())
+}
@@ -0,0 +1,133 @@
+//! Let verified members see what the bot knows about them and change their class or groups
+
+use crate::{
+	commands::setup::groups_message_components,
+	constants,
+	database::{
+		models::{Group, Guild, VerifiedMember},
+		prelude::*,
+		schema::{class_change_requests, groups, groups_of_verified_members, guilds},
+	},
+	states::{ApplicationContext, ApplicationContextPolyfill, InteractionResult},
+	translation::Translate,
+	verification::{
+		class_change::{class_from_roster, class_label},
+		member_kind::MemberKind,
+	},
+};
+use poise::{
+	command,
+	serenity_prelude::{ButtonStyle, CreateActionRow, CreateButton, CreateEmbed},
+	CreateReply,
+};
+
+/// Show your verification in this guild, and change your class or your groups
+#[command(slash_command, guild_only)]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn profile(ctx: ApplicationContext<'_>) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+
+	let mut connection = ctx.data.database.get().await?;
+
+	let Some(verified_member) = VerifiedMember::with_ids(ctx.interaction.user.id, guild_id)
+		.select(VerifiedMember::as_select())
+		.first::<VerifiedMember>(&mut connection)
+		.await
+		.optional()?
+	else {
+		ctx.shout(ctx.translate("profile-not-verified", None))
+			.await?;
+
+		return Ok(());
+	};
+
+	let member_kind = verified_member.member_kind.parse::<MemberKind>()?;
+
+	let mut embed = CreateEmbed::new()
+		.title(format!(
+			"{} {}",
+			verified_member.first_name, verified_member.last_name
+		))
+		.color(0x0000_FF00)
+		.field(
+			ctx.translate("profile-kind", None),
+			ctx.translate(member_kind.translation_key(), None),
+			true,
+		);
+
+	if let Some(class_id) = verified_member.class_id {
+		embed = embed.field(
+			ctx.translate("profile-class", None),
+			class_label(&mut connection, class_id).await?,
+			true,
+		);
+	}
+
+	if let Some(requested_class_id) = class_change_requests::table
+		.find(verified_member.member_id)
+		.select(class_change_requests::class_id)
+		.first::<i32>(&mut connection)
+		.await
+		.optional()?
+	{
+		embed = embed.field(
+			ctx.translate("profile-requested-class", None),
+			class_label(&mut connection, requested_class_id).await?,
+			true,
+		);
+	}
+
+	let group_names = groups_of_verified_members::table
+		.inner_join(groups::table)
+		.filter(groups_of_verified_members::verified_member_id.eq(verified_member.member_id))
+		.select(groups::name)
+		.load::<String>(&mut connection)
+		.await?;
+	embed = embed.field(
+		ctx.translate("profile-groups", None),
+		if group_names.is_empty() {
+			"-".to_owned()
+		} else {
+			group_names.join(", ")
+		},
+		false,
+	);
+
+	let mut components = Vec::new();
+
+	let class_change_approval = Guild::with_id(guild_id)
+		.select(guilds::class_change_approval)
+		.first::<bool>(&mut connection)
+		.await?;
+
+	// The students of the roster can only ask the admins to change their class
+	if member_kind.has_class()
+		&& (class_change_approval
+			|| !class_from_roster(&mut connection, guild_id, &verified_member.mail).await?)
+	{
+		components.push(CreateActionRow::Buttons(vec![CreateButton::new(
+			constants::events::CHANGE_CLASS_BUTTON_INTERACTION,
+		)
+		.label(ctx.translate("event-setup-change-class-button", None))
+		.style(ButtonStyle::Secondary)]));
+	}
+
+	// Choosing a group joins it or leaves it, like on the groups message
+	let groups: Vec<Group> = Group::all_from_guild(guild_id)
+		.filter(groups::broken.eq(false))
+		.load(&mut connection)
+		.await?;
+	if !groups.is_empty() {
+		components.extend(groups_message_components(&ctx, &groups));
+	}
+
+	ctx.send(
+		CreateReply::default()
+			.ephemeral(true)
+			.embed(embed)
+			.components(components),
+	)
+	.await?;
+
+	Ok(())
+}
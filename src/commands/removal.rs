@@ -0,0 +1,276 @@
+//! Remove classes and levels that still have members
+//!
+//! The affected members are previewed and the removal must be confirmed, they are then moved to another class or unverified
+
+use crate::{
+	audit::{self, AuditEntry, AuditKind},
+	constants::events,
+	database::{
+		models::{Guild, GuildEmailRule},
+		prelude::*,
+		schema::{
+			groups, groups_of_verified_members, guild_email_rules, guilds, members,
+			verified_members,
+		},
+		DatabasePooledConnection,
+	},
+	states::{ApplicationContext, ApplicationContextPolyfill, InteractionError},
+	translation::Translate,
+	verification::{
+		is_unknown_role, offline_access::revoke_refresh_token, swap_role, unlink::forget,
+	},
+};
+use diesel_async::AsyncMysqlConnection;
+use fluent::fluent_args;
+use poise::{
+	serenity_prelude::{
+		self as serenity, ButtonStyle, ComponentInteractionCollector, CreateActionRow,
+		CreateButton, GuildId, Http, RoleId, UserId,
+	},
+	CreateReply,
+};
+use std::{collections::HashMap, time::Duration};
+
+/// A stored refresh token and the provider that gave it
+pub(super) type RefreshToken = (Option<Vec<u8>>, Option<String>);
+
+/// The number of members mentioned in a removal preview
+const MAX_PREVIEWED_MEMBERS: usize = 20;
+
+/// A verified member of a class that is about to be removed or rolled over
+#[derive(Debug, Clone, Copy)]
+pub(super) struct AffectedMember {
+	/// The id of the [`crate::database::models::VerifiedMember`]
+	pub(super) member_id: i32,
+	/// The `Discord` user id
+	pub(super) user_id: UserId,
+}
+
+/// Get the verified members of the given classes
+pub(super) async fn affected_members(
+	connection: &mut DatabasePooledConnection,
+	class_ids: &[i32],
+) -> Result<Vec<AffectedMember>, DieselError> {
+	let members = verified_members::table
+		.inner_join(members::table)
+		.filter(verified_members::class_id.eq_any(class_ids))
+		.select((verified_members::member_id, members::discord_id))
+		.load::<(i32, u64)>(connection)
+		.await?
+		.into_iter()
+		.map(|(member_id, discord_id)| AffectedMember {
+			member_id,
+			user_id: UserId::new(discord_id),
+		})
+		.collect();
+
+	Ok(members)
+}
+
+/// Mention the first affected members
+pub(super) fn preview(ctx: &ApplicationContext<'_>, members: &[AffectedMember]) -> String {
+	let mut preview = members
+		.iter()
+		.take(MAX_PREVIEWED_MEMBERS)
+		.map(|member| format!("<@{}>", member.user_id))
+		.collect::<Vec<_>>()
+		.join(", ");
+
+	if members.len() > MAX_PREVIEWED_MEMBERS {
+		preview.push(' ');
+		preview.push_str(&ctx.translate(
+			"removal-preview-more",
+			Some(fluent_args!["count" => members.len() - MAX_PREVIEWED_MEMBERS]),
+		));
+	}
+
+	preview
+}
+
+/// Ask the caller to confirm a removal, returns whether it was confirmed
+pub(super) async fn confirm(
+	ctx: &ApplicationContext<'_>,
+	content: String,
+) -> Result<bool, InteractionError> {
+	let action_row = CreateActionRow::Buttons(vec![
+		CreateButton::new(events::REMOVAL_CONFIRM_BUTTON_INTERACTION)
+			.label(ctx.translate("removal-confirm-button", None))
+			.style(ButtonStyle::Danger),
+		CreateButton::new(events::REMOVAL_CANCEL_BUTTON_INTERACTION)
+			.label(ctx.translate("cancel", None))
+			.style(ButtonStyle::Secondary),
+	]);
+
+	let reply = ctx
+		.send(
+			CreateReply::default()
+				.ephemeral(true)
+				.content(&content)
+				.components(vec![action_row]),
+		)
+		.await?;
+
+	let Some(interaction) = ComponentInteractionCollector::new(ctx)
+		.message_id(reply.message().await?.id)
+		.timeout(Duration::from_mins(1))
+		.await
+	else {
+		reply
+			.edit(
+				poise::Context::Application(*ctx),
+				CreateReply::default()
+					.content(ctx.translate("error-user-timeout", None))
+					.components(Vec::new()),
+			)
+			.await?;
+
+		return Ok(false);
+	};
+
+	interaction.defer(ctx).await?;
+
+	let confirmed = interaction.data.custom_id == events::REMOVAL_CONFIRM_BUTTON_INTERACTION;
+
+	reply
+		.edit(
+			poise::Context::Application(*ctx),
+			CreateReply::default()
+				.content(if confirmed {
+					content
+				} else {
+					ctx.translate("removal-cancelled", None)
+				})
+				.components(Vec::new()),
+		)
+		.await?;
+
+	Ok(confirmed)
+}
+
+/// Get the roles given by the verification to the affected members, to strip them once they are unverified
+///
+/// Must be called before [`unverify`], the groups of the members are forgotten afterwards
+pub(super) async fn verified_roles(
+	connection: &mut DatabasePooledConnection,
+	guild_id: GuildId,
+	members: &[AffectedMember],
+) -> Result<HashMap<RoleId, Vec<UserId>>, DieselError> {
+	let user_ids = members
+		.iter()
+		.map(|member| member.user_id)
+		.collect::<Vec<_>>();
+
+	let mut role_ids: Vec<Option<u64>> = GuildEmailRule::all_from_guild(guild_id)
+		.select(guild_email_rules::role_id)
+		.get_results(connection)
+		.await?;
+	role_ids.push(
+		Guild::with_id(guild_id)
+			.select(guilds::verified_role_id)
+			.first(connection)
+			.await?,
+	);
+
+	let mut roles = role_ids
+		.into_iter()
+		.flatten()
+		.map(|role_id| (RoleId::new(role_id), user_ids.clone()))
+		.collect::<HashMap<_, _>>();
+
+	let member_ids = members
+		.iter()
+		.map(|member| member.member_id)
+		.collect::<Vec<_>>();
+	for (role_id, discord_id) in groups_of_verified_members::table
+		.inner_join(groups::table)
+		.inner_join(verified_members::table.inner_join(members::table))
+		.filter(groups_of_verified_members::verified_member_id.eq_any(&member_ids))
+		.select((groups::role_id, members::discord_id))
+		.load::<(u64, u64)>(connection)
+		.await?
+	{
+		roles
+			.entry(RoleId::new(role_id))
+			.or_default()
+			.push(UserId::new(discord_id));
+	}
+
+	Ok(roles)
+}
+
+/// Forget the verification of the affected members, meant to run in a transaction
+///
+/// Returns their refresh tokens, to revoke with [`unverified`] once the transaction is committed
+pub(super) async fn unverify(
+	connection: &mut AsyncMysqlConnection,
+	members: &[AffectedMember],
+) -> Result<Vec<RefreshToken>, DieselError> {
+	let member_ids = members
+		.iter()
+		.map(|member| member.member_id)
+		.collect::<Vec<_>>();
+
+	let refresh_tokens = verified_members::table
+		.filter(verified_members::member_id.eq_any(&member_ids))
+		.filter(verified_members::refresh_token.is_not_null())
+		.select((
+			verified_members::refresh_token,
+			verified_members::token_provider,
+		))
+		.load::<RefreshToken>(connection)
+		.await?;
+
+	forget(connection, &member_ids).await?;
+
+	Ok(refresh_tokens)
+}
+
+/// Revoke the refresh tokens of the members forgotten by [`unverify`] and record their unverification
+pub(super) async fn unverified(
+	ctx: &ApplicationContext<'_>,
+	members: &[AffectedMember],
+	refresh_tokens: Vec<RefreshToken>,
+	details: &str,
+) {
+	for refresh_token in refresh_tokens {
+		revoke_refresh_token(ctx.data, refresh_token).await;
+	}
+
+	for member in members {
+		audit::record(
+			ctx.data,
+			ctx.guild_only_id(),
+			AuditEntry::new(AuditKind::DataDeleted)
+				.actor(ctx.interaction.user.id)
+				.target(member.user_id)
+				.details(details),
+		)
+		.await;
+	}
+}
+
+/// Strip the roles collected by [`verified_roles`] from the unverified members
+pub(super) async fn strip_roles(
+	http: &Http,
+	guild_id: GuildId,
+	roles: HashMap<RoleId, Vec<UserId>>,
+) -> Result<(), serenity::Error> {
+	for (role_id, user_ids) in roles {
+		swap_role(http, guild_id, &user_ids, (Some(role_id), None)).await?;
+	}
+
+	Ok(())
+}
+
+/// Delete a role on `Discord`, ignoring roles that are already deleted
+pub(super) async fn delete_role(
+	http: &Http,
+	guild_id: GuildId,
+	role_id: RoleId,
+) -> Result<(), serenity::Error> {
+	match http.delete_role(guild_id, role_id, None).await {
+		Ok(()) => Ok(()),
+		Err(error) if is_unknown_role(&error) => Ok(()),
+		Err(error) => Err(error),
+	}
+}
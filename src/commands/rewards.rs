@@ -0,0 +1,202 @@
+//! Give roles to the members that earned enough XP
+
+use crate::{
+	constants,
+	database::{
+		models::{Guild, NewXpReward, XpReward},
+		prelude::*,
+		schema::{guilds, xp_rewards},
+	},
+	states::{ApplicationContext, ApplicationContextPolyfill, InteractionResult},
+	translation::Translate,
+	xp::rewards::backfill_rewards,
+};
+use fluent::fluent_args;
+use poise::{
+	command,
+	serenity_prelude::{GuildChannel, Permissions, Role},
+};
+use std::fmt::Write as _;
+
+/// Add, remove or list the roles given for XP
+#[allow(clippy::unused_async)]
+#[command(
+	slash_command,
+	subcommands(
+		"rewards_add",
+		"rewards_remove",
+		"rewards_list",
+		"rewards_channel",
+		"rewards_backfill"
+	),
+	default_member_permissions = "MANAGE_ROLES",
+	required_bot_permissions = "MANAGE_ROLES"
+)]
+pub(crate) async fn rewards(_: ApplicationContext<'_>) -> InteractionResult {
+	Ok(())
+}
+
+/// Give a role to the members reaching an amount of XP
+#[command(slash_command, guild_only, rename = "add")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn rewards_add(
+	ctx: ApplicationContext<'_>,
+	#[min = 1] threshold: i32,
+	role: Role,
+) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+
+	if role.has_permission(Permissions::ADMINISTRATOR) {
+		ctx.shout(ctx.translate("setup_role-role-admin", None))
+			.await?;
+
+		return Ok(());
+	}
+
+	let mut connection = ctx.data.database.get().await?;
+
+	let updated = diesel::update(
+		XpReward::all_from_guild(guild_id).filter(xp_rewards::role_id.eq(role.id.get())),
+	)
+	.set(xp_rewards::threshold.eq(threshold))
+	.execute(&mut connection)
+	.await?;
+
+	if updated == 0 {
+		let nb_of_rewards: i64 = XpReward::all_from_guild(guild_id)
+			.count()
+			.get_result(&mut connection)
+			.await?;
+
+		if nb_of_rewards >= i64::from(constants::limits::MAX_XP_REWARDS_PER_GUILD) {
+			ctx.shout(ctx.translate(
+				"rewards_add-too-many",
+				Some(fluent_args!["max" => constants::limits::MAX_XP_REWARDS_PER_GUILD]),
+			))
+			.await?;
+
+			return Ok(());
+		}
+
+		NewXpReward {
+			guild_id: guild_id.get(),
+			threshold,
+			role_id: role.id.get(),
+		}
+		.insert()
+		.execute(&mut connection)
+		.await?;
+	}
+
+	ctx.shout(ctx.translate(
+		"rewards_add-success",
+		Some(fluent_args!["role" => role.name, "threshold" => threshold]),
+	))
+	.await?;
+
+	Ok(())
+}
+
+/// Stop giving a role for XP, the members keep it
+#[command(slash_command, guild_only, rename = "remove")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn rewards_remove(ctx: ApplicationContext<'_>, role: Role) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+
+	let deleted = diesel::delete(
+		XpReward::all_from_guild(guild_id).filter(xp_rewards::role_id.eq(role.id.get())),
+	)
+	.execute(&mut ctx.data.database.get().await?)
+	.await?;
+
+	let key = if deleted == 0 {
+		"rewards_remove-not-found"
+	} else {
+		"rewards_remove-success"
+	};
+
+	ctx.shout(ctx.translate(key, Some(fluent_args!["role" => role.name])))
+		.await?;
+
+	Ok(())
+}
+
+/// List the roles given for XP
+#[command(slash_command, guild_only, rename = "list")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn rewards_list(ctx: ApplicationContext<'_>) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+
+	let rewards = XpReward::all_from_guild(guild_id)
+		.order_by(xp_rewards::threshold)
+		.load::<XpReward>(&mut ctx.data.database.get().await?)
+		.await?;
+
+	if rewards.is_empty() {
+		ctx.shout(ctx.translate("rewards_list-none", None)).await?;
+
+		return Ok(());
+	}
+
+	let mut message = format!("**{}**:", ctx.translate("rewards_list-title", None));
+	for reward in rewards {
+		let _ = write!(
+			message,
+			"\n- {} XP: <@&{}>",
+			reward.threshold, reward.role_id
+		);
+	}
+
+	ctx.shout(message).await?;
+
+	Ok(())
+}
+
+/// Set the channel where level ups are announced, or stop announcing them
+#[command(slash_command, guild_only, rename = "channel")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn rewards_channel(
+	ctx: ApplicationContext<'_>,
+	#[channel_types("Text")] channel: Option<GuildChannel>,
+) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+
+	diesel::update(Guild::with_id(guild_id))
+		.set(guilds::level_up_channel_id.eq(channel.as_ref().map(|channel| channel.id.get())))
+		.execute(&mut ctx.data.database.get().await?)
+		.await?;
+
+	let content = channel.map_or_else(
+		|| ctx.translate("rewards_channel-disabled", None),
+		|channel| {
+			ctx.translate(
+				"rewards_channel-success",
+				Some(fluent_args!["channel" => format!("<#{}>", channel.id)]),
+			)
+		},
+	);
+
+	ctx.shout(content).await?;
+
+	Ok(())
+}
+
+/// Give or take the reward roles so that every member matches their XP
+#[command(slash_command, guild_only, rename = "backfill")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn rewards_backfill(ctx: ApplicationContext<'_>) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+
+	ctx.defer_ephemeral().await?;
+
+	let (given, taken) =
+		backfill_rewards(ctx.http(), &mut ctx.data.database.get().await?, guild_id).await?;
+
+	ctx.shout(ctx.translate(
+		"rewards_backfill-success",
+		Some(fluent_args!["given" => given, "taken" => taken]),
+	))
+	.await?;
+
+	Ok(())
+}
@@ -0,0 +1,343 @@
+//! Import the school roster to bind students to their class before they login
+
+use super::removal::delete_role;
+use crate::{
+	constants, csv,
+	database::{
+		models::{Class, Level, NewClass, NewLevel, NewRosterEntry},
+		prelude::*,
+		schema,
+	},
+	states::{ApplicationContext, ApplicationContextPolyfill, InteractionError, InteractionResult},
+	translation::Translate,
+};
+use diesel_async::{scoped_futures::ScopedFutureExt, AsyncConnection, AsyncMysqlConnection};
+use fluent::fluent_args;
+use poise::{
+	command,
+	serenity_prelude::{Attachment, EditRole, GuildId, Permissions, RoleId},
+};
+use std::collections::HashMap;
+
+/// The columns a roster must contain
+const COLUMNS: [&str; 5] = ["email", "first_name", "last_name", "level", "class"];
+
+/// Manage the school roster
+#[allow(clippy::unused_async)]
+#[command(
+	slash_command,
+	subcommands("roster_import"),
+	default_member_permissions = "MANAGE_ROLES",
+	required_bot_permissions = "MANAGE_ROLES"
+)]
+pub(crate) async fn roster(_: ApplicationContext<'_>) -> InteractionResult {
+	Ok(())
+}
+
+/// What the import created
+#[derive(Debug, Default)]
+struct ImportReport {
+	/// The number of imported students
+	students: usize,
+	/// The number of created levels
+	levels: usize,
+	/// The number of created classes
+	classes: usize,
+	/// The lines that could not be imported
+	skipped_lines: Vec<usize>,
+	/// The roles created on `Discord`, deleted again if the import fails
+	created_roles: Vec<RoleId>,
+}
+
+/// Create a role for a new level or class
+async fn create_role(
+	ctx: &ApplicationContext<'_>,
+	report: &mut ImportReport,
+	guild_id: GuildId,
+	name: &str,
+) -> Result<u64, poise::serenity_prelude::Error> {
+	let role = guild_id
+		.create_role(
+			&ctx.serenity_context,
+			EditRole::new()
+				.name(name)
+				.permissions(Permissions::empty())
+				.mentionable(true),
+		)
+		.await?;
+	report.created_roles.push(role.id);
+
+	Ok(role.id.get())
+}
+
+/// Get the id of a level from its name, creating it if needed
+///
+/// Returns `None` if the guild cannot have more levels
+async fn level_id(
+	ctx: &ApplicationContext<'_>,
+	connection: &mut AsyncMysqlConnection,
+	levels: &mut HashMap<String, i32>,
+	report: &mut ImportReport,
+	name: &str,
+) -> Result<Option<i32>, InteractionError> {
+	if let Some(level_id) = levels.get(name) {
+		return Ok(Some(*level_id));
+	}
+
+	if levels.len() >= usize::from(constants::limits::MAX_LEVELS_PER_GUILD) {
+		return Ok(None);
+	}
+
+	let guild_id = ctx.guild_only_id();
+	let new_level = NewLevel {
+		name,
+		guild_id: guild_id.get(),
+		role_id: create_role(ctx, report, guild_id, name).await?,
+	};
+
+	new_level.insert().execute(connection).await?;
+
+	let level_id: i32 = Level::all_from_guild(guild_id)
+		.filter(schema::levels::name.eq(name))
+		.select(schema::levels::id)
+		.first(connection)
+		.await?;
+
+	levels.insert(name.to_owned(), level_id);
+	report.levels += 1;
+
+	Ok(Some(level_id))
+}
+
+/// Get the id of a class from its level and name, creating it if needed
+///
+/// Returns `None` if the level cannot have more classes, or if another level has a class with this name
+async fn class_id(
+	ctx: &ApplicationContext<'_>,
+	connection: &mut AsyncMysqlConnection,
+	classes: &mut HashMap<(i32, String), i32>,
+	report: &mut ImportReport,
+	(level_id, name): (i32, &str),
+) -> Result<Option<i32>, InteractionError> {
+	if let Some(class_id) = classes.get(&(level_id, name.to_owned())) {
+		return Ok(Some(*class_id));
+	}
+
+	let nb_of_classes = classes
+		.keys()
+		.filter(|(class_level_id, _)| *class_level_id == level_id)
+		.count();
+	if nb_of_classes >= usize::from(constants::limits::MAX_CLASSES_PER_LEVEL) {
+		return Ok(None);
+	}
+
+	// The names of the classes are unique in the whole guild, whatever the case
+	if classes
+		.keys()
+		.any(|(_, class_name)| class_name.to_lowercase() == name.to_lowercase())
+	{
+		return Ok(None);
+	}
+
+	let guild_id = ctx.guild_only_id();
+	let new_class = NewClass {
+		name,
+		level_id,
+		guild_id: guild_id.get(),
+		role_id: create_role(ctx, report, guild_id, name).await?,
+	};
+
+	new_class.insert().execute(connection).await?;
+
+	let class_id: i32 = Class::all_from_level(level_id)
+		.filter(schema::classes::name.eq(name))
+		.select(schema::classes::id)
+		.first(connection)
+		.await?;
+
+	classes.insert((level_id, name.to_owned()), class_id);
+	report.classes += 1;
+
+	Ok(Some(class_id))
+}
+
+/// Import a CSV roster with the `email`, `first_name`, `last_name`, `level` and `class` columns.
+///
+/// Missing levels and classes are created, students are then bound to their class on login.
+#[command(slash_command, guild_only, rename = "import")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn roster_import(
+	ctx: ApplicationContext<'_>,
+	file: Attachment,
+) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+
+	if file.size > constants::limits::MAX_ROSTER_SIZE {
+		ctx.shout(ctx.translate("roster_import-too-large", None))
+			.await?;
+
+		return Ok(());
+	}
+
+	// Creating the roles can take longer than the interaction deadline
+	ctx.defer_ephemeral().await?;
+
+	let Ok(content) = String::from_utf8(file.download().await?) else {
+		ctx.shout(ctx.translate("roster_import-not-utf8", None))
+			.await?;
+
+		return Ok(());
+	};
+
+	let mut records = match csv::parse(&content) {
+		Ok(records) => records.into_iter(),
+		Err(error) => {
+			ctx.shout(ctx.translate(
+				"roster_import-invalid",
+				Some(fluent_args!["error" => error.to_string()]),
+			))
+			.await?;
+
+			return Ok(());
+		}
+	};
+
+	let header = records
+		.next()
+		.unwrap_or_default()
+		.iter()
+		.map(|column| column.trim().to_lowercase().replace([' ', '-'], "_"))
+		.collect::<Vec<_>>();
+
+	let mut indexes = [0; COLUMNS.len()];
+	for (index, column) in indexes.iter_mut().zip(COLUMNS) {
+		let Some(position) = header.iter().position(|name| name == column) else {
+			ctx.shout(ctx.translate(
+				"roster_import-missing-column",
+				Some(fluent_args!["column" => column]),
+			))
+			.await?;
+
+			return Ok(());
+		};
+
+		*index = position;
+	}
+
+	let mut connection = ctx.data.database.get().await?;
+
+	let mut levels = Level::all_from_guild(guild_id)
+		.select((schema::levels::name, schema::levels::id))
+		.get_results::<(String, i32)>(&mut connection)
+		.await?
+		.into_iter()
+		.collect::<HashMap<_, _>>();
+	let mut classes = Class::all_from_guild(guild_id)
+		.select((
+			schema::classes::level_id,
+			schema::classes::name,
+			schema::classes::id,
+		))
+		.get_results::<(i32, String, i32)>(&mut connection)
+		.await?
+		.into_iter()
+		.map(|(level_id, name, id)| ((level_id, name), id))
+		.collect::<HashMap<_, _>>();
+
+	let mut report = ImportReport::default();
+
+	// A failing line rolls the whole import back, the roles created so far are then deleted
+	let result = connection
+		.transaction::<_, InteractionError, _>(|connection| {
+			async {
+				// The header is on the first line
+				for (line, record) in (2..).zip(records) {
+					let [mail, first_name, last_name, level, class] =
+						indexes.map(|index| record.get(index).map_or("", |field| field.trim()));
+
+					if !mail.contains('@') || level.is_empty() || class.is_empty() {
+						report.skipped_lines.push(line);
+						continue;
+					}
+
+					let Some(level_id) =
+						level_id(&ctx, connection, &mut levels, &mut report, level).await?
+					else {
+						report.skipped_lines.push(line);
+						continue;
+					};
+
+					let Some(class_id) = class_id(
+						&ctx,
+						connection,
+						&mut classes,
+						&mut report,
+						(level_id, class),
+					)
+					.await?
+					else {
+						report.skipped_lines.push(line);
+						continue;
+					};
+
+					let mail = mail.to_lowercase();
+					let new_roster_entry = NewRosterEntry {
+						guild_id: guild_id.get(),
+						mail: &mail,
+						first_name,
+						last_name,
+						class_id,
+					};
+
+					new_roster_entry.replace().execute(connection).await?;
+					report.students += 1;
+				}
+
+				Ok(())
+			}
+			.scope_boxed()
+		})
+		.await;
+
+	if let Err(error) = result {
+		for role_id in report.created_roles {
+			if let Err(error) = delete_role(ctx.http(), guild_id, role_id).await {
+				tracing::warn!(
+					role_id = role_id.get(),
+					error = ?error,
+					"could not delete a role created by a failed import"
+				);
+			}
+		}
+
+		return Err(error);
+	}
+
+	let mut message = ctx.translate(
+		"roster_import-done",
+		Some(fluent_args![
+			"students" => report.students,
+			"levels" => report.levels,
+			"classes" => report.classes,
+		]),
+	);
+
+	if !report.skipped_lines.is_empty() {
+		let lines = report
+			.skipped_lines
+			.iter()
+			.map(ToString::to_string)
+			.collect::<Vec<_>>()
+			.join(", ");
+
+		message.push('\n');
+		message.push_str(&ctx.translate(
+			"roster_import-skipped",
+			Some(fluent_args!["lines" => lines]),
+		));
+	}
+
+	ctx.shout(message).await?;
+
+	Ok(())
+}
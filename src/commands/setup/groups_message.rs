@@ -1,42 +1,37 @@
 //! Setup messages for roles interactions
 
 use crate::{
+	audit::{self, AuditEntry, AuditKind},
 	constants,
-	database::{models::Group, prelude::*, schema},
-	states::{ApplicationContext, ApplicationContextPolyfill, InteractionResult},
+	database::{
+		models::{Group, Guild},
+		prelude::*,
+		schema, DatabasePooledConnection,
+	},
+	states::{ApplicationContext, ApplicationContextPolyfill, InteractionError, InteractionResult},
 	translation::Translate,
 };
 use poise::{
 	command,
 	serenity_prelude::{
-		CreateActionRow, CreateMessage, CreateSelectMenu, CreateSelectMenuKind,
-		CreateSelectMenuOption, ReactionType,
+		self as serenity, ChannelId, CreateActionRow, CreateMessage, CreateSelectMenu,
+		CreateSelectMenuKind, CreateSelectMenuOption, EditMessage, ErrorResponse, HttpError,
+		MessageId, ReactionType,
 	},
 };
 
-/// Sets the login and logout message.
-#[command(slash_command, guild_only, rename = "groups_message")]
-#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
-pub(crate) async fn setup_groups_message(ctx: ApplicationContext<'_>) -> InteractionResult {
-	let mut connection = ctx.data.database.get().await?;
-	let guild_id = ctx.guild_only_id();
-
-	// TODO: check that there is a least one group
-	// TODO: use guild locale or interaction locale as fallback
-
-	let mut groups: Vec<Group> = Group::all_from_guild(guild_id)
-		.load(&mut connection)
-		.await?;
-
-	if groups.is_empty() {
-		ctx.shout(ctx.translate("setup_groups_message-not-enough-groups", None))
-			.await?;
-
-		return Ok(());
-	}
+/// The `Discord` JSON error code returned when a message does not exist anymore
+///
+/// <https://discord.com/developers/docs/topics/opcodes-and-status-codes#json-json-error-codes>
+const UNKNOWN_MESSAGE_ERROR_CODE: isize = 10008;
 
+/// Build the select menu of the groups message, also shown by `/profile`
+pub(crate) fn groups_message_components(
+	ctx: &ApplicationContext<'_>,
+	groups: &[Group],
+) -> Vec<CreateActionRow> {
 	let groups = groups
-		.iter_mut()
+		.iter()
 		.map(|group| {
 			let mut op = CreateSelectMenuOption::new(&group.name, group.id.to_string());
 			if let Some(emoji) = &group.emoji {
@@ -49,13 +44,36 @@ pub(crate) async fn setup_groups_message(ctx: ApplicationContext<'_>) -> Interac
 		})
 		.collect::<Vec<_>>();
 
-	let action_row = CreateActionRow::SelectMenu(
+	vec![CreateActionRow::SelectMenu(
 		CreateSelectMenu::new(
 			constants::events::GROUPS_SELECT_MENU_INTERACTION,
 			CreateSelectMenuKind::String { options: groups },
 		)
 		.placeholder(ctx.translate("setup_groups_message-placeholder", None)),
-	);
+	)]
+}
+
+/// Sets the login and logout message.
+#[command(slash_command, guild_only, rename = "groups_message")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn setup_groups_message(ctx: ApplicationContext<'_>) -> InteractionResult {
+	let mut connection = ctx.data.database.get().await?;
+	let guild_id = ctx.guild_only_id();
+
+	// TODO: check that there is a least one group
+	// TODO: use guild locale or interaction locale as fallback
+
+	let groups: Vec<Group> = Group::all_from_guild(guild_id)
+		.filter(schema::groups::broken.eq(false))
+		.load(&mut connection)
+		.await?;
+
+	if groups.is_empty() {
+		ctx.shout(ctx.translate("setup_groups_message-not-enough-groups", None))
+			.await?;
+
+		return Ok(());
+	}
 
 	let reply = ctx
 		.interaction
@@ -64,17 +82,86 @@ pub(crate) async fn setup_groups_message(ctx: ApplicationContext<'_>) -> Interac
 			&ctx.serenity_context,
 			CreateMessage::new()
 				.content(ctx.translate("setup_groups_message-message", None))
-				.components(vec![action_row]),
+				.components(groups_message_components(&ctx, &groups)),
 		)
 		.await?;
 
 	// Update the `setup_message_id`
 	diesel::update(schema::guilds::table.find(guild_id.get()))
-		.set(schema::guilds::groups_message_id.eq(reply.id.get()))
+		.set((
+			schema::guilds::groups_message_id.eq(reply.id.get()),
+			schema::guilds::groups_message_channel_id.eq(reply.channel_id.get()),
+		))
 		.execute(&mut connection)
 		.await?;
 
+	audit::record(
+		ctx.data,
+		guild_id,
+		AuditEntry::new(AuditKind::ConfigChanged)
+			.actor(ctx.interaction.user.id)
+			.details(format!("groups_message: {}", reply.link())),
+	)
+	.await;
+
 	ctx.shout(ctx.translate("done", None)).await?;
 
 	Ok(())
 }
+
+/// Render the posted groups message again, after the groups changed
+///
+/// Messages posted before their channel was recorded cannot be found and are left as is
+pub(crate) async fn refresh_groups_message(
+	ctx: &ApplicationContext<'_>,
+	connection: &mut DatabasePooledConnection,
+) -> Result<(), InteractionError> {
+	let guild_id = ctx.guild_only_id();
+
+	let (Some(message_id), Some(channel_id)) = Guild::with_id(guild_id)
+		.select((
+			schema::guilds::groups_message_id,
+			schema::guilds::groups_message_channel_id,
+		))
+		.first::<(Option<u64>, Option<u64>)>(connection)
+		.await?
+	else {
+		return Ok(());
+	};
+
+	let groups: Vec<Group> = Group::all_from_guild(guild_id)
+		.filter(schema::groups::broken.eq(false))
+		.load(connection)
+		.await?;
+
+	// A select menu needs at least one option
+	if groups.is_empty() {
+		return Ok(());
+	}
+
+	match ChannelId::new(channel_id)
+		.edit_message(
+			&ctx.serenity_context,
+			MessageId::new(message_id),
+			EditMessage::new().components(groups_message_components(ctx, &groups)),
+		)
+		.await
+	{
+		Ok(_) => Ok(()),
+		// The message was deleted, forget it
+		Err(serenity::Error::Http(HttpError::UnsuccessfulRequest(ErrorResponse {
+			error, ..
+		}))) if error.code == UNKNOWN_MESSAGE_ERROR_CODE => {
+			diesel::update(Guild::with_id(guild_id))
+				.set((
+					schema::guilds::groups_message_id.eq::<Option<u64>>(None),
+					schema::guilds::groups_message_channel_id.eq::<Option<u64>>(None),
+				))
+				.execute(connection)
+				.await?;
+
+			Ok(())
+		}
+		Err(error) => Err(error.into()),
+	}
+}
@@ -1,7 +1,10 @@
 //! Setup messages for roles interactions
 
 use crate::{
-	constants::events::{LOGIN_BUTTON_INTERACTION, LOGOUT_BUTTON_INTERACTION},
+	audit::{self, AuditEntry, AuditKind},
+	constants::events::{
+		CHANGE_CLASS_BUTTON_INTERACTION, LOGIN_BUTTON_INTERACTION, LOGOUT_BUTTON_INTERACTION,
+	},
 	database::{models::Guild, prelude::*, schema},
 	states::{ApplicationContext, ApplicationContextPolyfill, InteractionResult},
 	translation::Translate,
@@ -43,6 +46,9 @@ pub(crate) async fn setup_login_message(ctx: ApplicationContext<'_>) -> Interact
 		CreateButton::new(LOGOUT_BUTTON_INTERACTION)
 			.label(ctx.translate("event-setup-logout-button", None))
 			.style(ButtonStyle::Danger),
+		CreateButton::new(CHANGE_CLASS_BUTTON_INTERACTION)
+			.label(ctx.translate("event-setup-change-class-button", None))
+			.style(ButtonStyle::Secondary),
 	]);
 
 	let message = CreateMessage::new()
@@ -61,6 +67,15 @@ pub(crate) async fn setup_login_message(ctx: ApplicationContext<'_>) -> Interact
 		.execute(&mut connection)
 		.await?;
 
+	audit::record(
+		ctx.data,
+		guild_id,
+		AuditEntry::new(AuditKind::ConfigChanged)
+			.actor(ctx.interaction.user.id)
+			.details(format!("login_message: {}", reply.link())),
+	)
+	.await;
+
 	ctx.shout(ctx.translate("done", None)).await?;
 
 	Ok(())
@@ -1,21 +1,29 @@
 //! A set of commands to manage the bot.
 
 use crate::{
-	database::{models::Guild, prelude::*, schema},
+	audit::{self, AuditEntry, AuditKind},
+	database::{models::Guild, prelude::*, query::lower, schema},
 	states::{ApplicationContext, ApplicationContextPolyfill, InteractionResult},
 	translation::Translate,
+	verification::{
+		duplicates::DuplicateMailPolicy, member_kind::MemberKind,
+		offline_access::revoke_refresh_token, role_policy::VerifiedRolePolicy,
+	},
 };
 use fluent::fluent_args;
 use poise::{
 	command,
-	serenity_prelude::{Permissions, Role},
+	serenity_prelude::{GuildChannel, Permissions, Role},
 };
 
 mod groups_message;
 mod login_message;
+mod pattern;
 
 use groups_message::setup_groups_message;
+pub(crate) use groups_message::{groups_message_components, refresh_groups_message};
 use login_message::setup_login_message;
+use pattern::setup_pattern;
 
 /// A set of commands to setup the bot
 #[allow(clippy::unused_async)]
@@ -26,7 +34,15 @@ use login_message::setup_login_message;
 		"setup_groups_message",
 		"setup_login_message",
 		"setup_role",
-		"setup_pattern"
+		"setup_kind",
+		"setup_pattern",
+		"setup_provider",
+		"setup_log_channel",
+		"setup_verified_role_policy",
+		"setup_reverification",
+		"setup_offline_access",
+		"setup_duplicate_mails",
+		"setup_class_change_approval"
 	),
 	default_member_permissions = "ADMINISTRATOR"
 )]
@@ -53,31 +69,461 @@ pub(crate) async fn setup_role(ctx: ApplicationContext<'_>, role: Role) -> Inter
 		.execute(&mut ctx.data.database.get().await?)
 		.await?;
 
+	audit::record(
+		ctx.data,
+		guild_id,
+		AuditEntry::new(AuditKind::ConfigChanged)
+			.actor(ctx.interaction.user.id)
+			.details(format!("role: <@&{}>", role.id)),
+	)
+	.await;
+
 	ctx.shout(ctx.translate("done", None)).await?;
 
 	Ok(())
 }
 
-/// Setup the role to apply to verified members.
-#[command(slash_command, guild_only, rename = "pattern")]
+/// Setup the role to apply to the members of a kind, or disable the kind.
+#[command(slash_command, guild_only, rename = "kind")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn setup_kind(
+	ctx: ApplicationContext<'_>,
+	kind: MemberKind,
+	role: Option<Role>,
+) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+
+	if kind.has_class() {
+		ctx.shout(ctx.translate("setup_kind-student", None)).await?;
+
+		return Ok(());
+	}
+
+	if role
+		.as_ref()
+		.is_some_and(|role| role.has_permission(Permissions::ADMINISTRATOR))
+	{
+		ctx.shout(ctx.translate("setup_role-role-admin", None))
+			.await?;
+
+		return Ok(());
+	}
+
+	let role_id = role.as_ref().map(|role| role.id.get());
+	let update = diesel::update(Guild::with_id(guild_id));
+	let mut connection = ctx.data.database.get().await?;
+
+	// Update the role of the kind
+	match kind {
+		MemberKind::Teacher => {
+			update
+				.set(schema::guilds::teacher_role_id.eq(role_id))
+				.execute(&mut connection)
+				.await?
+		}
+		MemberKind::Staff => {
+			update
+				.set(schema::guilds::staff_role_id.eq(role_id))
+				.execute(&mut connection)
+				.await?
+		}
+		MemberKind::Student => unreachable!("students do not have a kind role"),
+	};
+
+	audit::record(
+		ctx.data,
+		guild_id,
+		AuditEntry::new(AuditKind::ConfigChanged)
+			.actor(ctx.interaction.user.id)
+			.details(format!(
+				"{kind}: {}",
+				role.as_ref()
+					.map_or_else(|| "-".to_owned(), |role| format!("<@&{}>", role.id))
+			)),
+	)
+	.await;
+
+	let kind_name = ctx.translate(kind.translation_key(), None);
+	let message = if role.is_some() {
+		ctx.translate("setup_kind-done", Some(fluent_args!["kind" => kind_name]))
+	} else {
+		ctx.translate(
+			"setup_kind-disabled",
+			Some(fluent_args!["kind" => kind_name]),
+		)
+	};
+	ctx.shout(message).await?;
+
+	Ok(())
+}
+
+/// Autocompletes the identity providers configured on the bot
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+async fn autocomplete_providers(ctx: ApplicationContext<'_>, partial: &str) -> Vec<String> {
+	ctx.data
+		.auth
+		.names()
+		.filter(|provider| provider.contains(partial))
+		.map(ToOwned::to_owned)
+		.collect()
+}
+
+/// Setup the identity provider members use to verify themselves.
+#[command(slash_command, guild_only, rename = "provider")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn setup_provider(
+	ctx: ApplicationContext<'_>,
+	#[autocomplete = "autocomplete_providers"] provider: String,
+) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+
+	if ctx.data.auth.get(&provider).is_none() {
+		ctx.shout(ctx.translate(
+			"setup_provider-unknown",
+			Some(fluent_args!["provider" => provider]),
+		))
+		.await?;
+
+		return Ok(());
+	}
+
+	// Update the identity provider
+	diesel::update(Guild::with_id(guild_id))
+		.set(schema::guilds::identity_provider.eq(&provider))
+		.execute(&mut ctx.data.database.get().await?)
+		.await?;
+
+	audit::record(
+		ctx.data,
+		guild_id,
+		AuditEntry::new(AuditKind::ConfigChanged)
+			.actor(ctx.interaction.user.id)
+			.details(format!("provider: {provider}")),
+	)
+	.await;
+
+	ctx.shout(ctx.translate(
+		"setup_provider-done",
+		Some(fluent_args!["provider" => provider]),
+	))
+	.await?;
+
+	Ok(())
+}
+
+/// Setup the channel where the bot tells the admins what it did on its own.
+#[command(slash_command, guild_only, rename = "log_channel")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn setup_log_channel(
+	ctx: ApplicationContext<'_>,
+	#[channel_types("Text")] channel: Option<GuildChannel>,
+) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+
+	diesel::update(Guild::with_id(guild_id))
+		.set(schema::guilds::log_channel_id.eq(channel.as_ref().map(|channel| channel.id.get())))
+		.execute(&mut ctx.data.database.get().await?)
+		.await?;
+
+	audit::record(
+		ctx.data,
+		guild_id,
+		AuditEntry::new(AuditKind::ConfigChanged)
+			.actor(ctx.interaction.user.id)
+			.details(format!(
+				"log_channel: {}",
+				channel
+					.as_ref()
+					.map_or_else(|| "-".to_owned(), |channel| format!("<#{}>", channel.id))
+			)),
+	)
+	.await;
+
+	let message = channel.map_or_else(
+		|| ctx.translate("setup_log_channel-disabled", None),
+		|channel| {
+			ctx.translate(
+				"setup_log_channel-done",
+				Some(fluent_args!["channel" => format!("<#{}>", channel.id)]),
+			)
+		},
+	);
+	ctx.shout(message).await?;
+
+	Ok(())
+}
+
+/// Setup what to do when a verified member loses the verified role by hand.
+#[command(slash_command, guild_only, rename = "verified_role_policy")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn setup_verified_role_policy(
+	ctx: ApplicationContext<'_>,
+	policy: VerifiedRolePolicy,
+) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+
+	diesel::update(Guild::with_id(guild_id))
+		.set(schema::guilds::verified_role_policy.eq(policy.as_str()))
+		.execute(&mut ctx.data.database.get().await?)
+		.await?;
+
+	audit::record(
+		ctx.data,
+		guild_id,
+		AuditEntry::new(AuditKind::ConfigChanged)
+			.actor(ctx.interaction.user.id)
+			.details(format!("verified_role_policy: {policy}")),
+	)
+	.await;
+
+	ctx.shout(ctx.translate(
+		"setup_verified_role_policy-done",
+		Some(fluent_args!["policy" => ctx.translate(policy.translation_key(), None)]),
+	))
+	.await?;
+
+	Ok(())
+}
+
+/// Setup after how many days the verified members must verify themselves again.
+#[command(slash_command, guild_only, rename = "reverification")]
 #[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
-pub(crate) async fn setup_pattern(
+pub(crate) async fn setup_reverification(
 	ctx: ApplicationContext<'_>,
-	pattern: String,
+	#[min = 1] days: Option<i32>,
 ) -> InteractionResult {
 	let guild_id = ctx.guild_only_id();
 
-	// Update the verification email domain
 	diesel::update(Guild::with_id(guild_id))
-		.set(schema::guilds::verification_email_domain.eq(&pattern))
+		.set(schema::guilds::reverification_days.eq(days))
 		.execute(&mut ctx.data.database.get().await?)
 		.await?;
 
+	audit::record(
+		ctx.data,
+		guild_id,
+		AuditEntry::new(AuditKind::ConfigChanged)
+			.actor(ctx.interaction.user.id)
+			.details(format!(
+				"reverification: {}",
+				days.map_or_else(|| "-".to_owned(), |days| days.to_string())
+			)),
+	)
+	.await;
+
+	let message = days.map_or_else(
+		|| ctx.translate("setup_reverification-disabled", None),
+		|days| {
+			ctx.translate(
+				"setup_reverification-done",
+				Some(fluent_args!["days" => days]),
+			)
+		},
+	);
+	ctx.shout(message).await?;
+
+	Ok(())
+}
+
+/// Setup whether to keep a refresh token of the members, to notice when their account is deactivated.
+#[command(slash_command, guild_only, rename = "offline_access")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn setup_offline_access(
+	ctx: ApplicationContext<'_>,
+	enabled: bool,
+) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+
+	if enabled && ctx.data.config.token_cipher.is_none() {
+		ctx.shout(ctx.translate("setup_offline_access-no-key", None))
+			.await?;
+
+		return Ok(());
+	}
+
+	ctx.defer_ephemeral().await?;
+
+	let mut connection = ctx.data.database.get().await?;
+
+	diesel::update(Guild::with_id(guild_id))
+		.set(schema::guilds::offline_access.eq(enabled))
+		.execute(&mut connection)
+		.await?;
+
+	// The tokens already given are not needed anymore
+	if !enabled {
+		let member_ids = schema::members::table
+			.filter(schema::members::guild_id.eq(guild_id.get()))
+			.select(schema::members::id);
+
+		let tokens = schema::verified_members::table
+			.filter(schema::verified_members::member_id.eq_any(member_ids))
+			.filter(schema::verified_members::refresh_token.is_not_null())
+			.select((
+				schema::verified_members::refresh_token,
+				schema::verified_members::token_provider,
+			))
+			.load::<(Option<Vec<u8>>, Option<String>)>(&mut connection)
+			.await?;
+		for token in tokens {
+			revoke_refresh_token(ctx.data, token).await;
+		}
+
+		diesel::update(
+			schema::verified_members::table
+				.filter(schema::verified_members::member_id.eq_any(member_ids)),
+		)
+		.set((
+			schema::verified_members::refresh_token.eq::<Option<Vec<u8>>>(None),
+			schema::verified_members::token_provider.eq::<Option<String>>(None),
+		))
+		.execute(&mut connection)
+		.await?;
+	}
+
+	audit::record(
+		ctx.data,
+		guild_id,
+		AuditEntry::new(AuditKind::ConfigChanged)
+			.actor(ctx.interaction.user.id)
+			.details(format!("offline_access: {enabled}")),
+	)
+	.await;
+
+	let key = if enabled {
+		"setup_offline_access-enabled"
+	} else {
+		"setup_offline_access-disabled"
+	};
+	ctx.shout(ctx.translate(key, None)).await?;
+
+	Ok(())
+}
+
+/// Setup what happens when another account verifies itself with a mail already verified.
+#[command(slash_command, guild_only, rename = "duplicate_mails")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn setup_duplicate_mails(
+	ctx: ApplicationContext<'_>,
+	policy: DuplicateMailPolicy,
+) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+
+	let mut connection = ctx.data.database.get().await?;
+
+	diesel::update(Guild::with_id(guild_id))
+		.set(schema::guilds::duplicate_mail_policy.eq(policy.as_str()))
+		.execute(&mut connection)
+		.await?;
+
+	// The members already verified claim their mail, the first one wins when several share it
+	diesel::delete(
+		schema::mail_claims::table.filter(schema::mail_claims::guild_id.eq(guild_id.get())),
+	)
+	.execute(&mut connection)
+	.await?;
+	if policy == DuplicateMailPolicy::Block {
+		diesel::insert_or_ignore_into(schema::mail_claims::table)
+			.values(
+				schema::verified_members::table
+					.inner_join(schema::members::table)
+					.filter(schema::members::guild_id.eq(guild_id.get()))
+					// The members verified by hand without an account have no mail to claim
+					.filter(schema::verified_members::mail.ne(""))
+					.order_by(schema::verified_members::verified_at)
+					.select((
+						schema::members::guild_id,
+						// The claims are stored in lower case
+						lower(schema::verified_members::mail),
+						schema::verified_members::member_id,
+					)),
+			)
+			.into_columns((
+				schema::mail_claims::guild_id,
+				schema::mail_claims::mail,
+				schema::mail_claims::member_id,
+			))
+			.execute(&mut connection)
+			.await?;
+	}
+
+	audit::record(
+		ctx.data,
+		guild_id,
+		AuditEntry::new(AuditKind::ConfigChanged)
+			.actor(ctx.interaction.user.id)
+			.details(format!("duplicate_mail_policy: {policy}")),
+	)
+	.await;
+
 	ctx.shout(ctx.translate(
-		"setup_pattern-done",
-		Some(fluent_args!["pattern" => pattern]),
+		"setup_duplicate_mails-done",
+		Some(fluent_args!["policy" => ctx.translate(policy.translation_key(), None)]),
 	))
 	.await?;
 
 	Ok(())
 }
+
+/// Setup whether the students changing their class wait for the approval of an admin.
+#[command(slash_command, guild_only, rename = "class_change_approval")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn setup_class_change_approval(
+	ctx: ApplicationContext<'_>,
+	enabled: bool,
+) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+
+	let mut connection = ctx.data.database.get().await?;
+
+	// The requests are posted in the log channel for the admins to review
+	if enabled
+		&& Guild::with_id(guild_id)
+			.select(schema::guilds::log_channel_id)
+			.first::<Option<u64>>(&mut connection)
+			.await?
+			.is_none()
+	{
+		ctx.shout(ctx.translate("setup_class_change_approval-no-log-channel", None))
+			.await?;
+
+		return Ok(());
+	}
+
+	diesel::update(Guild::with_id(guild_id))
+		.set(schema::guilds::class_change_approval.eq(enabled))
+		.execute(&mut connection)
+		.await?;
+
+	// The requests waiting for a review cannot be approved anymore
+	if !enabled {
+		let member_ids = schema::members::table
+			.filter(schema::members::guild_id.eq(guild_id.get()))
+			.select(schema::members::id);
+
+		diesel::delete(
+			schema::class_change_requests::table
+				.filter(schema::class_change_requests::member_id.eq_any(member_ids)),
+		)
+		.execute(&mut connection)
+		.await?;
+	}
+
+	audit::record(
+		ctx.data,
+		guild_id,
+		AuditEntry::new(AuditKind::ConfigChanged)
+			.actor(ctx.interaction.user.id)
+			.details(format!("class_change_approval: {enabled}")),
+	)
+	.await;
+
+	let key = if enabled {
+		"setup_class_change_approval-enabled"
+	} else {
+		"setup_class_change_approval-disabled"
+	};
+	ctx.shout(ctx.translate(key, None)).await?;
+
+	Ok(())
+}
@@ -0,0 +1,230 @@
+//! Manage the rules that decide which emails can verify themselves
+
+use crate::{
+	audit::{self, AuditEntry, AuditKind},
+	constants,
+	database::{
+		models::{GuildEmailRule, NewGuildEmailRule},
+		prelude::*,
+		schema,
+	},
+	states::{ApplicationContext, ApplicationContextPolyfill, InteractionResult},
+	translation::Translate,
+	verification::{
+		email_rules::{EmailMatcher, EmailRuleKind},
+		member_kind::MemberKind,
+	},
+};
+use fluent::fluent_args;
+use poise::{
+	command,
+	serenity_prelude::{Permissions, Role},
+};
+
+/// A set of commands to manage the allowed emails
+#[allow(clippy::unused_async)]
+#[command(
+	slash_command,
+	guild_only,
+	rename = "pattern",
+	subcommands("setup_pattern_add", "setup_pattern_remove", "setup_pattern_list")
+)]
+pub(crate) async fn setup_pattern(_: ApplicationContext<'_>) -> InteractionResult {
+	Ok(())
+}
+
+/// Allow the emails matching a pattern to verify themselves.
+#[command(slash_command, guild_only, rename = "add")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn setup_pattern_add(
+	ctx: ApplicationContext<'_>,
+	kind: EmailRuleKind,
+	pattern: String,
+	role: Option<Role>,
+	member_kind: Option<MemberKind>,
+) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+	let mut connection = ctx.data.database.get().await?;
+
+	if let Err(error) = EmailMatcher::new(kind, &pattern) {
+		ctx.shout(ctx.translate(
+			"setup_pattern_add-invalid",
+			Some(fluent_args!["pattern" => pattern, "error" => error.to_string()]),
+		))
+		.await?;
+
+		return Ok(());
+	}
+
+	if role
+		.as_ref()
+		.is_some_and(|role| role.has_permission(Permissions::ADMINISTRATOR))
+	{
+		ctx.shout(ctx.translate("setup_role-role-admin", None))
+			.await?;
+
+		return Ok(());
+	}
+
+	let nb_of_rules: i64 = GuildEmailRule::all_from_guild(guild_id)
+		.count()
+		.get_result(&mut connection)
+		.await?;
+
+	if nb_of_rules >= i64::from(constants::limits::MAX_EMAIL_RULES_PER_GUILD) {
+		ctx.shout(ctx.translate("setup_pattern_add-too-many-patterns", None))
+			.await?;
+
+		return Ok(());
+	}
+
+	let already_exists: bool = db_dsl::select(db_dsl::exists(
+		GuildEmailRule::all_from_guild(guild_id)
+			.filter(schema::guild_email_rules::kind.eq(kind.as_str()))
+			.filter(schema::guild_email_rules::pattern.eq(&pattern)),
+	))
+	.get_result(&mut connection)
+	.await?;
+
+	if already_exists {
+		ctx.shout(ctx.translate(
+			"setup_pattern_add-already-exists",
+			Some(fluent_args!["pattern" => pattern]),
+		))
+		.await?;
+
+		return Ok(());
+	}
+
+	let new_rule = NewGuildEmailRule {
+		guild_id: guild_id.get(),
+		kind: kind.as_str(),
+		pattern: &pattern,
+		role_id: role.map(|role| role.id.get()),
+		member_kind: member_kind.map(MemberKind::as_str),
+	};
+
+	new_rule.insert().execute(&mut connection).await?;
+
+	audit::record(
+		ctx.data,
+		guild_id,
+		AuditEntry::new(AuditKind::ConfigChanged)
+			.actor(ctx.interaction.user.id)
+			.details(format!("pattern added: {kind} `{pattern}`")),
+	)
+	.await;
+
+	ctx.shout(ctx.translate(
+		"setup_pattern_add-done",
+		Some(fluent_args!["pattern" => pattern]),
+	))
+	.await?;
+
+	Ok(())
+}
+
+// TODO: allow using a result instead of unwrapping everything
+/// Autocompletes the patterns of the guild email rules
+#[allow(clippy::unwrap_used)]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+async fn autocomplete_patterns<'a>(
+	ctx: ApplicationContext<'_>,
+	partial: &'a str,
+) -> impl Iterator<Item = String> + 'a {
+	let patterns: Vec<_> = GuildEmailRule::all_from_guild(ctx.interaction.guild_id.unwrap())
+		.select(schema::guild_email_rules::pattern)
+		.get_results::<String>(&mut ctx.data.database.get().await.unwrap())
+		.await
+		.unwrap();
+
+	patterns
+		.into_iter()
+		.filter(move |pattern| pattern.contains(partial))
+}
+
+/// Stop allowing the emails matching a pattern to verify themselves.
+#[command(slash_command, guild_only, rename = "remove")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn setup_pattern_remove(
+	ctx: ApplicationContext<'_>,
+	#[autocomplete = "autocomplete_patterns"] pattern: String,
+) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+
+	let deleted = diesel::delete(
+		GuildEmailRule::all_from_guild(guild_id)
+			.filter(schema::guild_email_rules::pattern.eq(&pattern)),
+	)
+	.execute(&mut ctx.data.database.get().await?)
+	.await?;
+
+	let key = if deleted == 0 {
+		"setup_pattern_remove-not-found"
+	} else {
+		audit::record(
+			ctx.data,
+			guild_id,
+			AuditEntry::new(AuditKind::ConfigChanged)
+				.actor(ctx.interaction.user.id)
+				.details(format!("pattern removed: `{pattern}`")),
+		)
+		.await;
+
+		"setup_pattern_remove-done"
+	};
+
+	ctx.shout(ctx.translate(key, Some(fluent_args!["pattern" => pattern])))
+		.await?;
+
+	Ok(())
+}
+
+/// List the patterns of the emails allowed to verify themselves.
+#[command(slash_command, guild_only, rename = "list")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn setup_pattern_list(ctx: ApplicationContext<'_>) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+
+	let rules: Vec<GuildEmailRule> = GuildEmailRule::all_from_guild(guild_id)
+		.order_by(schema::guild_email_rules::id)
+		.get_results(&mut ctx.data.database.get().await?)
+		.await?;
+
+	if rules.is_empty() {
+		ctx.shout(ctx.translate("setup_pattern_list-none", None))
+			.await?;
+
+		return Ok(());
+	}
+
+	let rules_string = rules
+		.iter()
+		.map(|rule| {
+			let member_kind = rule
+				.member_kind
+				.as_deref()
+				.and_then(|member_kind| member_kind.parse::<MemberKind>().ok())
+				.map(|member_kind| {
+					format!(" ({})", ctx.translate(member_kind.translation_key(), None))
+				})
+				.unwrap_or_default();
+			let role = rule
+				.role_id
+				.map(|role_id| format!(" → <@&{role_id}>"))
+				.unwrap_or_default();
+
+			format!("- {} `{}`{member_kind}{role}", rule.kind, rule.pattern)
+		})
+		.collect::<Vec<_>>()
+		.join("\n");
+
+	ctx.shout(format!(
+		"**{}**:\n{}",
+		ctx.translate("setup_pattern_list-title", None),
+		rules_string
+	))
+	.await?;
+
+	Ok(())
+}
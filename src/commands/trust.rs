@@ -0,0 +1,230 @@
+//! Share the verifications with other guilds, e.g. the clubs of a school
+
+use crate::{
+	audit::{self, AuditEntry, AuditKind},
+	database::{
+		models::{Guild, NewTrustGroup, TrustGroup},
+		prelude::*,
+		schema::{guilds, trust_groups},
+	},
+	states::{ApplicationContext, ApplicationContextPolyfill, InteractionResult},
+	translation::Translate,
+};
+use diesel::dsl;
+use fluent::fluent_args;
+use poise::command;
+use uuid::Uuid;
+
+/// Share the verifications with other guilds
+#[allow(clippy::unused_async)]
+#[command(
+	slash_command,
+	subcommands("trust_create", "trust_join", "trust_leave", "trust_show"),
+	default_member_permissions = "ADMINISTRATOR"
+)]
+pub(crate) async fn trust(_: ApplicationContext<'_>) -> InteractionResult {
+	Ok(())
+}
+
+/// Create a trust group, other guilds join it with its invite code
+#[command(slash_command, guild_only, rename = "create")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn trust_create(
+	ctx: ApplicationContext<'_>,
+	#[max_length = 100] name: String,
+) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+
+	let mut connection = ctx.data.database.get().await?;
+
+	if Guild::with_id(guild_id)
+		.select(guilds::trust_group_id)
+		.first::<Option<i32>>(&mut connection)
+		.await?
+		.is_some()
+	{
+		ctx.shout(ctx.translate("trust-already-member", None))
+			.await?;
+
+		return Ok(());
+	}
+
+	let invite_code = Uuid::new_v4().simple().to_string();
+
+	NewTrustGroup {
+		name: &name,
+		invite_code: &invite_code,
+	}
+	.insert()
+	.execute(&mut connection)
+	.await?;
+
+	let trust_group_id = TrustGroup::with_invite_code(&invite_code)
+		.select(trust_groups::id)
+		.first::<i32>(&mut connection)
+		.await?;
+
+	diesel::update(Guild::with_id(guild_id))
+		.set(guilds::trust_group_id.eq(trust_group_id))
+		.execute(&mut connection)
+		.await?;
+
+	audit::record(
+		ctx.data,
+		guild_id,
+		AuditEntry::new(AuditKind::ConfigChanged)
+			.actor(ctx.interaction.user.id)
+			.details(format!("trust_group: {name}")),
+	)
+	.await;
+
+	ctx.shout(ctx.translate(
+		"trust_create-success",
+		Some(fluent_args!["name" => name, "code" => invite_code]),
+	))
+	.await?;
+
+	Ok(())
+}
+
+/// Join a trust group with the invite code given by one of its guilds
+#[command(slash_command, guild_only, rename = "join")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn trust_join(ctx: ApplicationContext<'_>, code: String) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+
+	let mut connection = ctx.data.database.get().await?;
+
+	if Guild::with_id(guild_id)
+		.select(guilds::trust_group_id)
+		.first::<Option<i32>>(&mut connection)
+		.await?
+		.is_some()
+	{
+		ctx.shout(ctx.translate("trust-already-member", None))
+			.await?;
+
+		return Ok(());
+	}
+
+	let Some(trust_group) = TrustGroup::with_invite_code(code.trim())
+		.first::<TrustGroup>(&mut connection)
+		.await
+		.optional()?
+	else {
+		ctx.shout(ctx.translate("trust_join-unknown-code", None))
+			.await?;
+
+		return Ok(());
+	};
+
+	diesel::update(Guild::with_id(guild_id))
+		.set(guilds::trust_group_id.eq(trust_group.id))
+		.execute(&mut connection)
+		.await?;
+
+	audit::record(
+		ctx.data,
+		guild_id,
+		AuditEntry::new(AuditKind::ConfigChanged)
+			.actor(ctx.interaction.user.id)
+			.details(format!("trust_group: {}", trust_group.name)),
+	)
+	.await;
+
+	ctx.shout(ctx.translate(
+		"trust_join-success",
+		Some(fluent_args!["name" => trust_group.name]),
+	))
+	.await?;
+
+	Ok(())
+}
+
+/// Leave the trust group, the verifications already shared are kept
+#[command(slash_command, guild_only, rename = "leave")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn trust_leave(ctx: ApplicationContext<'_>) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+
+	let mut connection = ctx.data.database.get().await?;
+
+	let Some(trust_group_id) = Guild::with_id(guild_id)
+		.select(guilds::trust_group_id)
+		.first::<Option<i32>>(&mut connection)
+		.await?
+	else {
+		ctx.shout(ctx.translate("trust-not-member", None)).await?;
+
+		return Ok(());
+	};
+
+	diesel::update(Guild::with_id(guild_id))
+		.set(guilds::trust_group_id.eq::<Option<i32>>(None))
+		.execute(&mut connection)
+		.await?;
+
+	// The identities of an empty group cannot be used anymore
+	if !dsl::select(dsl::exists(
+		guilds::table.filter(guilds::trust_group_id.eq(trust_group_id)),
+	))
+	.get_result::<bool>(&mut connection)
+	.await?
+	{
+		diesel::delete(trust_groups::table.find(trust_group_id))
+			.execute(&mut connection)
+			.await?;
+	}
+
+	audit::record(
+		ctx.data,
+		guild_id,
+		AuditEntry::new(AuditKind::ConfigChanged)
+			.actor(ctx.interaction.user.id)
+			.details("trust_group: none"),
+	)
+	.await;
+
+	ctx.shout(ctx.translate("done", None)).await?;
+
+	Ok(())
+}
+
+/// Show the trust group of the guild, its invite code and its guilds
+#[command(slash_command, guild_only, rename = "show")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn trust_show(ctx: ApplicationContext<'_>) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+
+	let mut connection = ctx.data.database.get().await?;
+
+	let Some(trust_group) = Guild::with_id(guild_id)
+		.inner_join(trust_groups::table)
+		.select(TrustGroup::as_select())
+		.first::<TrustGroup>(&mut connection)
+		.await
+		.optional()?
+	else {
+		ctx.shout(ctx.translate("trust-not-member", None)).await?;
+
+		return Ok(());
+	};
+
+	let guild_names = guilds::table
+		.filter(guilds::trust_group_id.eq(trust_group.id))
+		.select(guilds::name)
+		.load::<String>(&mut connection)
+		.await?;
+
+	ctx.shout(ctx.translate(
+		"trust_show-content",
+		Some(fluent_args![
+			"name" => trust_group.name,
+			"code" => trust_group.invite_code,
+			"guilds" => guild_names.join(", "),
+		]),
+	))
+	.await?;
+
+	Ok(())
+}
@@ -0,0 +1,146 @@
+//! Verify members by hand, e.g. students whose account does not work yet
+
+use crate::{
+	audit::{self, AuditEntry, AuditKind},
+	auth::UserMetadata,
+	commands::{classes::autocomplete_classes, levels::autocomplete_levels},
+	database::{
+		models::{Guild, Member, VerifiedMember},
+		prelude::*,
+		schema::{guilds, members},
+	},
+	states::{ApplicationContext, ApplicationContextPolyfill, InteractionResult},
+	translation::Translate,
+	verification::{
+		apply_changes, class_change::find_class, member_kind::MemberKind, VerifiedRole,
+	},
+};
+use diesel::dsl;
+use fluent::fluent_args;
+use poise::{
+	command,
+	serenity_prelude::{Mentionable, RoleId, User},
+};
+
+/// Verify members by hand
+#[allow(clippy::unused_async)]
+#[command(
+	slash_command,
+	subcommands("verify_manual"),
+	default_member_permissions = "ADMINISTRATOR",
+	required_bot_permissions = "MANAGE_ROLES"
+)]
+pub(crate) async fn verify(_: ApplicationContext<'_>) -> InteractionResult {
+	Ok(())
+}
+
+/// Verify a student without their account, they can verify themselves with it later
+#[allow(clippy::too_many_lines)]
+#[command(slash_command, guild_only, rename = "manual")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn verify_manual(
+	ctx: ApplicationContext<'_>,
+	user: User,
+	#[autocomplete = "autocomplete_levels"] level: String,
+	#[autocomplete = "autocomplete_classes"] class: String,
+	first_name: String,
+	last_name: String,
+) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+	let mut connection = ctx.data.database.get().await?;
+
+	let Some(verified_role_id) = Guild::with_id(guild_id)
+		.select(guilds::verified_role_id)
+		.first::<Option<u64>>(&mut connection)
+		.await?
+	else {
+		ctx.shout(ctx.translate("verify_manual-not-set-up", None))
+			.await?;
+
+		return Ok(());
+	};
+
+	let Some(member_id) = Member::with_ids(user.id, guild_id)
+		.select(members::id)
+		.first::<i32>(&mut connection)
+		.await
+		.optional()?
+	else {
+		ctx.shout(ctx.translate(
+			"error-member-not-registered",
+			Some(fluent_args!["user" => user.name]),
+		))
+		.await?;
+
+		return Ok(());
+	};
+
+	if dsl::select(dsl::exists(VerifiedMember::from_member_id(member_id)))
+		.get_result::<bool>(&mut connection)
+		.await?
+	{
+		ctx.shout(ctx.translate(
+			"verify_manual-already-verified",
+			Some(fluent_args!["user" => user.mention().to_string()]),
+		))
+		.await?;
+
+		return Ok(());
+	}
+
+	let Some(class_id) = find_class(&mut connection, guild_id, (&level, &class)).await? else {
+		ctx.shout(ctx.translate(
+			"verify_manual-class-not-found",
+			Some(fluent_args!["level" => level.as_str(), "class" => class.as_str()]),
+		))
+		.await?;
+
+		return Ok(());
+	};
+
+	ctx.defer_ephemeral().await?;
+
+	// There is no mail until the member verifies themselves with their account,
+	// so nothing is claimed and the empty mail is skipped when the claims are rebuilt
+	let user_data = UserMetadata {
+		mail: String::new(),
+		first_name,
+		last_name,
+		domain: None,
+		refresh_token: None,
+	};
+
+	apply_changes(
+		&ctx.data.http,
+		&mut connection,
+		(guild_id, user.id),
+		&user_data,
+		member_id,
+		VerifiedRole::Guild(RoleId::new(verified_role_id)),
+		MemberKind::Student,
+		Some(class_id),
+		Some(ctx.interaction.user.id),
+	)
+	.await?;
+
+	audit::record(
+		ctx.data,
+		guild_id,
+		AuditEntry::new(AuditKind::ManualVerification)
+			.actor(ctx.interaction.user.id)
+			.target(user.id)
+			.details(format!(
+				"{} {} ({level}, {class})",
+				user_data.first_name, user_data.last_name
+			)),
+	)
+	.await;
+
+	ctx.shout(ctx.translate(
+		"verify_manual-done",
+		Some(fluent_args!["user" => user.mention().to_string()]),
+	))
+	.await?;
+
+	Ok(())
+}
@@ -0,0 +1,169 @@
+//! Show the experience points earned by the members
+
+use crate::{
+	constants,
+	database::{prelude::*, schema::members},
+	states::{ApplicationContext, ApplicationContextPolyfill, InteractionResult},
+	translation::Translate,
+};
+use fluent::fluent_args;
+use poise::{
+	command,
+	serenity_prelude::{CreateEmbed, User},
+	ChoiceParameter, CreateReply,
+};
+use std::fmt::Write as _;
+
+/// The XP a leaderboard is sorted by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ChoiceParameter)]
+pub(crate) enum XpKind {
+	/// Message and voice XP together
+	#[default]
+	#[name = "total"]
+	Total,
+	/// XP earned with messages
+	#[name = "message"]
+	Message,
+	/// XP earned in voice channels
+	#[name = "vocal"]
+	Vocal,
+}
+
+impl XpKind {
+	/// Pick the XP of this kind
+	const fn of(self, (message_xp, vocal_xp): (i32, i32)) -> i32 {
+		match self {
+			Self::Total => message_xp.saturating_add(vocal_xp),
+			Self::Message => message_xp,
+			Self::Vocal => vocal_xp,
+		}
+	}
+}
+
+/// Show the XP and the rank of a member
+#[command(slash_command, guild_only)]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn rank(ctx: ApplicationContext<'_>, user: Option<User>) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+	let user = user.as_ref().unwrap_or(&ctx.interaction.user);
+	let mut connection = ctx.data.database.get().await?;
+
+	let Some((message_xp, vocal_xp)) = members::table
+		.filter(members::guild_id.eq(guild_id.get()))
+		.filter(members::discord_id.eq(user.id.get()))
+		.select((members::message_xp, members::vocal_xp))
+		.first::<(i32, i32)>(&mut connection)
+		.await
+		.optional()?
+	else {
+		ctx.shout(ctx.translate(
+			"rank-unknown-member",
+			Some(fluent_args!["user" => user.name.as_str()]),
+		))
+		.await?;
+
+		return Ok(());
+	};
+
+	let total = XpKind::Total.of((message_xp, vocal_xp));
+	let ahead: i64 = members::table
+		.filter(members::guild_id.eq(guild_id.get()))
+		.filter((members::message_xp + members::vocal_xp).gt(total))
+		.count()
+		.get_result(&mut connection)
+		.await?;
+
+	ctx.send(
+		CreateReply::default().ephemeral(true).embed(
+			CreateEmbed::new()
+				.title(ctx.translate(
+					"rank-title",
+					Some(fluent_args!["user" => user.name.as_str()]),
+				))
+				.field(
+					ctx.translate("rank-position", None),
+					format!("#{}", ahead + 1),
+					true,
+				)
+				.field(ctx.translate("rank-total", None), total.to_string(), true)
+				.field(
+					ctx.translate("rank-message", None),
+					message_xp.to_string(),
+					true,
+				)
+				.field(
+					ctx.translate("rank-vocal", None),
+					vocal_xp.to_string(),
+					true,
+				)
+				.color(0x0000_FF00),
+		),
+	)
+	.await?;
+
+	Ok(())
+}
+
+/// Show the members with the most XP
+#[command(slash_command, guild_only)]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn leaderboard(
+	ctx: ApplicationContext<'_>,
+	kind: Option<XpKind>,
+) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+	let kind = kind.unwrap_or_default();
+
+	let mut query = members::table
+		.filter(members::guild_id.eq(guild_id.get()))
+		.select((members::discord_id, members::message_xp, members::vocal_xp))
+		.limit(constants::xp::LEADERBOARD_SIZE)
+		.into_boxed();
+
+	query = match kind {
+		XpKind::Total => query.order_by((members::message_xp + members::vocal_xp).desc()),
+		XpKind::Message => query.order_by(members::message_xp.desc()),
+		XpKind::Vocal => query.order_by(members::vocal_xp.desc()),
+	};
+
+	let leaders = query
+		.load::<(u64, i32, i32)>(&mut ctx.data.database.get().await?)
+		.await?
+		.into_iter()
+		.filter(|(_, message_xp, vocal_xp)| kind.of((*message_xp, *vocal_xp)) > 0)
+		.collect::<Vec<_>>();
+
+	if leaders.is_empty() {
+		ctx.shout(ctx.translate("leaderboard-empty", None)).await?;
+
+		return Ok(());
+	}
+
+	let mut description = String::new();
+	for (position, (discord_id, message_xp, vocal_xp)) in (1..).zip(leaders) {
+		let _ = writeln!(
+			description,
+			"**{position}.** <@{discord_id}> — {} XP",
+			kind.of((message_xp, vocal_xp))
+		);
+	}
+
+	ctx.send(
+		CreateReply::default().ephemeral(true).embed(
+			CreateEmbed::new()
+				.title(ctx.translate(
+					match kind {
+						XpKind::Total => "leaderboard-title-total",
+						XpKind::Message => "leaderboard-title-message",
+						XpKind::Vocal => "leaderboard-title-vocal",
+					},
+					None,
+				))
+				.description(description)
+				.color(0x0000_FF00),
+		),
+	)
+	.await?;
+
+	Ok(())
+}
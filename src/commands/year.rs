@@ -0,0 +1,505 @@
+//! Move the students up a level when the school year rolls over
+
+use crate::{
+	commands::{levels::autocomplete_levels, removal},
+	database::{
+		models::{Class, Level},
+		prelude::*,
+		schema::{self, members, verified_members},
+	},
+	states::{ApplicationContext, ApplicationContextPolyfill, InteractionError, InteractionResult},
+	translation::Translate,
+	verification::swap_role,
+};
+use diesel_async::{scoped_futures::ScopedFutureExt, AsyncConnection};
+use fluent::fluent_args;
+use poise::{
+	command,
+	serenity_prelude::{RoleId, UserId},
+	CreateReply,
+};
+use std::{
+	collections::{BTreeSet, HashMap},
+	fmt::Write as _,
+	time::Duration,
+};
+
+/// The number of members whose roles are changed before pausing, to stay under the `Discord` rate limits
+const ROLLOVER_BATCH_SIZE: usize = 25;
+/// The pause between two batches of role changes
+const ROLLOVER_BATCH_PAUSE: Duration = Duration::from_secs(2);
+/// The length after which the preview is cut, messages are limited to 2000 characters
+const MAX_PREVIEW_LENGTH: usize = 1800;
+/// The number of members whose roles could not be changed that are mentioned at the end of the rollover
+const MAX_REPORTED_FAILURES: usize = 20;
+
+/// Configure and apply the school year rollover
+#[allow(clippy::unused_async)]
+#[command(
+	slash_command,
+	subcommands("year_next", "year_graduate", "year_rollover"),
+	default_member_permissions = "MANAGE_ROLES",
+	required_bot_permissions = "MANAGE_ROLES"
+)]
+pub(crate) async fn year(_: ApplicationContext<'_>) -> InteractionResult {
+	Ok(())
+}
+
+/// Set the level that the students of a level move to, or keep them in place
+#[command(slash_command, guild_only, rename = "next")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn year_next(
+	ctx: ApplicationContext<'_>,
+	#[autocomplete = "autocomplete_levels"] level: String,
+	#[autocomplete = "autocomplete_levels"] next_level: Option<String>,
+) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+	let mut connection = ctx.data.database.get().await?;
+
+	let Some(level_id) = Level::all_from_guild(guild_id)
+		.filter(schema::levels::name.eq(&level))
+		.select(schema::levels::id)
+		.first::<i32>(&mut connection)
+		.await
+		.optional()?
+	else {
+		ctx.shout(ctx.translate("levels_remove-not-found", None))
+			.await?;
+
+		return Ok(());
+	};
+
+	let next_level_id = if let Some(next_level) = &next_level {
+		let Some(next_level_id) = Level::all_from_guild(guild_id)
+			.filter(schema::levels::name.eq(next_level))
+			.filter(schema::levels::id.ne(level_id))
+			.select(schema::levels::id)
+			.first::<i32>(&mut connection)
+			.await
+			.optional()?
+		else {
+			ctx.shout(ctx.translate(
+				"levels_remove-no-such-target",
+				Some(fluent_args!["level" => next_level.as_str()]),
+			))
+			.await?;
+
+			return Ok(());
+		};
+
+		Some(next_level_id)
+	} else {
+		None
+	};
+
+	diesel::update(Level::with_id(level_id))
+		.set((
+			schema::levels::next_level_id.eq(next_level_id),
+			schema::levels::graduating.eq(false),
+		))
+		.execute(&mut connection)
+		.await?;
+
+	let message = next_level.map_or_else(
+		|| {
+			ctx.translate(
+				"year_next-cleared",
+				Some(fluent_args!["level" => level.as_str()]),
+			)
+		},
+		|next_level| {
+			ctx.translate(
+				"year_next-success",
+				Some(fluent_args!["level" => level.as_str(), "next_level" => next_level]),
+			)
+		},
+	);
+	ctx.shout(message).await?;
+
+	Ok(())
+}
+
+/// Mark a level as the last one, its students graduate when the school year rolls over
+#[command(slash_command, guild_only, rename = "graduate")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn year_graduate(
+	ctx: ApplicationContext<'_>,
+	#[autocomplete = "autocomplete_levels"] level: String,
+) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+	let mut connection = ctx.data.database.get().await?;
+
+	let updated =
+		diesel::update(Level::all_from_guild(guild_id).filter(schema::levels::name.eq(&level)))
+			.set((
+				schema::levels::next_level_id.eq::<Option<i32>>(None),
+				schema::levels::graduating.eq(true),
+			))
+			.execute(&mut connection)
+			.await?;
+
+	if updated == 0 {
+		ctx.shout(ctx.translate("levels_remove-not-found", None))
+			.await?;
+	} else {
+		ctx.shout(ctx.translate(
+			"year_graduate-success",
+			Some(fluent_args!["level" => level]),
+		))
+		.await?;
+	}
+
+	Ok(())
+}
+
+/// The class of the next level that corresponds to a class
+///
+/// `6eA` goes to `5eA` when the levels are `6e` and `5e`, otherwise classes are matched by their position in the level
+fn corresponding_class<'a>(
+	(level, next_level): (&Level, &Level),
+	(class, position): (&Class, usize),
+	next_classes: &[&'a Class],
+) -> Option<&'a Class> {
+	class
+		.name
+		.strip_prefix(&level.name)
+		.and_then(|suffix| {
+			let name = format!("{}{suffix}", next_level.name);
+			next_classes
+				.iter()
+				.find(|next_class| next_class.name == name)
+		})
+		.or_else(|| next_classes.get(position))
+		.copied()
+}
+
+/// The students of a class and where they go
+struct ClassMove<'a> {
+	/// The class the students leave
+	from: &'a Class,
+	/// The level of the class
+	level: &'a Level,
+	/// The class and level the students go to, `None` if no class corresponds
+	to: Option<(&'a Class, &'a Level)>,
+	/// The verified members of the class, with their `Discord` user id
+	members: Vec<removal::AffectedMember>,
+}
+
+/// Preview and apply the school year rollover, graduates can be unverified
+#[allow(clippy::too_many_lines)]
+#[command(slash_command, guild_only, rename = "rollover")]
+#[tracing::instrument(skip(ctx), fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn year_rollover(
+	ctx: ApplicationContext<'_>,
+	unverify_graduates: Option<bool>,
+) -> InteractionResult {
+	let guild_id = ctx.guild_only_id();
+	let unverify_graduates = unverify_graduates.unwrap_or(false);
+	let mut connection = ctx.data.database.get().await?;
+
+	let levels: Vec<Level> = Level::all_from_guild(guild_id)
+		.order_by(schema::levels::name)
+		.load(&mut connection)
+		.await?;
+	let classes: Vec<Class> = Class::all_from_guild(guild_id)
+		.order_by(schema::classes::name)
+		.load(&mut connection)
+		.await?;
+
+	let mut members_of_classes = HashMap::<i32, Vec<removal::AffectedMember>>::new();
+	for (member_id, discord_id, class_id) in verified_members::table
+		.inner_join(members::table)
+		.filter(members::guild_id.eq(guild_id.get()))
+		.filter(verified_members::class_id.is_not_null())
+		.select((
+			verified_members::member_id,
+			members::discord_id,
+			verified_members::class_id,
+		))
+		.load::<(i32, u64, Option<i32>)>(&mut connection)
+		.await?
+	{
+		if let Some(class_id) = class_id {
+			members_of_classes
+				.entry(class_id)
+				.or_default()
+				.push(removal::AffectedMember {
+					member_id,
+					user_id: UserId::new(discord_id),
+				});
+		}
+	}
+
+	let classes_of_level = |level_id: i32| {
+		classes
+			.iter()
+			.filter(move |class| class.level_id == level_id)
+			.collect::<Vec<_>>()
+	};
+
+	let mut moves = Vec::new();
+	let mut graduates = Vec::new();
+	for level in &levels {
+		if level.graduating {
+			for class in classes_of_level(level.id) {
+				graduates.push(ClassMove {
+					from: class,
+					level,
+					to: None,
+					members: members_of_classes.remove(&class.id).unwrap_or_default(),
+				});
+			}
+		} else if let Some(next_level) = level
+			.next_level_id
+			.and_then(|next_level_id| levels.iter().find(|level| level.id == next_level_id))
+		{
+			let next_classes = classes_of_level(next_level.id);
+
+			for (position, class) in classes_of_level(level.id).into_iter().enumerate() {
+				moves.push(ClassMove {
+					from: class,
+					level,
+					to: corresponding_class((level, next_level), (class, position), &next_classes)
+						.map(|next_class| (next_class, next_level)),
+					members: members_of_classes.remove(&class.id).unwrap_or_default(),
+				});
+			}
+		}
+	}
+
+	if moves
+		.iter()
+		.chain(&graduates)
+		.all(|class_move| class_move.members.is_empty())
+	{
+		ctx.shout(ctx.translate("year_rollover-nothing-to-do", None))
+			.await?;
+
+		return Ok(());
+	}
+
+	let mut preview = ctx.translate("year_rollover-preview", None);
+	for class_move in &moves {
+		let line = class_move.to.map_or_else(
+			|| {
+				ctx.translate(
+					"year_rollover-preview-unmapped",
+					Some(fluent_args![
+						"class" => class_move.from.name.as_str(),
+						"count" => class_move.members.len(),
+					]),
+				)
+			},
+			|(next_class, _)| {
+				ctx.translate(
+					"year_rollover-preview-move",
+					Some(fluent_args![
+						"class" => class_move.from.name.as_str(),
+						"next_class" => next_class.name.as_str(),
+						"count" => class_move.members.len(),
+					]),
+				)
+			},
+		);
+		let _ = write!(preview, "\n- {line}");
+	}
+	for class_move in &graduates {
+		let line = ctx.translate(
+			if unverify_graduates {
+				"year_rollover-preview-graduate-unverify"
+			} else {
+				"year_rollover-preview-graduate"
+			},
+			Some(fluent_args![
+				"class" => class_move.from.name.as_str(),
+				"count" => class_move.members.len(),
+			]),
+		);
+		let _ = write!(preview, "\n- {line}");
+	}
+	if preview.len() > MAX_PREVIEW_LENGTH {
+		let mut end = MAX_PREVIEW_LENGTH;
+		while !preview.is_char_boundary(end) {
+			end -= 1;
+		}
+		preview.truncate(end);
+		preview.push('…');
+	}
+
+	if !removal::confirm(&ctx, preview).await? {
+		return Ok(());
+	}
+
+	let moves = moves
+		.into_iter()
+		.filter_map(|class_move| {
+			class_move
+				.to
+				.map(|to| (class_move.from, class_move.level, to, class_move.members))
+		})
+		.collect::<Vec<_>>();
+	let graduates = graduates
+		.into_iter()
+		.filter(|_| unverify_graduates)
+		.collect::<Vec<_>>();
+	let graduate_members = graduates
+		.iter()
+		.flat_map(|class_move| class_move.members.iter().copied())
+		.collect::<Vec<_>>();
+
+	// Every member is moved at once, so a student is never moved twice
+	let mut role_changes = Vec::new();
+	for (from, level, (to, next_level), members) in &moves {
+		let user_ids = members
+			.iter()
+			.map(|member| member.user_id)
+			.collect::<Vec<_>>();
+
+		role_changes.push((
+			user_ids.clone(),
+			(
+				Some(RoleId::new(from.role_id)),
+				Some(RoleId::new(to.role_id)),
+			),
+		));
+		role_changes.push((
+			user_ids,
+			(
+				Some(RoleId::new(level.role_id)),
+				Some(RoleId::new(next_level.role_id)),
+			),
+		));
+	}
+	if !graduate_members.is_empty() {
+		for (role_id, user_ids) in
+			removal::verified_roles(&mut connection, guild_id, &graduate_members).await?
+		{
+			role_changes.push((user_ids, (Some(role_id), None)));
+		}
+		for class_move in &graduates {
+			let user_ids = class_move
+				.members
+				.iter()
+				.map(|member| member.user_id)
+				.collect::<Vec<_>>();
+
+			role_changes.push((
+				user_ids.clone(),
+				(Some(RoleId::new(class_move.from.role_id)), None),
+			));
+			role_changes.push((
+				user_ids,
+				(Some(RoleId::new(class_move.level.role_id)), None),
+			));
+		}
+	}
+
+	let refresh_tokens = connection
+		.transaction::<_, InteractionError, _>(|connection| {
+			async {
+				for (_, _, (to, _), members) in &moves {
+					let member_ids = members
+						.iter()
+						.map(|member| member.member_id)
+						.collect::<Vec<_>>();
+
+					diesel::update(
+						verified_members::table
+							.filter(verified_members::member_id.eq_any(member_ids)),
+					)
+					.set(verified_members::class_id.eq(to.id))
+					.execute(connection)
+					.await?;
+				}
+
+				Ok(removal::unverify(connection, &graduate_members).await?)
+			}
+			.scope_boxed()
+		})
+		.await?;
+
+	// The graduates are forgotten now, whatever happens to the roles
+	removal::unverified(&ctx, &graduate_members, refresh_tokens, "graduated").await;
+
+	let total = role_changes
+		.iter()
+		.map(|(user_ids, _)| user_ids.len())
+		.sum::<usize>();
+	let progress = ctx
+		.shout(ctx.translate(
+			"year_rollover-progress",
+			Some(fluent_args!["done" => 0, "total" => total]),
+		))
+		.await?;
+
+	// The database is already updated, the other members must still get their roles
+	let mut failed = BTreeSet::new();
+	let mut done = 0;
+	for (user_ids, roles) in &role_changes {
+		for batch in user_ids.chunks(ROLLOVER_BATCH_SIZE) {
+			if let Err(error) = swap_role(ctx.http(), guild_id, batch, *roles).await {
+				tracing::warn!(error = ?error, "could not update the roles of a rollover batch");
+				failed.extend(batch.iter().copied());
+			}
+			done += batch.len();
+
+			// The interaction token expires after 15 minutes, the rollover must go on without it
+			if let Err(error) = progress
+				.edit(
+					poise::Context::Application(ctx),
+					CreateReply::default().content(ctx.translate(
+						"year_rollover-progress",
+						Some(fluent_args!["done" => done, "total" => total]),
+					)),
+				)
+				.await
+			{
+				tracing::warn!(error = ?error, "could not edit the rollover progress");
+			}
+
+			tokio::time::sleep(ROLLOVER_BATCH_PAUSE).await;
+		}
+	}
+
+	let moved = moves
+		.iter()
+		.map(|(_, _, _, members)| members.len())
+		.sum::<usize>();
+	let mut success = ctx.translate(
+		"year_rollover-success",
+		Some(fluent_args![
+			"moved" => moved,
+			"graduates" => graduate_members.len(),
+		]),
+	);
+	if !failed.is_empty() {
+		let mut members = failed
+			.iter()
+			.take(MAX_REPORTED_FAILURES)
+			.map(|user_id| format!("<@{user_id}>"))
+			.collect::<Vec<_>>()
+			.join(", ");
+		if failed.len() > MAX_REPORTED_FAILURES {
+			members.push_str(", …");
+		}
+
+		success.push('\n');
+		success.push_str(&ctx.translate(
+			"year_rollover-failed",
+			Some(fluent_args!["count" => failed.len(), "members" => members]),
+		));
+	}
+	if let Err(error) = progress
+		.edit(
+			poise::Context::Application(ctx),
+			CreateReply::default().content(success.clone()),
+		)
+		.await
+	{
+		tracing::warn!(error = ?error, "could not edit the rollover progress");
+
+		ctx.interaction.channel_id.say(ctx.http(), success).await?;
+	}
+
+	Ok(())
+}
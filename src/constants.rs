@@ -3,7 +3,10 @@
 use tokio::time::Duration;
 
 /// The timeout for the authentication process
-pub(crate) const AUTHENTICATION_TIMEOUT: Duration = Duration::from_secs(60 * 10);
+pub(crate) const AUTHENTICATION_TIMEOUT: Duration = Duration::from_mins(10);
+
+/// The identity provider used by guilds that did not select one
+pub(crate) const DEFAULT_IDENTITY_PROVIDER: &str = "google";
 
 /// The interaction identifiers for buttons interactions
 pub(crate) mod events {
@@ -11,21 +14,44 @@ pub(crate) mod events {
 	pub(crate) const LOGIN_BUTTON_INTERACTION: &str = "events.setup.button.login";
 	/// The setup message button logout interaction
 	pub(crate) const LOGOUT_BUTTON_INTERACTION: &str = "events.setup.button.logout";
+	/// The setup message button to change the class of a verified student, also offered by `/profile`
+	pub(crate) const CHANGE_CLASS_BUTTON_INTERACTION: &str = "events.setup.button.change-class";
 
 	/// Custom ID for the ok button in logout interaction
 	pub(crate) const LOGOUT_OK_BUTTON_INTERACTION: &str = "events.setup.button.logout.ok";
 	/// Custom ID for the cancel button in logout interaction
 	pub(crate) const LOGOUT_CANCEL_BUTTON_INTERACTION: &str = "events.setup.button.logout.cancel";
 
+	/// The prefix of the login event follow up member kind buttons, followed by the kind
+	pub(crate) const AUTHENTICATION_BUTTON_MEMBER_KIND_INTERACTION: &str =
+		"events.login.button.member-kind";
 	/// The login event follow up class selection interaction
 	pub(crate) const AUTHENTICATION_SELECT_MENU_CLASS_INTERACTION: &str =
 		"events.login.button.class-menu";
 	/// The login event follow up level selection interaction
 	pub(crate) const AUTHENTICATION_SELECT_MENU_LEVEL_INTERACTION: &str =
 		"events.login.button.level-menu";
+	/// The login event follow up button to reuse the verification of the trust group
+	pub(crate) const AUTHENTICATION_BUTTON_SHARED_IDENTITY_INTERACTION: &str =
+		"events.login.button.shared-identity";
+
+	/// Custom ID for the confirm button of a class or level removal
+	pub(crate) const REMOVAL_CONFIRM_BUTTON_INTERACTION: &str = "commands.removal.button.confirm";
+	/// Custom ID for the cancel button of a class or level removal
+	pub(crate) const REMOVAL_CANCEL_BUTTON_INTERACTION: &str = "commands.removal.button.cancel";
 
 	/// The login event follow up class selection interaction
 	pub(crate) const GROUPS_SELECT_MENU_INTERACTION: &str = "events.setup.groups.menu";
+
+	/// The prefix of the re-verification reminder button, followed by the guild id
+	pub(crate) const REVERIFY_BUTTON_INTERACTION: &str = "events.reverify.button.login";
+
+	/// The prefix of the button approving a class change, followed by the member id and the class id
+	pub(crate) const CLASS_CHANGE_APPROVE_BUTTON_INTERACTION: &str =
+		"events.class-change.button.approve";
+	/// The prefix of the button rejecting a class change, followed by the member id and the class id
+	pub(crate) const CLASS_CHANGE_REJECT_BUTTON_INTERACTION: &str =
+		"events.class-change.button.reject";
 }
 
 /// A set of URLs used in the library
@@ -37,17 +63,25 @@ pub(crate) mod urls {
 	/// The Google `OAuth2` revoke endpoint
 	pub(crate) const GOOGLE_REVOKE_ENDPOINT: &str = "https://oauth2.googleapis.com/revoke";
 
-	/// The Google `People API` endpoint
-	pub(crate) const GOOGLE_PEOPLE_API_ENDPOINT: &str =
-		"https://people.googleapis.com/v1/people/me";
+	/// The Google keys used to sign ID tokens
+	pub(crate) const GOOGLE_JWKS_ENDPOINT: &str = "https://www.googleapis.com/oauth2/v3/certs";
+	/// The issuers Google puts in ID tokens
+	///
+	/// <https://developers.google.com/identity/openid-connect/openid-connect#validatinganidtoken>
+	pub(crate) const GOOGLE_ISSUERS: [&str; 2] =
+		["https://accounts.google.com", "accounts.google.com"];
 }
 
-/// Google `OAuth2` scopes used in the authentification process
+/// `OAuth2` scopes used in the authentification process
 pub(crate) mod scopes {
-	/// User's email scope
-	pub(crate) const USER_INFO_EMAIL: &str = "https://www.googleapis.com/auth/userinfo.email";
-	/// User's informations scope
-	pub(crate) const USER_INFO_PROFILE: &str = "https://www.googleapis.com/auth/userinfo.profile";
+	/// `OpenID Connect` scope, to get an ID token
+	pub(crate) const OPENID: &str = "openid";
+	/// `OpenID Connect` email claims scope
+	pub(crate) const EMAIL: &str = "email";
+	/// `OpenID Connect` profile claims scope
+	pub(crate) const PROFILE: &str = "profile";
+	/// `OpenID Connect` scope to get a refresh token
+	pub(crate) const OFFLINE_ACCESS: &str = "offline_access";
 
 	// /// User's classrooms readonly scope
 	// pub(crate) const CLASSROOM_COURSES_READONLY: &str =
@@ -76,4 +110,65 @@ pub(crate) mod limits {
 	/// This is mostly dictated by the Discord API limit of 25 buttons per message
 	/// <https://discord.com/developers/docs/interactions/message-components#select-menu-object-select-menu-structure>
 	pub(crate) const MAX_GROUPS_PER_GUILD: u8 = 25;
+
+	/// The maximum number of email rules that can be created per guild
+	///
+	/// This is mostly dictated by the Discord API limit of 25 autocomplete choices
+	/// <https://discord.com/developers/docs/interactions/application-commands#autocomplete>
+	pub(crate) const MAX_EMAIL_RULES_PER_GUILD: u8 = 25;
+
+	/// The maximum size in bytes of an imported roster, enough for a few thousand students
+	pub(crate) const MAX_ROSTER_SIZE: u32 = 1024 * 1024;
+
+	/// The maximum number of XP rewards that can be created per guild
+	///
+	/// Each reward is checked for every member on each flush, so keep it small
+	pub(crate) const MAX_XP_REWARDS_PER_GUILD: u8 = 25;
+}
+
+/// Experience points earned by the members
+pub(crate) mod xp {
+	use tokio::time::Duration;
+
+	/// The XP earned by a message
+	pub(crate) const MESSAGE_XP: i32 = 10;
+	/// The delay before another message of the same member earns XP
+	pub(crate) const MESSAGE_COOLDOWN: Duration = Duration::from_mins(1);
+	/// The XP earned for each minute spent in a voice channel that is not the AFK one
+	pub(crate) const VOCAL_XP_PER_MINUTE: i32 = 5;
+	/// The interval between two writes of the earned XP to the database
+	pub(crate) const FLUSH_INTERVAL: Duration = Duration::from_mins(1);
+	/// The number of members shown in the leaderboard
+	pub(crate) const LEADERBOARD_SIZE: i64 = 10;
+}
+
+/// The audit log of the guilds
+pub(crate) mod audit {
+	/// The maximum number of events shown by a search
+	pub(crate) const SEARCH_LIMIT: i64 = 20;
+	/// How far back a search looks when no period is given
+	pub(crate) const DEFAULT_SEARCH_PERIOD: &str = "7d";
+	/// The maximum length of the details shown for each event of a search
+	pub(crate) const MAX_DETAILS_LENGTH: usize = 100;
+	/// The maximum length of an embed description on `Discord`
+	pub(crate) const MAX_DESCRIPTION_LENGTH: usize = 4096;
+}
+
+/// The expiry of the verifications
+pub(crate) mod reverification {
+	use chrono::TimeDelta;
+	use tokio::time::Duration;
+
+	/// The interval between two checks of the verifications that expire
+	pub(crate) const CHECK_INTERVAL: Duration = Duration::from_hours(1);
+	/// How long before their verification expires the members are reminded, at most
+	pub(crate) const REMINDER_LEAD: TimeDelta = TimeDelta::days(7);
+}
+
+/// The refresh tokens kept to notice deactivated accounts
+pub(crate) mod offline_access {
+	use tokio::time::Duration;
+
+	/// The interval between two refreshes of every token
+	pub(crate) const REFRESH_INTERVAL: Duration = Duration::from_hours(24);
 }
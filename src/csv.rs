@@ -0,0 +1,112 @@
+//! Minimal CSV reading and writing, as described in RFC 4180
+//!
+//! Spreadsheets configured in French export with `;` separators, so the separator is guessed from the header
+
+use thiserror::Error;
+
+/// Errors that can happen while reading a CSV file
+#[derive(Debug, Error)]
+pub(crate) enum CsvError {
+	/// A quoted field is never closed
+	#[error("a quoted field starting on line {0} is never closed")]
+	UnterminatedQuote(usize),
+	/// A quote appears in the middle of a field that is not quoted
+	#[error("unexpected quote on line {0}")]
+	UnexpectedQuote(usize),
+}
+
+/// Guess the separator from the first line
+fn separator(input: &str) -> char {
+	let header = input.lines().next().unwrap_or_default();
+
+	if header.contains(';') && !header.contains(',') {
+		';'
+	} else {
+		','
+	}
+}
+
+/// Read the records of a CSV file, empty lines are skipped
+pub(crate) fn parse(input: &str) -> Result<Vec<Vec<String>>, CsvError> {
+	let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+	let separator = separator(input);
+
+	let mut records = Vec::new();
+	let mut record = Vec::new();
+	let mut field = String::new();
+	let mut line = 1;
+
+	let mut chars = input.chars().peekable();
+	while let Some(char) = chars.next() {
+		match char {
+			'"' if field.is_empty() => {
+				let start = line;
+
+				loop {
+					match chars.next() {
+						Some('"') if chars.peek() == Some(&'"') => {
+							chars.next();
+							field.push('"');
+						}
+						Some('"') => break,
+						Some(char) => {
+							if char == '\n' {
+								line += 1;
+							}
+							field.push(char);
+						}
+						None => return Err(CsvError::UnterminatedQuote(start)),
+					}
+				}
+			}
+			'"' => return Err(CsvError::UnexpectedQuote(line)),
+			'\r' if chars.peek() == Some(&'\n') => {}
+			'\n' => {
+				record.push(std::mem::take(&mut field));
+				if record.iter().any(|field| !field.is_empty()) {
+					records.push(std::mem::take(&mut record));
+				}
+				record.clear();
+				line += 1;
+			}
+			char if char == separator => record.push(std::mem::take(&mut field)),
+			char => field.push(char),
+		}
+	}
+
+	record.push(field);
+	if record.iter().any(|field| !field.is_empty()) {
+		records.push(record);
+	}
+
+	Ok(records)
+}
+
+/// Append a record to a CSV file, quoting the fields when needed
+///
+/// Fields that a spreadsheet would run as a formula are prefixed with `'`, they may come from the members
+pub(crate) fn write_record<'a>(output: &mut String, fields: impl IntoIterator<Item = &'a str>) {
+	for (index, field) in fields.into_iter().enumerate() {
+		if index != 0 {
+			output.push(',');
+		}
+
+		let escaped;
+		let field = if field.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+			escaped = format!("'{field}");
+			escaped.as_str()
+		} else {
+			field
+		};
+
+		if field.contains([',', ';', '"', '\n', '\r']) {
+			output.push('"');
+			output.push_str(&field.replace('"', "\"\""));
+			output.push('"');
+		} else {
+			output.push_str(field);
+		}
+	}
+
+	output.push_str("\r\n");
+}
@@ -4,8 +4,11 @@
 // TODO: build a macro to reduce boilerplate and generate ids struct for each table with a `AsExpression` implementation
 
 use super::schema::{
-	classes, groups, groups_of_verified_members, guilds, levels, members, verified_members,
+	audit_events, classes, groups, groups_of_verified_members, guild_email_rules, guilds,
+	identities, levels, members, pending_logins, roster_entries, trust_groups, verified_members,
+	xp_rewards,
 };
+use chrono::NaiveDateTime;
 use diesel::{AsChangeset, Associations, Identifiable, Insertable, Queryable, Selectable};
 
 /// Represent a `Discord` guild
@@ -16,11 +19,23 @@ pub(crate) struct Guild {
 	pub(crate) name: String,
 	pub(crate) owner_id: u64,
 
-	pub(crate) verification_email_domain: Option<String>,
 	pub(crate) verified_role_id: Option<u64>,
+	pub(crate) teacher_role_id: Option<u64>,
+	pub(crate) staff_role_id: Option<u64>,
+	pub(crate) identity_provider: Option<String>,
 
 	pub(crate) login_message_id: Option<u64>,
 	pub(crate) groups_message_id: Option<u64>,
+	pub(crate) groups_message_channel_id: Option<u64>,
+	pub(crate) level_up_channel_id: Option<u64>,
+	pub(crate) log_channel_id: Option<u64>,
+
+	pub(crate) verified_role_policy: String,
+	pub(crate) reverification_days: Option<i32>,
+	pub(crate) offline_access: bool,
+	pub(crate) trust_group_id: Option<i32>,
+	pub(crate) duplicate_mail_policy: String,
+	pub(crate) class_change_approval: bool,
 }
 
 /// Use to create a new [`Guild`]
@@ -32,11 +47,40 @@ pub(crate) struct NewGuild<'a> {
 	pub(crate) name: &'a str,
 	pub(crate) owner_id: u64,
 
-	pub(crate) verification_email_domain: Option<&'a str>,
 	pub(crate) verified_role_id: Option<u64>,
+	pub(crate) teacher_role_id: Option<u64>,
+	pub(crate) staff_role_id: Option<u64>,
+	pub(crate) identity_provider: Option<&'a str>,
 
 	pub(crate) login_message_id: Option<u64>,
 	pub(crate) groups_message_id: Option<u64>,
+	pub(crate) groups_message_channel_id: Option<u64>,
+	pub(crate) level_up_channel_id: Option<u64>,
+	pub(crate) log_channel_id: Option<u64>,
+}
+
+/// Represent a rule that allows some emails to verify themselves in a [`Guild`]
+#[derive(Debug, PartialEq, Eq, Queryable, Identifiable, Selectable, Associations)]
+#[diesel(table_name = guild_email_rules, belongs_to(Guild))]
+pub(crate) struct GuildEmailRule {
+	pub(crate) id: i32,
+
+	pub(crate) guild_id: u64,
+	pub(crate) kind: String,
+	pub(crate) pattern: String,
+	pub(crate) role_id: Option<u64>,
+	pub(crate) member_kind: Option<String>,
+}
+
+/// Use to create a new [`GuildEmailRule`]
+#[derive(Debug, Insertable, AsChangeset)]
+#[diesel(table_name = guild_email_rules)]
+pub(crate) struct NewGuildEmailRule<'a> {
+	pub(crate) guild_id: u64,
+	pub(crate) kind: &'a str,
+	pub(crate) pattern: &'a str,
+	pub(crate) role_id: Option<u64>,
+	pub(crate) member_kind: Option<&'a str>,
 }
 
 /// Represent a known user with `Discord` metadata and some other informations
@@ -71,8 +115,18 @@ pub(crate) struct VerifiedMember {
 	pub(crate) mail: String,
 	pub(crate) first_name: String,
 	pub(crate) last_name: String,
+	pub(crate) member_kind: String,
 
-	pub(crate) class_id: i32,
+	pub(crate) class_id: Option<i32>,
+
+	pub(crate) verified_at: NaiveDateTime,
+	pub(crate) reminded_at: Option<NaiveDateTime>,
+
+	pub(crate) refresh_token: Option<Vec<u8>>,
+	pub(crate) token_provider: Option<String>,
+
+	pub(crate) identity_id: Option<i32>,
+	pub(crate) verified_by: Option<u64>,
 }
 
 /// Use to create a new [`VerifiedMember`]
@@ -84,8 +138,12 @@ pub(crate) struct NewVerifiedMember<'a> {
 	pub(crate) first_name: &'a str,
 	pub(crate) last_name: &'a str,
 	pub(crate) mail: &'a str,
+	pub(crate) member_kind: &'a str,
 
-	pub(crate) class_id: i32,
+	pub(crate) class_id: Option<i32>,
+
+	pub(crate) verified_at: NaiveDateTime,
+	pub(crate) verified_by: Option<u64>,
 }
 
 /// Represent a Level
@@ -97,6 +155,10 @@ pub(crate) struct Level {
 	pub(crate) name: String,
 	pub(crate) guild_id: u64,
 	pub(crate) role_id: u64,
+
+	pub(crate) next_level_id: Option<i32>,
+	pub(crate) graduating: bool,
+	pub(crate) broken: bool,
 }
 
 /// Use to create a new [`Level`]
@@ -118,6 +180,7 @@ pub(crate) struct Class {
 
 	pub(crate) guild_id: u64,
 	pub(crate) role_id: u64,
+	pub(crate) broken: bool,
 }
 
 /// Use to create a new [`Class`]
@@ -141,6 +204,7 @@ pub(crate) struct Group {
 
 	pub(crate) guild_id: u64,
 	pub(crate) role_id: u64,
+	pub(crate) broken: bool,
 }
 
 /// Use to create a new [`Group`]
@@ -168,3 +232,171 @@ pub(crate) struct NewGroupOfVerifiedMember {
 	pub(crate) verified_member_id: i32,
 	pub(crate) group_id: i32,
 }
+
+/// Represent an `OAuth2` login started from `Discord` that is waiting for the provider callback
+#[derive(Debug, PartialEq, Eq, Queryable, Identifiable, Selectable, Associations)]
+#[diesel(table_name = pending_logins, belongs_to(Guild), belongs_to(Class), primary_key(csrf_state))]
+pub(crate) struct PendingLogin {
+	pub(crate) csrf_state: String,
+
+	pub(crate) guild_id: u64,
+	pub(crate) discord_id: u64,
+	pub(crate) username: String,
+	pub(crate) guild_image_source: String,
+	pub(crate) locale: String,
+	pub(crate) interaction_token: String,
+	pub(crate) identity_provider: String,
+	pub(crate) nonce: String,
+	pub(crate) member_kind: String,
+
+	pub(crate) class_id: Option<i32>,
+
+	pub(crate) expires_at: NaiveDateTime,
+}
+
+/// Use to create a new [`PendingLogin`]
+#[derive(Debug, Insertable, AsChangeset)]
+#[diesel(table_name = pending_logins)]
+pub(crate) struct NewPendingLogin<'a> {
+	pub(crate) csrf_state: &'a str,
+
+	pub(crate) guild_id: u64,
+	pub(crate) discord_id: u64,
+	pub(crate) username: &'a str,
+	pub(crate) guild_image_source: &'a str,
+	pub(crate) locale: &'a str,
+	pub(crate) interaction_token: &'a str,
+	pub(crate) identity_provider: &'a str,
+	pub(crate) nonce: &'a str,
+	pub(crate) member_kind: &'a str,
+
+	pub(crate) class_id: Option<i32>,
+
+	pub(crate) expires_at: NaiveDateTime,
+}
+
+/// Represent a student of the school roster, bound to a [`Class`] before logging in
+#[derive(Debug, PartialEq, Eq, Queryable, Identifiable, Selectable, Associations)]
+#[diesel(table_name = roster_entries, belongs_to(Guild), belongs_to(Class))]
+pub(crate) struct RosterEntry {
+	pub(crate) id: i32,
+
+	pub(crate) guild_id: u64,
+	pub(crate) mail: String,
+	pub(crate) first_name: String,
+	pub(crate) last_name: String,
+
+	pub(crate) class_id: i32,
+}
+
+/// Use to create a new [`RosterEntry`]
+#[derive(Debug, Insertable, AsChangeset)]
+#[diesel(table_name = roster_entries)]
+pub(crate) struct NewRosterEntry<'a> {
+	pub(crate) guild_id: u64,
+	pub(crate) mail: &'a str,
+	pub(crate) first_name: &'a str,
+	pub(crate) last_name: &'a str,
+
+	pub(crate) class_id: i32,
+}
+
+/// Represent a role granted to the members of a [`Guild`] once they earned enough XP
+#[derive(Debug, PartialEq, Eq, Queryable, Identifiable, Selectable, Associations)]
+#[diesel(table_name = xp_rewards, belongs_to(Guild))]
+pub(crate) struct XpReward {
+	pub(crate) id: i32,
+
+	pub(crate) guild_id: u64,
+	pub(crate) threshold: i32,
+	pub(crate) role_id: u64,
+}
+
+/// Use to create a new [`XpReward`]
+#[derive(Debug, Insertable, AsChangeset)]
+#[diesel(table_name = xp_rewards)]
+pub(crate) struct NewXpReward {
+	pub(crate) guild_id: u64,
+	pub(crate) threshold: i32,
+	pub(crate) role_id: u64,
+}
+
+/// Represent something that happened in a [`Guild`], shown to the admins
+#[derive(Debug, PartialEq, Eq, Queryable, Identifiable, Selectable, Associations)]
+#[diesel(table_name = audit_events, belongs_to(Guild))]
+pub(crate) struct AuditEvent {
+	pub(crate) id: i32,
+
+	pub(crate) guild_id: u64,
+	pub(crate) kind: String,
+	pub(crate) actor_id: Option<u64>,
+	pub(crate) target_id: Option<u64>,
+	pub(crate) details: Option<String>,
+
+	pub(crate) created_at: NaiveDateTime,
+}
+
+/// Use to create a new [`AuditEvent`]
+#[derive(Debug, Insertable, AsChangeset)]
+#[diesel(table_name = audit_events)]
+pub(crate) struct NewAuditEvent<'a> {
+	pub(crate) guild_id: u64,
+	pub(crate) kind: &'a str,
+	pub(crate) actor_id: Option<u64>,
+	pub(crate) target_id: Option<u64>,
+	pub(crate) details: Option<&'a str>,
+
+	pub(crate) created_at: NaiveDateTime,
+}
+
+/// Represent guilds that reuse the verifications of each other
+#[derive(Debug, PartialEq, Eq, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = trust_groups)]
+pub(crate) struct TrustGroup {
+	pub(crate) id: i32,
+
+	pub(crate) name: String,
+	pub(crate) invite_code: String,
+}
+
+/// Use to create a new [`TrustGroup`]
+#[derive(Debug, Insertable, AsChangeset)]
+#[diesel(table_name = trust_groups)]
+pub(crate) struct NewTrustGroup<'a> {
+	pub(crate) name: &'a str,
+	pub(crate) invite_code: &'a str,
+}
+
+/// Represent an account proven by a user in a [`TrustGroup`]
+#[derive(Debug, PartialEq, Eq, Queryable, Identifiable, Selectable, Associations)]
+#[diesel(table_name = identities, belongs_to(TrustGroup))]
+pub(crate) struct Identity {
+	pub(crate) id: i32,
+
+	pub(crate) trust_group_id: i32,
+	pub(crate) discord_id: u64,
+
+	pub(crate) mail: String,
+	pub(crate) first_name: String,
+	pub(crate) last_name: String,
+	pub(crate) domain: Option<String>,
+	pub(crate) provider: String,
+
+	pub(crate) verified_at: NaiveDateTime,
+}
+
+/// Use to create a new [`Identity`]
+#[derive(Debug, Insertable, AsChangeset)]
+#[diesel(table_name = identities)]
+pub(crate) struct NewIdentity<'a> {
+	pub(crate) trust_group_id: i32,
+	pub(crate) discord_id: u64,
+
+	pub(crate) mail: &'a str,
+	pub(crate) first_name: &'a str,
+	pub(crate) last_name: &'a str,
+	pub(crate) domain: Option<&'a str>,
+	pub(crate) provider: &'a str,
+
+	pub(crate) verified_at: NaiveDateTime,
+}
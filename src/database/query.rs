@@ -5,21 +5,31 @@
 
 use super::{
 	models::{
-		Class, Group, GroupOfVerifiedMember, Guild, Level, Member, NewClass, NewGroup,
-		NewGroupOfVerifiedMember, NewLevel, NewMember, NewVerifiedMember, VerifiedMember,
+		AuditEvent, Class, Group, GroupOfVerifiedMember, Guild, GuildEmailRule, Identity, Level,
+		Member, NewAuditEvent, NewClass, NewGroup, NewGroupOfVerifiedMember, NewGuildEmailRule,
+		NewLevel, NewMember, NewPendingLogin, NewRosterEntry, NewTrustGroup, NewVerifiedMember,
+		NewXpReward, PendingLogin, RosterEntry, TrustGroup, VerifiedMember, XpReward,
 	},
 	prelude::*,
 	schema::{
-		classes, groups, groups_of_verified_members, guilds, levels, members, verified_members,
+		audit_events, classes, groups, groups_of_verified_members, guild_email_rules, guilds,
+		identities, levels, members, pending_logins, roster_entries, trust_groups,
+		verified_members, xp_rewards,
 	},
 };
+use chrono::NaiveDateTime;
 use diesel::{
-	dsl::insert_into,
-	helper_types::{Eq, Filter, Find, InnerJoin},
-	query_builder::InsertStatement,
+	dsl::{insert_into, replace_into},
+	helper_types::{Eq, Filter, Find, InnerJoin, Lt},
+	query_builder::{InsertStatement, ReplaceStatement},
 };
 use poise::serenity_prelude::{GuildId, UserId};
 
+diesel::define_sql_function! {
+	/// The text in lower case, whatever the collation of the column
+	fn lower(text: diesel::sql_types::Varchar) -> diesel::sql_types::Varchar;
+}
+
 impl Class {
 	/// Select classes from their [`GuildId`]
 	#[inline]
@@ -62,6 +72,35 @@ impl Guild {
 	}
 }
 
+impl GuildEmailRule {
+	/// Select the email rules of a guild from its [`GuildId`]
+	#[inline]
+	pub(crate) fn all_from_guild(
+		guild_id: GuildId,
+	) -> Filter<guild_email_rules::table, Eq<guild_email_rules::guild_id, u64>> {
+		guild_email_rules::table.filter(guild_email_rules::guild_id.eq(guild_id.get()))
+	}
+
+	/// Select an email rule from its `id`
+	#[inline]
+	pub(crate) fn with_id(rule_id: i32) -> Find<guild_email_rules::table, i32> {
+		guild_email_rules::table.find(rule_id)
+	}
+}
+
+impl<'a> NewGuildEmailRule<'a> {
+	/// Prepare a [`NewGuildEmailRule`] insert
+	#[inline]
+	pub(crate) fn insert(
+		&'a self,
+	) -> InsertStatement<
+		guild_email_rules::table,
+		<&'a Self as Insertable<guild_email_rules::table>>::Values,
+	> {
+		insert_into(guild_email_rules::table).values(self)
+	}
+}
+
 impl Member {
 	/// Select member from his [`GuildId`] and [`UserId`]
 	#[inline]
@@ -206,3 +245,144 @@ impl NewGroupOfVerifiedMember {
 		insert_into(groups_of_verified_members::table).values(self)
 	}
 }
+
+impl PendingLogin {
+	/// Select a pending login from its CSRF state
+	#[inline]
+	pub(crate) fn with_state(csrf_state: &str) -> Find<pending_logins::table, &str> {
+		pending_logins::table.find(csrf_state)
+	}
+
+	/// Select pending logins that expired before the given date
+	#[inline]
+	pub(crate) fn all_expired(
+		now: NaiveDateTime,
+	) -> Filter<pending_logins::table, Lt<pending_logins::expires_at, NaiveDateTime>> {
+		pending_logins::table.filter(pending_logins::expires_at.lt(now))
+	}
+}
+
+impl<'a> NewPendingLogin<'a> {
+	/// Prepare a [`NewPendingLogin`] insert
+	#[inline]
+	pub(crate) fn insert(
+		&'a self,
+	) -> InsertStatement<
+		pending_logins::table,
+		<&'a Self as Insertable<pending_logins::table>>::Values,
+	> {
+		insert_into(pending_logins::table).values(self)
+	}
+}
+
+impl RosterEntry {
+	/// Select the roster entries of a guild from its [`GuildId`]
+	#[inline]
+	pub(crate) fn all_from_guild(
+		guild_id: GuildId,
+	) -> Filter<roster_entries::table, Eq<roster_entries::guild_id, u64>> {
+		roster_entries::table.filter(roster_entries::guild_id.eq(guild_id.get()))
+	}
+
+	/// Select the roster entry of a mail in a guild, mails are stored in lower case
+	#[inline]
+	pub(crate) fn with_mail(
+		guild_id: GuildId,
+		mail: &str,
+	) -> Filter<
+		Filter<roster_entries::table, Eq<roster_entries::guild_id, u64>>,
+		Eq<roster_entries::mail, String>,
+	> {
+		Self::all_from_guild(guild_id).filter(roster_entries::mail.eq(mail.to_lowercase()))
+	}
+}
+
+impl<'a> NewRosterEntry<'a> {
+	/// Prepare a [`NewRosterEntry`] insert that replaces the entry with the same mail
+	#[inline]
+	pub(crate) fn replace(
+		&'a self,
+	) -> ReplaceStatement<
+		roster_entries::table,
+		<&'a Self as Insertable<roster_entries::table>>::Values,
+	> {
+		replace_into(roster_entries::table).values(self)
+	}
+}
+
+impl XpReward {
+	/// Select the XP rewards of a guild from its [`GuildId`]
+	#[inline]
+	pub(crate) fn all_from_guild(
+		guild_id: GuildId,
+	) -> Filter<xp_rewards::table, Eq<xp_rewards::guild_id, u64>> {
+		xp_rewards::table.filter(xp_rewards::guild_id.eq(guild_id.get()))
+	}
+}
+
+impl NewXpReward {
+	/// Prepare a [`NewXpReward`] insert
+	#[inline]
+	pub(crate) fn insert(
+		&self,
+	) -> InsertStatement<xp_rewards::table, <&Self as Insertable<xp_rewards::table>>::Values> {
+		insert_into(xp_rewards::table).values(self)
+	}
+}
+
+impl AuditEvent {
+	/// Select the audit events of a guild from its [`GuildId`]
+	#[inline]
+	pub(crate) fn all_from_guild(
+		guild_id: GuildId,
+	) -> Filter<audit_events::table, Eq<audit_events::guild_id, u64>> {
+		audit_events::table.filter(audit_events::guild_id.eq(guild_id.get()))
+	}
+}
+
+impl NewAuditEvent<'_> {
+	/// Prepare a [`NewAuditEvent`] insert
+	#[inline]
+	pub(crate) fn insert(
+		&self,
+	) -> InsertStatement<audit_events::table, <&Self as Insertable<audit_events::table>>::Values> {
+		insert_into(audit_events::table).values(self)
+	}
+}
+
+impl TrustGroup {
+	/// Select a trust group from its invite code
+	#[inline]
+	pub(crate) fn with_invite_code(
+		invite_code: &str,
+	) -> Filter<trust_groups::table, Eq<trust_groups::invite_code, &str>> {
+		trust_groups::table.filter(trust_groups::invite_code.eq(invite_code))
+	}
+}
+
+impl<'a> NewTrustGroup<'a> {
+	/// Prepare a [`NewTrustGroup`] insert
+	#[inline]
+	pub(crate) fn insert(
+		&'a self,
+	) -> InsertStatement<trust_groups::table, <&'a Self as Insertable<trust_groups::table>>::Values>
+	{
+		insert_into(trust_groups::table).values(self)
+	}
+}
+
+impl Identity {
+	/// Select the identity of a user in a trust group
+	#[inline]
+	pub(crate) fn with_ids(
+		user_id: UserId,
+		trust_group_id: i32,
+	) -> Filter<
+		Filter<identities::table, Eq<identities::discord_id, u64>>,
+		Eq<identities::trust_group_id, i32>,
+	> {
+		identities::table
+			.filter(identities::discord_id.eq(user_id.get()))
+			.filter(identities::trust_group_id.eq(trust_group_id))
+	}
+}
@@ -1,5 +1,17 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+	audit_events (id) {
+		id -> Integer,
+		guild_id -> Unsigned<Bigint>,
+		kind -> Varchar,
+		actor_id -> Nullable<Unsigned<Bigint>>,
+		target_id -> Nullable<Unsigned<Bigint>>,
+		details -> Nullable<Text>,
+		created_at -> Datetime,
+	}
+}
+
 diesel::table! {
 	classes (id) {
 		id -> Integer,
@@ -7,6 +19,15 @@ diesel::table! {
 		level_id -> Integer,
 		guild_id -> Unsigned<Bigint>,
 		role_id -> Unsigned<Bigint>,
+		broken -> Bool,
+	}
+}
+
+diesel::table! {
+	class_change_requests (member_id) {
+		member_id -> Integer,
+		class_id -> Integer,
+		requested_at -> Datetime,
 	}
 }
 
@@ -17,6 +38,7 @@ diesel::table! {
 		emoji -> Nullable<Char>,
 		guild_id -> Unsigned<Bigint>,
 		role_id -> Unsigned<Bigint>,
+		broken -> Bool,
 	}
 }
 
@@ -27,15 +49,51 @@ diesel::table! {
 	}
 }
 
+diesel::table! {
+	guild_email_rules (id) {
+		id -> Integer,
+		guild_id -> Unsigned<Bigint>,
+		kind -> Varchar,
+		pattern -> Varchar,
+		role_id -> Nullable<Unsigned<Bigint>>,
+		member_kind -> Nullable<Varchar>,
+	}
+}
+
 diesel::table! {
 	guilds (id) {
 		id -> Unsigned<Bigint>,
 		name -> Varchar,
 		owner_id -> Unsigned<Bigint>,
-		verification_email_domain -> Nullable<Varchar>,
 		verified_role_id -> Nullable<Unsigned<Bigint>>,
+		teacher_role_id -> Nullable<Unsigned<Bigint>>,
+		staff_role_id -> Nullable<Unsigned<Bigint>>,
+		identity_provider -> Nullable<Varchar>,
 		login_message_id -> Nullable<Unsigned<Bigint>>,
 		groups_message_id -> Nullable<Unsigned<Bigint>>,
+		groups_message_channel_id -> Nullable<Unsigned<Bigint>>,
+		level_up_channel_id -> Nullable<Unsigned<Bigint>>,
+		log_channel_id -> Nullable<Unsigned<Bigint>>,
+		verified_role_policy -> Varchar,
+		reverification_days -> Nullable<Integer>,
+		offline_access -> Bool,
+		trust_group_id -> Nullable<Integer>,
+		duplicate_mail_policy -> Varchar,
+		class_change_approval -> Bool,
+	}
+}
+
+diesel::table! {
+	identities (id) {
+		id -> Integer,
+		trust_group_id -> Integer,
+		discord_id -> Unsigned<Bigint>,
+		mail -> Varchar,
+		first_name -> Text,
+		last_name -> Text,
+		domain -> Nullable<Varchar>,
+		provider -> Varchar,
+		verified_at -> Datetime,
 	}
 }
 
@@ -45,6 +103,17 @@ diesel::table! {
 		name -> Text,
 		guild_id -> Unsigned<Bigint>,
 		role_id -> Unsigned<Bigint>,
+		next_level_id -> Nullable<Integer>,
+		graduating -> Bool,
+		broken -> Bool,
+	}
+}
+
+diesel::table! {
+	mail_claims (guild_id, mail) {
+		guild_id -> Unsigned<Bigint>,
+		mail -> Varchar,
+		member_id -> Integer,
 	}
 }
 
@@ -59,32 +128,107 @@ diesel::table! {
 	}
 }
 
+diesel::table! {
+	pending_logins (csrf_state) {
+		csrf_state -> Varchar,
+		guild_id -> Unsigned<Bigint>,
+		discord_id -> Unsigned<Bigint>,
+		username -> Varchar,
+		guild_image_source -> Text,
+		locale -> Varchar,
+		interaction_token -> Text,
+		identity_provider -> Varchar,
+		nonce -> Varchar,
+		member_kind -> Varchar,
+		class_id -> Nullable<Integer>,
+		expires_at -> Datetime,
+	}
+}
+
+diesel::table! {
+	roster_entries (id) {
+		id -> Integer,
+		guild_id -> Unsigned<Bigint>,
+		mail -> Varchar,
+		first_name -> Text,
+		last_name -> Text,
+		class_id -> Integer,
+	}
+}
+
+diesel::table! {
+	trust_groups (id) {
+		id -> Integer,
+		name -> Varchar,
+		invite_code -> Varchar,
+	}
+}
+
 diesel::table! {
 	verified_members (member_id) {
 		member_id -> Integer,
 		mail -> Varchar,
 		first_name -> Text,
 		last_name -> Text,
-		class_id -> Integer,
+		member_kind -> Varchar,
+		class_id -> Nullable<Integer>,
+		verified_at -> Datetime,
+		reminded_at -> Nullable<Datetime>,
+		refresh_token -> Nullable<Blob>,
+		token_provider -> Nullable<Varchar>,
+		identity_id -> Nullable<Integer>,
+		verified_by -> Nullable<Unsigned<Bigint>>,
+	}
+}
+
+diesel::table! {
+	xp_rewards (id) {
+		id -> Integer,
+		guild_id -> Unsigned<Bigint>,
+		threshold -> Integer,
+		role_id -> Unsigned<Bigint>,
 	}
 }
 
+diesel::joinable!(audit_events -> guilds (guild_id));
+diesel::joinable!(class_change_requests -> classes (class_id));
+diesel::joinable!(class_change_requests -> members (member_id));
 diesel::joinable!(classes -> guilds (guild_id));
 diesel::joinable!(classes -> levels (level_id));
 diesel::joinable!(groups -> guilds (guild_id));
 diesel::joinable!(groups_of_verified_members -> groups (group_id));
 diesel::joinable!(groups_of_verified_members -> verified_members (verified_member_id));
+diesel::joinable!(guild_email_rules -> guilds (guild_id));
+diesel::joinable!(guilds -> trust_groups (trust_group_id));
+diesel::joinable!(identities -> trust_groups (trust_group_id));
 diesel::joinable!(levels -> guilds (guild_id));
+diesel::joinable!(mail_claims -> guilds (guild_id));
+diesel::joinable!(mail_claims -> members (member_id));
 diesel::joinable!(members -> guilds (guild_id));
+diesel::joinable!(pending_logins -> classes (class_id));
+diesel::joinable!(pending_logins -> guilds (guild_id));
+diesel::joinable!(roster_entries -> classes (class_id));
+diesel::joinable!(roster_entries -> guilds (guild_id));
 diesel::joinable!(verified_members -> classes (class_id));
+diesel::joinable!(verified_members -> identities (identity_id));
 diesel::joinable!(verified_members -> members (member_id));
+diesel::joinable!(xp_rewards -> guilds (guild_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+	audit_events,
+	class_change_requests,
 	classes,
 	groups,
 	groups_of_verified_members,
+	guild_email_rules,
 	guilds,
+	identities,
 	levels,
+	mail_claims,
 	members,
+	pending_logins,
+	roster_entries,
+	trust_groups,
 	verified_members,
+	xp_rewards,
 );
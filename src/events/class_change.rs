@@ -0,0 +1,377 @@
+//! Let a verified student change their class, with the approval of an admin if the guild asks for it
+
+use crate::{
+	audit::{self, AuditEntry, AuditKind},
+	constants,
+	database::{
+		models::{Guild, Level, VerifiedMember},
+		prelude::*,
+		schema::{class_change_requests, levels, members, verified_members},
+	},
+	states::{InteractionResult, MessageComponentContext},
+	translation::Translate,
+	verification::{
+		class_change::{change_class as apply_class_change, class_from_roster, class_label},
+		member_kind::MemberKind,
+	},
+};
+use anyhow::anyhow;
+use chrono::{NaiveDateTime, Utc};
+use diesel::dsl::{insert_or_ignore_into, replace_into};
+use fluent::fluent_args;
+use poise::{
+	serenity_prelude::{
+		ButtonStyle, ChannelId, CreateActionRow, CreateButton, CreateMessage,
+		EditInteractionResponse, Mentionable, Permissions, UserId,
+	},
+	CreateReply,
+};
+
+/// Ask the student for their new class, then move them or send the request to the admins
+#[allow(clippy::too_many_lines)]
+#[tracing::instrument(skip_all, fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn change_class(ctx: MessageComponentContext<'_>) -> InteractionResult {
+	let mut connection = ctx.data.database.get().await?;
+	let member = ctx.guild_only_member();
+
+	let Some((member_id, member_kind, old_class_id, mail)) =
+		VerifiedMember::with_ids(member.user.id, member.guild_id)
+			.select((
+				verified_members::member_id,
+				verified_members::member_kind,
+				verified_members::class_id,
+				verified_members::mail,
+			))
+			.first::<(i32, String, Option<i32>, String)>(&mut connection)
+			.await
+			.optional()?
+	else {
+		ctx.shout(ctx.translate("event-class-change-not-verified", None))
+			.await?;
+
+		return Ok(());
+	};
+
+	if !member_kind.parse::<MemberKind>()?.has_class() {
+		ctx.shout(ctx.translate("event-class-change-not-a-student", None))
+			.await?;
+
+		return Ok(());
+	}
+
+	let guild = Guild::with_id(member.guild_id)
+		.first::<Guild>(&mut connection)
+		.await?;
+
+	// Without the approval of an admin, the roster would be bypassed
+	if !guild.class_change_approval
+		&& class_from_roster(&mut connection, member.guild_id, &mail).await?
+	{
+		ctx.shout(ctx.translate("event-class-change-from-roster", None))
+			.await?;
+
+		return Ok(());
+	}
+
+	// Levels without a role cannot be joined
+	let levels: Vec<Level> = Level::all_from_guild(member.guild_id)
+		.filter(levels::broken.eq(false))
+		.get_results::<Level>(&mut connection)
+		.await?;
+
+	let initial_response = ctx
+		.send(
+			CreateReply::default()
+				.ephemeral(true)
+				.content(ctx.translate("event-class-change-select", None)),
+		)
+		.await?;
+
+	let new_class_id = match super::login::ask_user_guild_and_levels(
+		&ctx,
+		&mut connection,
+		&initial_response,
+		levels,
+	)
+	.await?
+	{
+		Ok(class_id) => class_id,
+		Err(msg) => {
+			initial_response
+				.edit(CreateReply::default().content(msg).components(Vec::new()))
+				.await?;
+
+			return Ok(());
+		}
+	};
+
+	let (key, class) = if old_class_id == Some(new_class_id) {
+		("event-class-change-same-class", None)
+	} else {
+		let new_class = class_label(&mut connection, new_class_id).await?;
+
+		if !guild.class_change_approval {
+			apply_class_change(
+				&ctx.data.http,
+				&mut connection,
+				(member.guild_id, member.user.id),
+				member_id,
+				new_class_id,
+			)
+			.await?;
+
+			audit::record(
+				ctx.data,
+				member.guild_id,
+				AuditEntry::new(AuditKind::ClassChanged)
+					.actor(member.user.id)
+					.target(member.user.id)
+					.details(new_class.as_str()),
+			)
+			.await;
+
+			("event-class-change-done", Some(new_class))
+		} else if let Some(log_channel_id) = guild.log_channel_id {
+			// A new request of the member replaces the previous one
+			replace_into(class_change_requests::table)
+				.values((
+					class_change_requests::member_id.eq(member_id),
+					class_change_requests::class_id.eq(new_class_id),
+					class_change_requests::requested_at.eq(Utc::now().naive_utc()),
+				))
+				.execute(&mut connection)
+				.await?;
+
+			let old_class = match old_class_id {
+				Some(old_class_id) => class_label(&mut connection, old_class_id).await?,
+				None => "-".to_owned(),
+			};
+
+			let action_row = CreateActionRow::Buttons(vec![
+				CreateButton::new(format!(
+					"{}.{member_id}.{new_class_id}",
+					constants::events::CLASS_CHANGE_APPROVE_BUTTON_INTERACTION
+				))
+				.label(
+					ctx.data
+						.translate("event-class-change-approve-button", None),
+				)
+				.style(ButtonStyle::Success),
+				CreateButton::new(format!(
+					"{}.{member_id}.{new_class_id}",
+					constants::events::CLASS_CHANGE_REJECT_BUTTON_INTERACTION
+				))
+				.label(ctx.data.translate("event-class-change-reject-button", None))
+				.style(ButtonStyle::Danger),
+			]);
+
+			ChannelId::new(log_channel_id)
+				.send_message(
+					&ctx,
+					CreateMessage::new()
+						.content(ctx.data.translate(
+							"event-class-change-request",
+							Some(fluent_args![
+								"user" => member.user.mention().to_string(),
+								"old" => old_class,
+								"new" => new_class.as_str(),
+							]),
+						))
+						.components(vec![action_row]),
+				)
+				.await?;
+
+			("event-class-change-requested", Some(new_class))
+		} else {
+			("event-class-change-no-log-channel", None)
+		}
+	};
+
+	initial_response
+		.edit(
+			CreateReply::default()
+				.content(ctx.translate(key, Some(fluent_args!["class" => class])))
+				.components(Vec::new()),
+		)
+		.await?;
+
+	Ok(())
+}
+
+/// Apply or drop a class change request, after an admin reviewed it in the log channel
+#[allow(clippy::too_many_lines)]
+#[tracing::instrument(skip_all, fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn review(ctx: MessageComponentContext<'_>) -> InteractionResult {
+	let custom_id = &ctx.interaction.data.custom_id;
+	let approved =
+		custom_id.starts_with(constants::events::CLASS_CHANGE_APPROVE_BUTTON_INTERACTION);
+
+	let mut segments = custom_id.rsplitn(3, '.');
+	let (Some(class_id), Some(member_id)) = (
+		segments.next().and_then(|id| id.parse::<i32>().ok()),
+		segments.next().and_then(|id| id.parse::<i32>().ok()),
+	) else {
+		return Err(anyhow!("Something went wrong while parsing the class change request").into());
+	};
+
+	let reviewer = ctx.guild_only_member();
+
+	if !ctx
+		.interaction
+		.member
+		.as_ref()
+		.and_then(|member| member.permissions)
+		.is_some_and(Permissions::manage_roles)
+	{
+		ctx.shout(ctx.translate("event-class-change-review-forbidden", None))
+			.await?;
+
+		return Ok(());
+	}
+
+	ctx.interaction.defer(&ctx).await?;
+
+	let mut connection = ctx.data.database.get().await?;
+
+	let user_id = members::table
+		.find(member_id)
+		.filter(members::guild_id.eq(reviewer.guild_id.get()))
+		.select(members::discord_id)
+		.first::<u64>(&mut connection)
+		.await
+		.optional()?
+		.map(UserId::new);
+
+	let request = match user_id {
+		Some(user_id) => {
+			let request = class_change_requests::table
+				.find(member_id)
+				.filter(class_change_requests::class_id.eq(class_id));
+
+			let requested_at = request
+				.select(class_change_requests::requested_at)
+				.first::<NaiveDateTime>(&mut connection)
+				.await
+				.optional()?;
+
+			// Only one of concurrent reviews deletes the request
+			let request_deleted = requested_at.is_some()
+				&& diesel::delete(request).execute(&mut connection).await? == 1;
+
+			let still_student = VerifiedMember::from_member_id(member_id)
+				.select(verified_members::member_kind)
+				.first::<String>(&mut connection)
+				.await
+				.optional()?
+				.map(|member_kind| member_kind.parse::<MemberKind>())
+				.transpose()?
+				.is_some_and(MemberKind::has_class);
+
+			requested_at
+				.filter(|_| request_deleted && still_student)
+				.map(|requested_at| (user_id, requested_at))
+		}
+		None => None,
+	};
+
+	// The request may have been replaced, reviewed or the member may have logged out since
+	let Some((user_id, requested_at)) = request else {
+		ctx.interaction
+			.edit_response(
+				&ctx,
+				EditInteractionResponse::new()
+					.content(
+						ctx.data
+							.translate("event-class-change-review-outdated", None),
+					)
+					.components(Vec::new()),
+			)
+			.await?;
+
+		return Ok(());
+	};
+
+	let class = class_label(&mut connection, class_id).await?;
+
+	if approved {
+		if let Err(error) = apply_class_change(
+			&ctx.data.http,
+			&mut connection,
+			(reviewer.guild_id, user_id),
+			member_id,
+			class_id,
+		)
+		.await
+		{
+			// The request is given back so that it can be reviewed again, unless the member asked for another class since
+			insert_or_ignore_into(class_change_requests::table)
+				.values((
+					class_change_requests::member_id.eq(member_id),
+					class_change_requests::class_id.eq(class_id),
+					class_change_requests::requested_at.eq(requested_at),
+				))
+				.execute(&mut connection)
+				.await?;
+
+			return Err(error);
+		}
+
+		audit::record(
+			ctx.data,
+			reviewer.guild_id,
+			AuditEntry::new(AuditKind::ClassChanged)
+				.actor(reviewer.user.id)
+				.target(user_id)
+				.details(class.as_str()),
+		)
+		.await;
+	}
+
+	let (review_key, notice_key) = if approved {
+		(
+			"event-class-change-approved",
+			"event-class-change-approved-notice",
+		)
+	} else {
+		(
+			"event-class-change-rejected",
+			"event-class-change-rejected-notice",
+		)
+	};
+
+	ctx.interaction
+		.edit_response(
+			&ctx,
+			EditInteractionResponse::new()
+				.content(format!(
+					"{}\n{}",
+					ctx.interaction.message.content,
+					ctx.data.translate(
+						review_key,
+						Some(fluent_args!["admin" => reviewer.user.mention().to_string()]),
+					)
+				))
+				.components(Vec::new()),
+		)
+		.await?;
+
+	// The member cannot be answered in the guild anymore, direct messages may be closed
+	if let Err(error) = user_id
+		.direct_message(
+			&ctx,
+			CreateMessage::new().content(
+				ctx.data
+					.translate(notice_key, Some(fluent_args!["class" => class])),
+			),
+		)
+		.await
+	{
+		tracing::warn!(
+			user_id = user_id.get(),
+			error = ?error,
+			"could not tell the member about the review of their class change"
+		);
+	}
+
+	Ok(())
+}
@@ -1,6 +1,7 @@
 //! Command to disconnect Discord and Google accounts together.
 
 use crate::{
+	audit::{self, AuditEntry, AuditKind},
 	database::{
 		models::{Group, GroupOfVerifiedMember, NewGroupOfVerifiedMember, VerifiedMember},
 		prelude::*,
@@ -57,6 +58,15 @@ pub(crate) async fn groups(
 			.execute(&mut connection)
 			.await?;
 
+		audit::record(
+			ctx.data,
+			member.guild_id,
+			AuditEntry::new(AuditKind::GroupLeft)
+				.actor(member.user.id)
+				.details(&group.name),
+		)
+		.await;
+
 		ctx.shout(ctx.translate("delete", None)).await?;
 	} else {
 		let new_group_of_verified_member = NewGroupOfVerifiedMember {
@@ -69,6 +79,15 @@ pub(crate) async fn groups(
 			.execute(&mut connection)
 			.await?;
 
+		audit::record(
+			ctx.data,
+			member.guild_id,
+			AuditEntry::new(AuditKind::GroupJoined)
+				.actor(member.user.id)
+				.details(&group.name),
+		)
+		.await;
+
 		ctx.shout(ctx.translate("insert", None)).await?;
 	}
 
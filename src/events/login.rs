@@ -2,26 +2,32 @@
 //! TODO (LARGE) : refactor this file
 
 use crate::{
-	auth::{GoogleAuthentificationError, GoogleUserMetadata},
 	constants,
 	database::{
 		self,
-		models::{Class, Guild, Level, Member, NewVerifiedMember, VerifiedMember},
+		models::{
+			Class, Guild, GuildEmailRule, Identity, Level, Member, NewPendingLogin, PendingLogin,
+			RosterEntry, VerifiedMember,
+		},
 		prelude::*,
 		schema, DatabasePooledConnection,
 	},
 	polyfill::MessageComponentReplyHandle,
-	states::{InteractionError, InteractionResult, MessageComponentContext},
+	states::{InteractionResult, MessageComponentContext},
 	translation::Translate,
+	verification::{
+		member_kind::MemberKind,
+		trust::{shared_identity, verify_with_identity},
+	},
 };
 use anyhow::{anyhow, Context};
+use chrono::Utc;
 use diesel::dsl;
 use fluent::fluent_args;
 use poise::{
 	serenity_prelude::{
-		self as serenity, ComponentInteractionCollector, ComponentInteractionDataKind,
-		CreateActionRow, CreateButton, CreateSelectMenu, CreateSelectMenuKind,
-		CreateSelectMenuOption, GuildId, RoleId,
+		ComponentInteractionCollector, ComponentInteractionDataKind, CreateActionRow, CreateButton,
+		CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, GuildId,
 	},
 	CreateReply,
 };
@@ -49,121 +55,223 @@ pub(crate) async fn login(ctx: MessageComponentContext<'_>) -> InteractionResult
 		return Ok(());
 	}
 
-	let (verified_role, levels, email_pattern) =
-		match check_login_components(&mut connection, member.guild_id).await {
-			Ok(components) => components,
-			Err(err) => match err {
-				CheckLoginComponentsError::Database(err) => return Err(err.into()),
-				CheckLoginComponentsError::NoEmailPattern
-				| CheckLoginComponentsError::NoVerifiedRole
-				| CheckLoginComponentsError::NoLevels => {
-					// TODO: translate errors
-
-					ctx.shout(err.to_string()).await?;
-
-					return Ok(());
-				}
-			},
-		};
+	let LoginComponents {
+		levels,
+		identity_provider,
+		member_kinds,
+		has_roster,
+		offline_access,
+		shared_identity,
+	} = match check_login_components(&ctx, &mut connection, member.guild_id).await {
+		Ok(components) => components,
+		Err(err) => match err {
+			CheckLoginComponentsError::Database(err) => return Err(err.into()),
+			CheckLoginComponentsError::NoEmailPattern
+			| CheckLoginComponentsError::NoVerifiedRole
+			| CheckLoginComponentsError::NoLevels
+			| CheckLoginComponentsError::UnknownIdentityProvider(_) => {
+				// TODO: translate errors
+
+				ctx.shout(err.to_string()).await?;
 
-	let (oauth2_url, token_response) = ctx
-		.data
-		.auth
-		.process_oauth2(
-			member.user.name.clone(),
-			member
-				.guild_id
-				.to_partial_guild(&ctx)
-				.await?
-				.icon_url()
-				.unwrap_or_default(),
-		)
-		.await;
+				return Ok(());
+			}
+		},
+	};
 
-	let action_row = CreateActionRow::Buttons(vec![
-		CreateButton::new_link(oauth2_url).label(ctx.translate("continue", None))
-	]);
+	if !dsl::select(dsl::exists(Member::with_ids(
+		member.user.id,
+		member.guild_id,
+	)))
+	.get_result::<bool>(&mut connection)
+	.await?
+	{
+		ctx.shout(ctx.translate(
+			"error-member-not-registered",
+			Some(fluent_args!["user" => member.user.name.as_str()]),
+		))
+		.await?;
+
+		return Ok(());
+	}
 
 	let initial_response = ctx
 		.send(
 			CreateReply::default()
 				.ephemeral(true)
-				.content(ctx.translate("use-google-account-to-login", None))
-				.components(vec![action_row]),
+				.content(ctx.translate("use-google-account-to-login", None)),
 		)
 		.await?;
 
-	let token_response = match token_response.await {
-		Ok(response) => response,
-		Err(GoogleAuthentificationError::Timeout) => {
-			ctx.shout(ctx.translate("did-not-finish-auth-process", None))
-				.await?;
+	let member_kind = if let [member_kind] = member_kinds[..] {
+		member_kind
+	} else {
+		match ask_member_kind(&ctx, &initial_response, &member_kinds).await? {
+			Ok(member_kind) => member_kind,
+			Err(msg) => {
+				ctx.shout(msg).await?;
 
-			return Ok(());
+				return Ok(());
+			}
 		}
-		Err(error) => return Err(error).context("Failed to get token response")?,
 	};
 
-	let user_data = ctx
-		.data
-		.auth
-		.query_google_user_metadata(&token_response)
-		.await
-		.context("Failed to query google user metadata")?;
-
-	let mail_domain = user_data
-		.mail
-		.split('@')
-		.next_back()
-		.context("email returned by google is invalid")?;
-	if mail_domain != email_pattern {
-		ctx.shout(ctx.translate("event-login-email-domain-not-allowed", None))
-			.await?;
-
-		return Ok(());
-	}
-
-	let (level_id, class_id) =
+	// Students of a roster are bound to their class once their email is known
+	let class_id = if member_kind.has_class() && !has_roster {
 		match ask_user_guild_and_levels(&ctx, &mut connection, &initial_response, levels).await? {
-			Ok((level_id, class_id)) => (level_id, class_id),
+			Ok(class_id) => Some(class_id),
 			Err(msg) => {
 				ctx.shout(msg).await?;
 
 				return Ok(());
 			}
-		};
+		}
+	} else {
+		None
+	};
 
-	let Some(verified_member_id) = Member::with_ids(member.user.id, member.guild_id)
-		.select(schema::members::id)
-		.first::<i32>(&mut connection)
+	let authorization_request = ctx
+		.data
+		.auth
+		.get(&identity_provider)
+		.context("identity provider was checked before")?
+		.authorize_url(offline_access)
 		.await
-		.optional()?
-	else {
-		ctx.shout(ctx.translate(
-			"error-member-not-registered",
-			Some(fluent_args!["user" => member.user.name.as_str()]),
-		))
+		.context("could not get the authorization url")?;
+
+	let guild_image_source = member
+		.guild_id
+		.to_partial_guild(&ctx)
+		.await?
+		.icon_url()
+		.unwrap_or_default();
+
+	let now = Utc::now().naive_utc();
+
+	// Clean up logins that were never finished
+	diesel::delete(PendingLogin::all_expired(now))
+		.execute(&mut connection)
 		.await?;
 
+	// The login is persisted so that the `OAuth2` callback can finish it on its own
+	let new_pending_login = NewPendingLogin {
+		csrf_state: authorization_request.csrf_state.secret(),
+		guild_id: member.guild_id.get(),
+		discord_id: member.user.id.get(),
+		username: &member.user.name,
+		guild_image_source: &guild_image_source,
+		locale: &ctx.interaction.locale,
+		interaction_token: &ctx.interaction.token,
+		identity_provider: &identity_provider,
+		nonce: authorization_request.nonce.secret(),
+		member_kind: member_kind.as_str(),
+		class_id,
+		expires_at: now + constants::AUTHENTICATION_TIMEOUT,
+	};
+
+	new_pending_login.insert().execute(&mut connection).await?;
+
+	let login_button =
+		CreateButton::new_link(authorization_request.url).label(ctx.translate("continue", None));
+
+	let Some(identity) = shared_identity else {
+		initial_response
+			.edit(
+				CreateReply::default()
+					.content(ctx.translate("use-google-account-to-login", None))
+					.components(vec![CreateActionRow::Buttons(vec![login_button])]),
+			)
+			.await?;
+
 		return Ok(());
 	};
 
-	apply_changes(
+	use_shared_identity(
 		&ctx,
 		&mut connection,
-		&member,
-		user_data,
-		verified_member_id,
-		verified_role,
-		(level_id, class_id),
+		&initial_response,
+		identity,
+		(member_kind, class_id),
+		(authorization_request.csrf_state.secret(), login_button),
+	)
+	.await
+}
+
+/// Offer the member to reuse the verification of the trust group, next to the login with the provider
+async fn use_shared_identity<'a>(
+	ctx: &'a MessageComponentContext<'a>,
+	connection: &mut DatabasePooledConnection,
+	initial_response: &MessageComponentReplyHandle<'a>,
+	identity: Identity,
+	(member_kind, class_id): (MemberKind, Option<i32>),
+	(csrf_state, login_button): (&str, CreateButton),
+) -> InteractionResult {
+	let member = ctx.guild_only_member();
+
+	let action_row = CreateActionRow::Buttons(vec![
+		CreateButton::new(constants::events::AUTHENTICATION_BUTTON_SHARED_IDENTITY_INTERACTION)
+			.label(ctx.translate("event-login-shared-identity-button", None)),
+		login_button.clone(),
+	]);
+
+	initial_response
+		.edit(
+			CreateReply::default()
+				.content(ctx.translate(
+					"event-login-shared-identity",
+					Some(fluent_args!["mail" => identity.mail.as_str()]),
+				))
+				.components(vec![action_row]),
+		)
+		.await?;
+
+	let Some(interaction) = ComponentInteractionCollector::new(ctx)
+		.message_id(initial_response.message().await?.id)
+		.timeout(Duration::from_mins(1))
+		.await
+	else {
+		// The login may have been finished with the provider in the meantime
+		if dsl::select(dsl::exists(PendingLogin::with_state(csrf_state)))
+			.get_result::<bool>(connection)
+			.await?
+		{
+			initial_response
+				.edit(
+					CreateReply::default()
+						.content(ctx.translate("use-google-account-to-login", None))
+						.components(vec![CreateActionRow::Buttons(vec![login_button])]),
+				)
+				.await?;
+		}
+
+		return Ok(());
+	};
+
+	interaction.defer(&ctx).await?;
+
+	// The login with the provider cannot be finished anymore
+	diesel::delete(PendingLogin::with_state(csrf_state))
+		.execute(connection)
+		.await?;
+
+	let key = match verify_with_identity(
+		ctx.data,
+		connection,
+		(member.guild_id, member.user.id),
+		identity,
+		(member_kind.as_str(), class_id),
 	)
-	.await?;
+	.await?
+	{
+		Ok(()) => "authentication-successful",
+		Err(refusal) => refusal.translation_key(),
+	};
 
 	initial_response
 		.edit(
 			CreateReply::default()
-				.content(ctx.translate("authentication-successful", None))
-				.components(vec![]),
+				.content(ctx.translate(key, None))
+				.components(Vec::new()),
 		)
 		.await?;
 
@@ -183,60 +291,152 @@ enum CheckLoginComponentsError {
 	/// No levels found in this guild
 	#[error("No levels found in this guild")]
 	NoLevels,
+	/// The selected identity provider is not configured on this bot
+	#[error("The identity provider `{0}` is not available")]
+	UnknownIdentityProvider(String),
 
 	/// An error from the database
 	#[error(transparent)]
 	Database(#[from] DieselError),
 }
 
+/// What a member can choose from to login
+struct LoginComponents {
+	/// The levels students choose from
+	levels: Vec<Level>,
+	/// The name of the identity provider to use
+	identity_provider: String,
+	/// The kinds of members that can login, students only if there are levels
+	member_kinds: Vec<MemberKind>,
+	/// Whether students are bound to their class by the imported roster
+	has_roster: bool,
+	/// Whether to ask for a refresh token, to notice when the account is deactivated
+	offline_access: bool,
+	/// The account the member proved in another guild of the trust group
+	shared_identity: Option<Identity>,
+}
+
 /// Extracted logic
 async fn check_login_components(
+	ctx: &MessageComponentContext<'_>,
 	connection: &mut DatabasePooledConnection,
 	guild_id: GuildId,
-) -> Result<(RoleId, Vec<Level>, String), CheckLoginComponentsError> {
-	let (verified_role, email_pattern) = {
-		let (inner_role_id, email_pattern): (Option<u64>, Option<String>) =
-			Guild::with_id(guild_id)
-				.select((
-					schema::guilds::verified_role_id,
-					schema::guilds::verification_email_domain,
-				))
-				.first(connection)
-				.await?;
+) -> Result<LoginComponents, CheckLoginComponentsError> {
+	let guild = Guild::with_id(guild_id).first::<Guild>(connection).await?;
 
-		let inner_role = match inner_role_id {
-			Some(role_id) => RoleId::new(role_id),
-			None => {
-				return Err(CheckLoginComponentsError::NoVerifiedRole);
-			}
-		};
+	if guild.verified_role_id.is_none() {
+		return Err(CheckLoginComponentsError::NoVerifiedRole);
+	}
 
-		let Some(email_pattern) = email_pattern else {
-			return Err(CheckLoginComponentsError::NoEmailPattern);
-		};
+	if !dsl::select(dsl::exists(GuildEmailRule::all_from_guild(guild_id)))
+		.get_result::<bool>(connection)
+		.await?
+	{
+		return Err(CheckLoginComponentsError::NoEmailPattern);
+	}
 
-		(inner_role, email_pattern)
-	};
+	let identity_provider = guild
+		.identity_provider
+		.clone()
+		.unwrap_or_else(|| constants::DEFAULT_IDENTITY_PROVIDER.into());
+	if ctx.data.auth.get(&identity_provider).is_none() {
+		return Err(CheckLoginComponentsError::UnknownIdentityProvider(
+			identity_provider,
+		));
+	}
 
+	// Levels without a role cannot be joined
 	let levels: Vec<Level> = Level::all_from_guild(guild_id)
+		.filter(schema::levels::broken.eq(false))
 		.get_results::<Level>(connection)
 		.await?;
 
-	if levels.is_empty() {
+	let member_kinds = MemberKind::ALL
+		.into_iter()
+		.filter(|member_kind| {
+			if member_kind.has_class() {
+				!levels.is_empty()
+			} else {
+				member_kind.role_id(&guild).is_some()
+			}
+		})
+		.collect::<Vec<_>>();
+
+	if member_kinds.is_empty() {
 		return Err(CheckLoginComponentsError::NoLevels);
 	}
 
-	Ok((verified_role, levels, email_pattern))
+	let has_roster = dsl::select(dsl::exists(RosterEntry::all_from_guild(guild_id)))
+		.get_result::<bool>(connection)
+		.await?;
+
+	Ok(LoginComponents {
+		levels,
+		identity_provider,
+		member_kinds,
+		has_roster,
+		offline_access: guild.offline_access && ctx.data.config.token_cipher.is_some(),
+		shared_identity: shared_identity(connection, &guild, ctx.interaction.user.id).await?,
+	})
+}
+
+/// Ask the user which kind of member they are, e.g. a student or a teacher
+async fn ask_member_kind<'a>(
+	ctx: &'a MessageComponentContext<'a>,
+	initial_response: &MessageComponentReplyHandle<'a>,
+	member_kinds: &[MemberKind],
+) -> anyhow::Result<Result<MemberKind, String>> {
+	let buttons = member_kinds
+		.iter()
+		.map(|member_kind| {
+			CreateButton::new(format!(
+				"{}.{member_kind}",
+				constants::events::AUTHENTICATION_BUTTON_MEMBER_KIND_INTERACTION
+			))
+			.label(ctx.translate(member_kind.translation_key(), None))
+		})
+		.collect::<Vec<_>>();
+
+	initial_response
+		.edit(
+			CreateReply::default()
+				.ephemeral(true)
+				.components(vec![CreateActionRow::Buttons(buttons)])
+				.content(ctx.translate("event-login-select-member-kind", None)),
+		)
+		.await?;
+
+	let Some(interaction) = ComponentInteractionCollector::new(ctx)
+		.message_id(initial_response.message().await?.id)
+		.timeout(Duration::from_mins(1))
+		.await
+	else {
+		return Ok(Err(ctx.translate("error-user-timeout", None)));
+	};
+
+	interaction.defer(&ctx).await?;
+
+	let member_kind = interaction
+		.data
+		.custom_id
+		.rsplit_once('.')
+		.ok_or_else(|| anyhow!("Something went wrong while parsing member kind"))?
+		.1
+		.parse::<MemberKind>()?;
+
+	Ok(Ok(member_kind))
 }
 
 /// Ask the user to select a level and then a guild
-async fn ask_user_guild_and_levels<'a>(
+///
+/// Also used by the verified students changing their class
+pub(super) async fn ask_user_guild_and_levels<'a>(
 	ctx: &'a MessageComponentContext<'a>,
 	connection: &mut database::DatabasePooledConnection,
 	initial_response: &MessageComponentReplyHandle<'a>,
 	mut levels: Vec<Level>,
 	// TODO: remove ugly as hell return type
-) -> anyhow::Result<Result<(i32, i32), String>> {
+) -> anyhow::Result<Result<i32, String>> {
 	let levels = levels
 		.iter_mut()
 		.map(|cl| CreateSelectMenuOption::new(&cl.name, cl.id.to_string()))
@@ -262,7 +462,7 @@ async fn ask_user_guild_and_levels<'a>(
 
 	let level_id = if let Some(interaction) = ComponentInteractionCollector::new(ctx)
 		.message_id(initial_response.message().await?.id)
-		.timeout(Duration::from_secs(60))
+		.timeout(Duration::from_mins(1))
 		.await
 	{
 		interaction.defer(&ctx).await?;
@@ -279,6 +479,7 @@ async fn ask_user_guild_and_levels<'a>(
 	};
 
 	let mut classes: Vec<Class> = Class::all_from_level(level_id)
+		.filter(schema::classes::broken.eq(false))
 		.get_results::<Class>(connection)
 		.await?;
 
@@ -311,7 +512,7 @@ async fn ask_user_guild_and_levels<'a>(
 
 	let class_id = if let Some(interaction) = ComponentInteractionCollector::new(ctx)
 		.message_id(initial_response.message().await?.id)
-		.timeout(Duration::from_secs(60))
+		.timeout(Duration::from_mins(1))
 		.await
 	{
 		interaction.defer(&ctx).await?;
@@ -327,85 +528,5 @@ async fn ask_user_guild_and_levels<'a>(
 		return Ok(Err(ctx.translate("error-user-timeout", None)));
 	};
 
-	Ok(Ok((level_id, class_id)))
-}
-
-/// Apply the changes to the user, updating the database and the Discord roles
-async fn apply_changes(
-	ctx: &MessageComponentContext<'_>,
-	mut connection: &mut DatabasePooledConnection,
-	member: &serenity::Member,
-	user_data: GoogleUserMetadata,
-	verified_member_id: i32,
-	verified_role: RoleId,
-	(level_id, class_id): (i32, i32),
-) -> Result<(), InteractionError> {
-	// Get Discord roles ids
-	let level_role = {
-		let id = Level::with_id(level_id)
-			.select(schema::levels::role_id)
-			.first::<u64>(&mut connection)
-			.await?;
-
-		RoleId::new(id)
-	};
-	let class_role = {
-		let id = Class::with_id(class_id)
-			.select(schema::classes::role_id)
-			.first::<u64>(&mut connection)
-			.await?;
-
-		RoleId::new(id)
-	};
-
-	// Update Discord roles for new verified member
-	match member.add_role(&ctx, verified_role).await {
-		Ok(()) => {}
-		Err(serenity::Error::Model(serenity::ModelError::RoleNotFound)) => {
-			diesel::update(Guild::with_id(member.guild_id))
-				.set(schema::guilds::verified_role_id.eq::<Option<u64>>(None))
-				.execute(&mut connection)
-				.await?;
-
-			return Err(anyhow!("Verified role was deleted").into());
-		}
-		Err(error) => return Err(error.into()),
-	}
-	match member.add_role(&ctx, level_role).await {
-		Ok(()) => {}
-		Err(serenity::Error::Model(serenity::ModelError::RoleNotFound)) => {
-			diesel::delete(Level::with_id(level_id))
-				.execute(&mut connection)
-				.await?;
-
-			return Err(anyhow!("Level role was deleted").into());
-		}
-		Err(error) => return Err(error.into()),
-	}
-	match member.add_role(&ctx, class_role).await {
-		Ok(()) => {}
-		Err(serenity::Error::Model(serenity::ModelError::RoleNotFound)) => {
-			diesel::delete(Class::with_id(class_id))
-				.execute(&mut connection)
-				.await?;
-
-			return Err(anyhow!("Level role was deleted").into());
-		}
-		Err(error) => return Err(error.into()),
-	}
-
-	let new_verified_member = NewVerifiedMember {
-		member_id: verified_member_id,
-		first_name: &user_data.first_name,
-		last_name: &user_data.last_name,
-		mail: &user_data.mail,
-		class_id,
-	};
-
-	new_verified_member
-		.insert()
-		.execute(&mut connection)
-		.await?;
-
-	Ok(())
+	Ok(Ok(class_id))
 }
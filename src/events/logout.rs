@@ -1,18 +1,18 @@
 //! Command to disconnect Discord and Google accounts together.
 
 use crate::{
+	audit::{self, AuditEntry, AuditKind},
 	constants::events,
-	database::{
-		models::{Guild, VerifiedMember},
-		prelude::*,
-		schema,
-	},
-	states::{InteractionResult, MessageComponentContext},
+	database::{models::VerifiedMember, prelude::*},
+	polyfill::MessageComponentReplyHandle,
+	states::{InteractionError, InteractionResult, MessageComponentContext},
 	translation::Translate,
+	verification::unlink::unlink,
 };
+use diesel::dsl;
 use poise::{
 	serenity_prelude::{
-		ButtonStyle, ComponentInteractionCollector, CreateActionRow, CreateButton, RoleId,
+		ButtonStyle, ComponentInteractionCollector, CreateActionRow, CreateButton, Member,
 	},
 	CreateReply,
 };
@@ -24,22 +24,26 @@ use std::time::Duration;
 pub(crate) async fn logout(ctx: MessageComponentContext<'_>) -> InteractionResult {
 	let member = ctx.guild_only_member();
 
-	let Some(member_id) = VerifiedMember::with_ids(member.user.id, member.guild_id)
-		.select(schema::verified_members::member_id)
-		.first(&mut ctx.data.database.get().await?)
-		.await
-		.optional()?
-	else {
+	if !dsl::select(dsl::exists(VerifiedMember::with_ids(
+		member.user.id,
+		member.guild_id,
+	)))
+	.get_result::<bool>(&mut ctx.data.database.get().await?)
+	.await?
+	{
 		ctx.shout("Member does not exist").await?;
 
 		return Ok(());
-	};
+	}
 
-	let action_row = CreateActionRow::Buttons(vec![CreateButton::new(
-		events::LOGOUT_OK_BUTTON_INTERACTION,
-	)
-	.label(ctx.translate("event-logout-disconnect-button", None))
-	.style(ButtonStyle::Danger)]);
+	let action_row = CreateActionRow::Buttons(vec![
+		CreateButton::new(events::LOGOUT_OK_BUTTON_INTERACTION)
+			.label(ctx.translate("event-logout-disconnect-button", None))
+			.style(ButtonStyle::Danger),
+		CreateButton::new(events::LOGOUT_CANCEL_BUTTON_INTERACTION)
+			.label(ctx.translate("cancel", None))
+			.style(ButtonStyle::Secondary),
+	]);
 
 	let reply = ctx
 		.send(
@@ -50,43 +54,206 @@ pub(crate) async fn logout(ctx: MessageComponentContext<'_>) -> InteractionResul
 		)
 		.await?;
 
-	if let Some(interaction) = ComponentInteractionCollector::new(&ctx)
-		.message_id(reply.message().await?.id)
-		.timeout(Duration::from_secs(60))
-		.await
-	{
-		interaction.defer(&ctx).await?;
+	confirm_logout(&mut DiscordLogout {
+		ctx: &ctx,
+		reply,
+		member,
+	})
+	.await
+}
 
-		// TODO
-		match &*interaction.data.custom_id {
-			events::LOGOUT_OK_BUTTON_INTERACTION | events::LOGOUT_CANCEL_BUTTON_INTERACTION => {}
+/// What the logout needs from `Discord` and the database once the warning is sent, faked in the tests
+trait LogoutSteps {
+	/// The custom id of the button clicked by the member, `None` if they did not answer in time
+	async fn wait_for_answer(&mut self) -> Result<Option<String>, InteractionError>;
 
-			_ => unreachable!(),
-		}
-	} else {
-		ctx.shout(ctx.translate("error-user-timeout", None)).await?;
+	/// Disconnect the accounts of the member
+	async fn unlink(&mut self) -> InteractionResult;
+
+	/// Replace the warning with the message of the translation key
+	async fn answer(&mut self, translation_key: &'static str) -> InteractionResult;
+}
+
+/// Wait for the answer of the member to the warning, then disconnect the accounts if they confirmed
+async fn confirm_logout(steps: &mut impl LogoutSteps) -> InteractionResult {
+	let decision = LogoutDecision::from_custom_id(steps.wait_for_answer().await?.as_deref());
+
+	if decision.unlinks() {
+		steps.unlink().await?;
 	}
 
-	db_dsl::delete(VerifiedMember::from_member_id(member_id))
-		.execute(&mut ctx.data.database.get().await?)
-		.await?;
+	steps.answer(decision.translation_key()).await
+}
 
-	let role_id: Option<u64> = Guild::with_id(member.guild_id)
-		.select(schema::guilds::verified_role_id)
-		.first(&mut ctx.data.database.get().await?)
-		.await?;
+/// The logout of a member clicking the button of a message
+struct DiscordLogout<'a> {
+	/// The context of the click on the logout button
+	ctx: &'a MessageComponentContext<'a>,
+	/// The warning sent to the member
+	reply: MessageComponentReplyHandle<'a>,
+	/// The member logging out
+	member: Member,
+}
+
+impl LogoutSteps for DiscordLogout<'_> {
+	async fn wait_for_answer(&mut self) -> Result<Option<String>, InteractionError> {
+		let Some(interaction) = ComponentInteractionCollector::new(self.ctx)
+			.message_id(self.reply.message().await?.id)
+			.timeout(Duration::from_mins(1))
+			.await
+		else {
+			return Ok(None);
+		};
+
+		interaction.defer(self.ctx).await?;
 
-	if let Some(role) = role_id.map(RoleId::new) {
-		member.remove_role(&ctx, role).await?;
+		Ok(Some(interaction.data.custom_id))
 	}
 
-	reply
-		.edit(
-			CreateReply::default()
-				.ephemeral(true)
-				.content(ctx.translate("event-logout-success", None)),
+	async fn unlink(&mut self) -> InteractionResult {
+		unlink(
+			self.ctx.data,
+			&mut self.ctx.data.database.get().await?,
+			(self.member.guild_id, self.member.user.id),
+			Some(self.member.user.id),
 		)
 		.await?;
 
-	Ok(())
+		audit::record(
+			self.ctx.data,
+			self.member.guild_id,
+			AuditEntry::new(AuditKind::Logout)
+				.actor(self.member.user.id)
+				.target(self.member.user.id),
+		)
+		.await;
+
+		Ok(())
+	}
+
+	async fn answer(&mut self, translation_key: &'static str) -> InteractionResult {
+		self.reply
+			.edit(
+				CreateReply::default()
+					.ephemeral(true)
+					.content(self.ctx.translate(translation_key, None))
+					.components(Vec::new()),
+			)
+			.await?;
+
+		Ok(())
+	}
+}
+
+/// What the member answered to the logout warning
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LogoutDecision {
+	/// The member confirmed the logout
+	Confirmed,
+	/// The member cancelled the logout
+	Cancelled,
+	/// The member did not answer in time
+	TimedOut,
+}
+
+impl LogoutDecision {
+	/// The decision given by the button clicked, `None` when no button was clicked in time
+	fn from_custom_id(custom_id: Option<&str>) -> Self {
+		match custom_id {
+			Some(events::LOGOUT_OK_BUTTON_INTERACTION) => Self::Confirmed,
+			Some(_) => Self::Cancelled,
+			None => Self::TimedOut,
+		}
+	}
+
+	/// Whether the accounts must be disconnected
+	const fn unlinks(self) -> bool {
+		matches!(self, Self::Confirmed)
+	}
+
+	/// The translation key of the message replacing the warning
+	const fn translation_key(self) -> &'static str {
+		match self {
+			Self::Confirmed => "event-logout-success",
+			Self::Cancelled => "event-logout-cancelled",
+			Self::TimedOut => "error-user-timeout",
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{confirm_logout, LogoutSteps};
+	use crate::{
+		constants::events,
+		states::{InteractionError, InteractionResult},
+	};
+
+	/// Answers the warning with a button, or not at all, and records what the logout did
+	struct FakeLogout {
+		/// The custom id of the button clicked, `None` to time out
+		answer: Option<&'static str>,
+		/// Whether the accounts were disconnected
+		unlinked: bool,
+		/// The translation key of the message replacing the warning
+		answered: Option<&'static str>,
+	}
+
+	impl FakeLogout {
+		fn new(answer: Option<&'static str>) -> Self {
+			Self {
+				answer,
+				unlinked: false,
+				answered: None,
+			}
+		}
+	}
+
+	impl LogoutSteps for FakeLogout {
+		async fn wait_for_answer(&mut self) -> Result<Option<String>, InteractionError> {
+			Ok(self.answer.map(ToOwned::to_owned))
+		}
+
+		async fn unlink(&mut self) -> InteractionResult {
+			self.unlinked = true;
+
+			Ok(())
+		}
+
+		async fn answer(&mut self, translation_key: &'static str) -> InteractionResult {
+			self.answered = Some(translation_key);
+
+			Ok(())
+		}
+	}
+
+	#[tokio::test]
+	async fn confirmed_logout_unlinks() {
+		let mut logout = FakeLogout::new(Some(events::LOGOUT_OK_BUTTON_INTERACTION));
+
+		assert!(confirm_logout(&mut logout).await.is_ok());
+
+		assert!(logout.unlinked);
+		assert_eq!(logout.answered, Some("event-logout-success"));
+	}
+
+	#[tokio::test]
+	async fn cancelled_logout_keeps_the_verification() {
+		let mut logout = FakeLogout::new(Some(events::LOGOUT_CANCEL_BUTTON_INTERACTION));
+
+		assert!(confirm_logout(&mut logout).await.is_ok());
+
+		assert!(!logout.unlinked);
+		assert_eq!(logout.answered, Some("event-logout-cancelled"));
+	}
+
+	#[tokio::test]
+	async fn timed_out_logout_keeps_the_verification_and_tells_the_member() {
+		let mut logout = FakeLogout::new(None);
+
+		assert!(confirm_logout(&mut logout).await.is_ok());
+
+		assert!(!logout.unlinked);
+		assert_eq!(logout.answered, Some("error-user-timeout"));
+	}
 }
@@ -9,14 +9,18 @@ use crate::{
 		schema::{guilds, members},
 	},
 	states::{ArcData, FrameworkContext, InteractionResult, MessageComponentContext},
+	verification::{reconcile::spawn_reconciliation, unlink::unlink},
 };
 use anyhow::Context;
 use poise::serenity_prelude::{self, ComponentInteractionDataKind, FullEvent, Interaction};
-use std::sync::atomic::AtomicBool;
+use std::sync::{atomic::AtomicBool, Arc};
 
+mod class_change;
 mod groups;
 mod login;
 mod logout;
+mod reverify;
+mod roles;
 
 /// Serenity listener to react to `Discord` events
 #[allow(clippy::too_many_lines)]
@@ -28,6 +32,9 @@ pub(crate) async fn event_handler(
 ) -> InteractionResult {
 	match event {
 		FullEvent::Ready { data_about_bot } => {
+			// Needed by the web server to edit interaction responses
+			data.http.set_application_id(data_about_bot.application.id);
+
 			register_(
 				&ctx.http,
 				&data.config.discord_development_guild,
@@ -38,6 +45,11 @@ pub(crate) async fn event_handler(
 
 			tracing::info!("`{}` is ready!", data_about_bot.user.name);
 
+			spawn_reconciliation(
+				Arc::clone(data),
+				data_about_bot.guilds.iter().map(|guild| guild.id).collect(),
+			)?;
+
 			Ok(())
 		}
 
@@ -80,18 +92,33 @@ pub(crate) async fn event_handler(
 		FullEvent::GuildMemberRemoval { guild_id, user, .. } => {
 			tracing::info!(guild_id = guild_id.get(), "Deleting member `{}`", user.name);
 
+			let mut connection = data.database.get().await?;
+
+			// The verification references the member
+			unlink(data, &mut connection, (*guild_id, user.id), None).await?;
+
 			diesel::delete(
 				members::table
 					.filter(members::guild_id.eq(guild_id.get()))
 					.filter(members::discord_id.eq(user.id.get())),
 			)
-			.execute(&mut data.database.get().await?)
+			.execute(&mut connection)
 			.await?;
 
 			Ok(())
 		}
 
-		FullEvent::GuildCreate { guild, .. } => {
+		FullEvent::GuildCreate { guild, is_new } => {
+			data.xp.set_afk_channel(
+				guild.id,
+				guild.afk_metadata.as_ref().map(|afk| afk.afk_channel_id),
+			);
+			// Members already in a voice channel start earning XP now
+			for voice_state in guild.voice_states.values() {
+				data.xp
+					.on_voice_state(guild.id, voice_state.user_id, voice_state.channel_id);
+			}
+
 			let mut connection = data.database.get().await?;
 
 			if let Ok(guild) = guilds::table
@@ -109,10 +136,15 @@ pub(crate) async fn event_handler(
 					id: guild.id.get(),
 					name: guild.name.as_str(),
 					owner_id: guild.owner_id.get(),
-					verification_email_domain: None,
+					identity_provider: None,
 					login_message_id: None,
 					groups_message_id: None,
+					groups_message_channel_id: None,
+					level_up_channel_id: None,
+					log_channel_id: None,
 					verified_role_id: None,
+					teacher_role_id: None,
+					staff_role_id: None,
 				};
 
 				tracing::info!(
@@ -127,9 +159,52 @@ pub(crate) async fn event_handler(
 					.await?;
 			}
 
+			// Guilds available at startup are reconciled once ready
+			if *is_new == Some(true) {
+				spawn_reconciliation(Arc::clone(data), vec![guild.id])?;
+			}
+
+			Ok(())
+		}
+
+		FullEvent::GuildUpdate { new_data, .. } => {
+			data.xp.set_afk_channel(
+				new_data.id,
+				new_data.afk_metadata.as_ref().map(|afk| afk.afk_channel_id),
+			);
+
 			Ok(())
 		}
 
+		FullEvent::Message { new_message } => {
+			if let Some(guild_id) = new_message.guild_id {
+				if !new_message.author.bot {
+					data.xp.on_message(guild_id, new_message.author.id);
+				}
+			}
+
+			Ok(())
+		}
+
+		FullEvent::VoiceStateUpdate { new, .. } => {
+			if let Some(guild_id) = new.guild_id {
+				if !new.member.as_ref().is_some_and(|member| member.user.bot) {
+					data.xp
+						.on_voice_state(guild_id, new.user_id, new.channel_id);
+				}
+			}
+
+			Ok(())
+		}
+
+		FullEvent::GuildRoleDelete {
+			guild_id,
+			removed_role_id,
+			..
+		} => roles::role_deleted(data, *guild_id, *removed_role_id).await,
+
+		FullEvent::GuildMemberUpdate { event, .. } => roles::member_updated(data, event).await,
+
 		FullEvent::GuildDelete { incomplete, .. } => {
 			tracing::warn!("Deleting guild ({})", incomplete.id);
 
@@ -161,6 +236,7 @@ pub(crate) async fn event_handler(
 			match interaction.data.custom_id.as_str() {
 				events::LOGIN_BUTTON_INTERACTION => login::login(ctx).await,
 				events::LOGOUT_BUTTON_INTERACTION => logout::logout(ctx).await,
+				events::CHANGE_CLASS_BUTTON_INTERACTION => class_change::change_class(ctx).await,
 
 				events::GROUPS_SELECT_MENU_INTERACTION => {
 					let ComponentInteractionDataKind::StringSelect { values } =
@@ -172,6 +248,22 @@ pub(crate) async fn event_handler(
 					groups::groups(ctx, values).await
 				}
 
+				custom_id
+					if custom_id
+						.strip_prefix(events::REVERIFY_BUTTON_INTERACTION)
+						.is_some_and(|guild_id| guild_id.starts_with('.')) =>
+				{
+					reverify::reverify(ctx).await
+				}
+
+				custom_id
+					if custom_id.starts_with(events::CLASS_CHANGE_APPROVE_BUTTON_INTERACTION)
+						|| custom_id
+							.starts_with(events::CLASS_CHANGE_REJECT_BUTTON_INTERACTION) =>
+				{
+					class_change::review(ctx).await
+				}
+
 				_ => Ok(()),
 			}
 		}
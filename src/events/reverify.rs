@@ -0,0 +1,107 @@
+//! Renew the verification of a member from the reminder sent before it expires
+
+use crate::{
+	constants,
+	database::{
+		models::{Guild, NewPendingLogin, PendingLogin, VerifiedMember},
+		prelude::*,
+		schema::verified_members,
+	},
+	states::{InteractionResult, MessageComponentContext},
+	translation::Translate,
+};
+use anyhow::{anyhow, Context};
+use chrono::Utc;
+use poise::{
+	serenity_prelude::{CreateActionRow, CreateButton, GuildId},
+	CreateReply,
+};
+
+/// Start the auth process again with the kind and class the member already has
+///
+/// The reminder is a direct message, so the guild comes from the button and not from the interaction
+#[tracing::instrument(skip_all, fields(caller_id = %ctx.interaction.user.id))]
+pub(crate) async fn reverify(ctx: MessageComponentContext<'_>) -> InteractionResult {
+	let guild_id = ctx
+		.interaction
+		.data
+		.custom_id
+		.rsplit_once('.')
+		.and_then(|(_, guild_id)| guild_id.parse::<GuildId>().ok())
+		.ok_or_else(|| anyhow!("Something went wrong while parsing the guild id"))?;
+	let user = &ctx.interaction.user;
+
+	let mut connection = ctx.data.database.get().await?;
+
+	let Some((member_kind, class_id)) = VerifiedMember::with_ids(user.id, guild_id)
+		.select((verified_members::member_kind, verified_members::class_id))
+		.first::<(String, Option<i32>)>(&mut connection)
+		.await
+		.optional()?
+	else {
+		ctx.shout(ctx.translate("reverification-not-verified", None))
+			.await?;
+
+		return Ok(());
+	};
+
+	let guild = Guild::with_id(guild_id)
+		.first::<Guild>(&mut connection)
+		.await?;
+	let identity_provider = guild
+		.identity_provider
+		.unwrap_or_else(|| constants::DEFAULT_IDENTITY_PROVIDER.into());
+
+	let authorization_request = ctx
+		.data
+		.auth
+		.get(&identity_provider)
+		.with_context(|| format!("The identity provider `{identity_provider}` is not available"))?
+		.authorize_url(guild.offline_access && ctx.data.config.token_cipher.is_some())
+		.await
+		.context("could not get the authorization url")?;
+
+	let guild_image_source = guild_id
+		.to_partial_guild(&ctx)
+		.await?
+		.icon_url()
+		.unwrap_or_default();
+
+	let now = Utc::now().naive_utc();
+
+	// Clean up logins that were never finished
+	diesel::delete(PendingLogin::all_expired(now))
+		.execute(&mut connection)
+		.await?;
+
+	let new_pending_login = NewPendingLogin {
+		csrf_state: authorization_request.csrf_state.secret(),
+		guild_id: guild_id.get(),
+		discord_id: user.id.get(),
+		username: &user.name,
+		guild_image_source: &guild_image_source,
+		locale: &ctx.interaction.locale,
+		interaction_token: &ctx.interaction.token,
+		identity_provider: &identity_provider,
+		nonce: authorization_request.nonce.secret(),
+		member_kind: &member_kind,
+		class_id,
+		expires_at: now + constants::AUTHENTICATION_TIMEOUT,
+	};
+
+	new_pending_login.insert().execute(&mut connection).await?;
+
+	let action_row =
+		CreateActionRow::Buttons(vec![CreateButton::new_link(authorization_request.url)
+			.label(ctx.translate("continue", None))]);
+
+	ctx.send(
+		CreateReply::default()
+			.ephemeral(true)
+			.content(ctx.translate("use-google-account-to-login", None))
+			.components(vec![action_row]),
+	)
+	.await?;
+
+	Ok(())
+}
@@ -0,0 +1,266 @@
+//! Keep the database in line with roles deleted or removed by hand on `Discord`
+
+use crate::{
+	audit::{self, AuditEntry, AuditKind},
+	database::{
+		models::{Guild, GuildEmailRule, VerifiedMember},
+		prelude::*,
+		schema::{
+			classes, groups, guild_email_rules, guilds, levels, verified_members, xp_rewards,
+		},
+	},
+	states::{Data, InteractionResult},
+	translation::Translate,
+	verification::{
+		email_rules::VerifiedRoles, is_unknown_member, is_unknown_role, member_kind::MemberKind,
+		role_policy::VerifiedRolePolicy, unlink::unlink,
+	},
+};
+use fluent::fluent_args;
+use poise::serenity_prelude::{GuildId, GuildMemberUpdateEvent, RoleId};
+
+/// Mark what used a deleted role as broken and tell the admins
+#[tracing::instrument(skip(data))]
+pub(super) async fn role_deleted(
+	data: &Data,
+	guild_id: GuildId,
+	role_id: RoleId,
+) -> InteractionResult {
+	let mut connection = data.database.get().await?;
+
+	let Some(guild) = Guild::with_id(guild_id)
+		.first::<Guild>(&mut connection)
+		.await
+		.optional()?
+	else {
+		return Ok(());
+	};
+
+	let role_id = role_id.get();
+	let mut notices = Vec::new();
+
+	for name in levels::table
+		.filter(levels::guild_id.eq(guild_id.get()))
+		.filter(levels::role_id.eq(role_id))
+		.select(levels::name)
+		.load::<String>(&mut connection)
+		.await?
+	{
+		notices.push(data.translate("role-deleted-level", Some(fluent_args!["name" => name])));
+	}
+	diesel::update(
+		levels::table
+			.filter(levels::guild_id.eq(guild_id.get()))
+			.filter(levels::role_id.eq(role_id)),
+	)
+	.set(levels::broken.eq(true))
+	.execute(&mut connection)
+	.await?;
+
+	for name in classes::table
+		.filter(classes::guild_id.eq(guild_id.get()))
+		.filter(classes::role_id.eq(role_id))
+		.select(classes::name)
+		.load::<String>(&mut connection)
+		.await?
+	{
+		notices.push(data.translate("role-deleted-class", Some(fluent_args!["name" => name])));
+	}
+	diesel::update(
+		classes::table
+			.filter(classes::guild_id.eq(guild_id.get()))
+			.filter(classes::role_id.eq(role_id)),
+	)
+	.set(classes::broken.eq(true))
+	.execute(&mut connection)
+	.await?;
+
+	for name in groups::table
+		.filter(groups::guild_id.eq(guild_id.get()))
+		.filter(groups::role_id.eq(role_id))
+		.select(groups::name)
+		.load::<String>(&mut connection)
+		.await?
+	{
+		notices.push(data.translate("role-deleted-group", Some(fluent_args!["name" => name])));
+	}
+	diesel::update(
+		groups::table
+			.filter(groups::guild_id.eq(guild_id.get()))
+			.filter(groups::role_id.eq(role_id)),
+	)
+	.set(groups::broken.eq(true))
+	.execute(&mut connection)
+	.await?;
+
+	// Without a role, the verified role and the kinds must be setup again
+	if guild.verified_role_id == Some(role_id) {
+		diesel::update(Guild::with_id(guild_id))
+			.set(guilds::verified_role_id.eq::<Option<u64>>(None))
+			.execute(&mut connection)
+			.await?;
+
+		notices.push(data.translate("role-deleted-verified", None));
+	}
+	for kind in [MemberKind::Teacher, MemberKind::Staff] {
+		if kind.role_id(&guild) != Some(role_id) {
+			continue;
+		}
+
+		let update = diesel::update(Guild::with_id(guild_id));
+		match kind {
+			MemberKind::Teacher => {
+				update
+					.set(guilds::teacher_role_id.eq::<Option<u64>>(None))
+					.execute(&mut connection)
+					.await?
+			}
+			MemberKind::Staff => {
+				update
+					.set(guilds::staff_role_id.eq::<Option<u64>>(None))
+					.execute(&mut connection)
+					.await?
+			}
+			MemberKind::Student => unreachable!("students do not have a kind role"),
+		};
+
+		notices.push(data.translate(
+			"role-deleted-kind",
+			Some(fluent_args!["kind" => data.translate(kind.translation_key(), None)]),
+		));
+	}
+
+	// The rules fall back to the verified role of the guild
+	for pattern in GuildEmailRule::all_from_guild(guild_id)
+		.filter(guild_email_rules::role_id.eq(role_id))
+		.select(guild_email_rules::pattern)
+		.load::<String>(&mut connection)
+		.await?
+	{
+		notices.push(data.translate(
+			"role-deleted-email-rule",
+			Some(fluent_args!["name" => pattern]),
+		));
+	}
+	diesel::update(
+		GuildEmailRule::all_from_guild(guild_id).filter(guild_email_rules::role_id.eq(role_id)),
+	)
+	.set(guild_email_rules::role_id.eq::<Option<u64>>(None))
+	.execute(&mut connection)
+	.await?;
+
+	let deleted_rewards = diesel::delete(
+		xp_rewards::table
+			.filter(xp_rewards::guild_id.eq(guild_id.get()))
+			.filter(xp_rewards::role_id.eq(role_id)),
+	)
+	.execute(&mut connection)
+	.await?;
+	if deleted_rewards > 0 {
+		notices.push(data.translate("role-deleted-reward", None));
+	}
+
+	if notices.is_empty() {
+		return Ok(());
+	}
+
+	tracing::warn!(notices = ?notices, "a role used by the bot was deleted");
+
+	audit::record(
+		data,
+		guild_id,
+		AuditEntry::new(AuditKind::RoleDeleted).details(notices.join("\n")),
+	)
+	.await;
+
+	Ok(())
+}
+
+/// Apply the policy of the guild when a verified member loses the verified role by hand
+#[tracing::instrument(skip_all, fields(guild_id = %event.guild_id, user_id = %event.user.id))]
+pub(super) async fn member_updated(
+	data: &Data,
+	event: &GuildMemberUpdateEvent,
+) -> InteractionResult {
+	if event.user.bot {
+		return Ok(());
+	}
+
+	let mut connection = data.database.get().await?;
+
+	let Some(guild) = Guild::with_id(event.guild_id)
+		.first::<Guild>(&mut connection)
+		.await
+		.optional()?
+	else {
+		return Ok(());
+	};
+
+	let policy = guild.verified_role_policy.parse::<VerifiedRolePolicy>()?;
+	if policy == VerifiedRolePolicy::Ignore {
+		return Ok(());
+	}
+
+	let Some(mail) = VerifiedMember::with_ids(event.user.id, event.guild_id)
+		.select(verified_members::mail)
+		.first::<String>(&mut connection)
+		.await
+		.optional()?
+	else {
+		return Ok(());
+	};
+
+	let rules = GuildEmailRule::all_from_guild(event.guild_id)
+		.order_by(guild_email_rules::id)
+		.load::<GuildEmailRule>(&mut connection)
+		.await?;
+
+	let Some(verified_role_id) = VerifiedRoles::new(&rules, guild.verified_role_id)
+		.of(&mail)
+		.map(RoleId::new)
+	else {
+		return Ok(());
+	};
+
+	if event.roles.contains(&verified_role_id) {
+		return Ok(());
+	}
+
+	let key = match policy {
+		VerifiedRolePolicy::Ignore => return Ok(()),
+		VerifiedRolePolicy::Reapply => {
+			match data
+				.http
+				.add_member_role(event.guild_id, event.user.id, verified_role_id, None)
+				.await
+			{
+				Ok(()) => {}
+				Err(error) if is_unknown_member(&error) || is_unknown_role(&error) => {
+					return Ok(());
+				}
+				Err(error) => return Err(error.into()),
+			}
+
+			"verified-role-removed-reapply"
+		}
+		VerifiedRolePolicy::Logout => {
+			unlink(data, &mut connection, (event.guild_id, event.user.id), None).await?;
+
+			"verified-role-removed-logout"
+		}
+	};
+
+	audit::record(
+		data,
+		event.guild_id,
+		AuditEntry::new(AuditKind::VerifiedRoleRemoved)
+			.target(event.user.id)
+			.details(data.translate(
+				key,
+				Some(fluent_args!["user" => format!("<@{}>", event.user.id)]),
+			)),
+	)
+	.await;
+
+	Ok(())
+}
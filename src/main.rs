@@ -1,8 +1,10 @@
 //! Discord SMP Bot
 
+mod audit;
 mod auth;
 mod commands;
 mod constants;
+mod csv;
 mod database;
 mod events;
 mod logging;
@@ -10,6 +12,8 @@ mod polyfill;
 mod server;
 mod states;
 mod translation;
+mod verification;
+mod xp;
 
 use crate::{
 	commands::{command_on_error, post_command, pre_command},
@@ -39,15 +43,29 @@ fn build_framework(data: ArcData) -> Framework {
 			post_command,
 			event_handler: |ctx, event, fw, data| Box::pin(event_handler(ctx, event, fw, data)),
 			commands: {
-				use commands::{classes, groups, helpers, information, levels, setup};
+				use commands::{
+					audit, classes, groups, helpers, information, leaderboard, levels, members,
+					privacy, profile, rank, rewards, roster, setup, trust, verify, year,
+				};
 
 				#[rustfmt::skip]
 				let mut commands = vec![
 					setup(),
 					levels(),
 					classes(),
+					roster(),
+					year(),
+					members(),
 					groups(),
 					information(),
+					rank(),
+					leaderboard(),
+					rewards(),
+					audit(),
+					privacy(),
+					profile(),
+					trust(),
+					verify(),
 					helpers::debug(),
 				];
 
@@ -70,6 +88,9 @@ async fn main() -> anyhow::Result<()> {
 	let _handle = start_server(Arc::clone(&data))?;
 
 	run_migrations(data.config.database_url.expose_secret()).context("failed to run migrations")?;
+	let _xp_handle = xp::spawn_flusher(Arc::clone(&data))?;
+	let _expiry_handle = verification::expiry::spawn_expiry_checker(Arc::clone(&data))?;
+	let _account_handle = verification::offline_access::spawn_account_checker(Arc::clone(&data))?;
 
 	let mut client = ClientBuilder::new(
 		data.config.discord_token.expose_secret(),
@@ -1,11 +1,30 @@
 //! The request handlers that serves content
 
 use super::{AcceptLanguage, ServerError};
-use crate::{auth::PendingAuthRequest, states::ArcData};
-use anyhow::{anyhow, Context};
-use oauth2::AuthorizationCode;
+use crate::{
+	audit::{self, AuditEntry, AuditKind},
+	database::{
+		models::{Member, PendingLogin, VerifiedMember},
+		prelude::*,
+		schema,
+	},
+	states::ArcData,
+	verification::{
+		apply_changes,
+		duplicates::flag_duplicate,
+		eligibility::{check_eligibility, Eligibility},
+		offline_access::store_refresh_token,
+		trust::share_identity,
+	},
+};
+use anyhow::Context;
+use chrono::{NaiveDateTime, Utc};
+use fluent::{fluent_args, FluentArgs};
+use poise::serenity_prelude::{EditInteractionResponse, GuildId, UserId};
 use rocket::{response::Redirect, FromForm, Request, State};
 use rocket_dyn_templates::{context, Template};
+use std::borrow::Cow;
+use unic_langid::LanguageIdentifier;
 
 /// The parameters for the `OAuth2` callback endpoint
 #[derive(FromForm)]
@@ -16,8 +35,43 @@ pub(crate) struct OAuth2Params {
 	state: String,
 }
 
+/// Edit the ephemeral login message of the member that started the flow
+///
+/// Failures are only logged, the interaction token is only valid for 15 minutes
+async fn answer_pending_login(
+	data: &ArcData,
+	pending_login: &PendingLogin,
+	key: &str,
+	args: Option<FluentArgs<'_>>,
+) {
+	let locale = pending_login
+		.locale
+		.parse::<LanguageIdentifier>()
+		.unwrap_or_else(|_| data.config.default_locale.clone());
+
+	let content = data
+		.translations
+		.translate_checked(&locale, key, args.as_ref())
+		.map_or_else(|_| key.to_owned(), Cow::into_owned);
+
+	let response = EditInteractionResponse::new()
+		.content(content)
+		.components(vec![]);
+
+	if let Err(error) = data
+		.http
+		.edit_original_interaction_response(&pending_login.interaction_token, &response, vec![])
+		.await
+	{
+		tracing::warn!(error = ?error, "could not edit the login message");
+	}
+}
+
 // TODO: show more comprehensive errors to the user
 /// Handle requests to `/oauth2` endpoints
+///
+/// The login is finished here, so it does not depend on the interaction task that started it
+#[allow(clippy::too_many_lines)]
 #[rocket::get("/oauth2?<params..>")]
 pub(super) async fn handle_oauth2(
 	data: &State<ArcData>,
@@ -26,40 +80,226 @@ pub(super) async fn handle_oauth2(
 ) -> Result<Template, ServerError> {
 	// let msg = data.translations.translate_checked(&lang, "", None)?;
 
-	let PendingAuthRequest {
-		guild_image_source,
-		tx,
-		username,
-	} = {
-		let mut queue = data.auth.pending.write().await;
+	let mut connection = data
+		.database
+		.get()
+		.await
+		.context("could not get a database connection")?;
 
-		queue.remove(&params.state).ok_or(ServerError::User(
+	let Some(pending_login) = PendingLogin::with_state(&params.state)
+		.first::<PendingLogin>(&mut connection)
+		.await
+		.optional()
+		.context("could not fetch the pending login")?
+	else {
+		return Err(ServerError::User(
 			"The given 'state' wasn't queued anymore".into(),
-		))?
+		));
+	};
+
+	// A state can only be used once
+	diesel::delete(&pending_login)
+		.execute(&mut connection)
+		.await
+		.context("could not delete the pending login")?;
+
+	if pending_login.expires_at < Utc::now().naive_utc() {
+		answer_pending_login(data, &pending_login, "did-not-finish-auth-process", None).await;
+
+		return Err(ServerError::User(
+			"The authentication timeout has expired".into(),
+		));
+	}
+
+	let guild_id = GuildId::new(pending_login.guild_id);
+	let user_id = UserId::new(pending_login.discord_id);
+
+	let Some(identity_provider) = data.auth.get(&pending_login.identity_provider) else {
+		return Err(ServerError::User(
+			"The identity provider is not available anymore".into(),
+		));
 	};
 
-	let http_client = reqwest::ClientBuilder::new()
-		// Following redirects opens the client up to SSRF vulnerabilities.
-		.redirect(reqwest::redirect::Policy::none())
-		.build()
-		.context("could not build http client")?;
-
-	let token_response = data
-		.auth
-		.client
-		.exchange_code(AuthorizationCode::new(params.code))
-		.request_async(&http_client)
+	let mut user_data = identity_provider
+		.user_metadata(params.code, &pending_login.nonce)
+		.await
+		.context("failed to query user metadata")?;
+
+	let Some(member_id) = Member::with_ids(user_id, guild_id)
+		.select(schema::members::id)
+		.first::<i32>(&mut connection)
 		.await
-		.context("could not get oauth2 token")?;
+		.optional()
+		.context("could not fetch the member")?
+	else {
+		answer_pending_login(
+			data,
+			&pending_login,
+			"error-member-not-registered",
+			Some(fluent_args!["user" => pending_login.username.as_str()]),
+		)
+		.await;
+
+		return Err(ServerError::User(
+			"You are not registered in this guild".into(),
+		));
+	};
+
+	let Eligibility {
+		verified_role,
+		member_kind,
+		class_id,
+		duplicates,
+	} = match check_eligibility(
+		&mut connection,
+		(guild_id, member_id),
+		&mut user_data,
+		(&pending_login.member_kind, pending_login.class_id),
+	)
+	.await?
+	{
+		Ok(eligibility) => eligibility,
+		Err(refusal) => {
+			answer_pending_login(data, &pending_login, refusal.translation_key(), None).await;
+
+			return Err(ServerError::User(refusal.to_string()));
+		}
+	};
+
+	// Either a renewal of the verification, or another login finished in the meantime
+	if let Some(verified_mail) = VerifiedMember::from_member_id(member_id)
+		.select(schema::verified_members::mail)
+		.first::<String>(&mut connection)
+		.await
+		.optional()
+		.context("could not check the verified member")?
+	{
+		// A manual verification has no mail yet, it takes the mail of the first account used
+		if !verified_mail.is_empty() && !verified_mail.eq_ignore_ascii_case(&user_data.mail) {
+			answer_pending_login(data, &pending_login, "reverification-different-mail", None).await;
+
+			return Err(ServerError::User("You are already verified".into()));
+		}
+
+		diesel::update(VerifiedMember::from_member_id(member_id))
+			.set((
+				schema::verified_members::mail.eq(&user_data.mail),
+				schema::verified_members::first_name.eq(&user_data.first_name),
+				schema::verified_members::last_name.eq(&user_data.last_name),
+				schema::verified_members::verified_at.eq(Utc::now().naive_utc()),
+				schema::verified_members::reminded_at.eq::<Option<NaiveDateTime>>(None),
+				schema::verified_members::verified_by.eq::<Option<u64>>(None),
+			))
+			.execute(&mut connection)
+			.await
+			.context("could not renew the verification")?;
+
+		if let Err(error) = store_refresh_token(
+			data,
+			&mut connection,
+			member_id,
+			(
+				user_data.refresh_token.as_ref(),
+				&pending_login.identity_provider,
+			),
+		)
+		.await
+		{
+			tracing::warn!(error = ?error, "could not store the refresh token");
+		}
+
+		if let Err(error) = share_identity(
+			&mut connection,
+			(guild_id, user_id),
+			member_id,
+			&user_data,
+			&pending_login.identity_provider,
+		)
+		.await
+		{
+			tracing::warn!(error = ?error, "could not share the identity");
+		}
+
+		audit::record(
+			data,
+			guild_id,
+			AuditEntry::new(AuditKind::Reverified)
+				.target(user_id)
+				.details(user_data.mail),
+		)
+		.await;
+
+		answer_pending_login(data, &pending_login, "reverification-successful", None).await;
+
+		return Ok(Template::render(
+			"auth",
+			context! {
+				username: pending_login.username,
+				guild_image_source: format!("{}?size=2048", pending_login.guild_image_source)
+			},
+		));
+	}
+
+	apply_changes(
+		&data.http,
+		&mut connection,
+		(guild_id, user_id),
+		&user_data,
+		member_id,
+		verified_role,
+		member_kind,
+		class_id,
+		None,
+	)
+	.await
+	.context("could not apply the verification")?;
+
+	if let Err(error) = store_refresh_token(
+		data,
+		&mut connection,
+		member_id,
+		(
+			user_data.refresh_token.as_ref(),
+			&pending_login.identity_provider,
+		),
+	)
+	.await
+	{
+		tracing::warn!(error = ?error, "could not store the refresh token");
+	}
+
+	if let Err(error) = share_identity(
+		&mut connection,
+		(guild_id, user_id),
+		member_id,
+		&user_data,
+		&pending_login.identity_provider,
+	)
+	.await
+	{
+		tracing::warn!(error = ?error, "could not share the identity");
+	}
+
+	if !duplicates.is_empty() {
+		flag_duplicate(data, (guild_id, user_id), &user_data.mail, &duplicates).await;
+	}
+
+	audit::record(
+		data,
+		guild_id,
+		AuditEntry::new(AuditKind::Login)
+			.target(user_id)
+			.details(format!("{} ({member_kind})", user_data.mail)),
+	)
+	.await;
 
-	tx.send(token_response)
-		.map_err(|_| ServerError::Other(anyhow!("the receiver was dropped")))?;
+	answer_pending_login(data, &pending_login, "authentication-successful", None).await;
 
 	Ok(Template::render(
 		"auth",
 		context! {
-			username,
-			guild_image_source: format!("{guild_image_source}?size=2048")
+			username: pending_login.username,
+			guild_image_source: format!("{}?size=2048", pending_login.guild_image_source)
 		},
 	))
 }
@@ -1,7 +1,11 @@
 //! Handles all the states of the bot and initial configuration
 
 use crate::{
-	auth::GoogleAuthentification, database::DatabasePool, polyfill, translation::Translations,
+	auth::{IdentityProviders, TokenCipher},
+	database::DatabasePool,
+	polyfill,
+	translation::Translations,
+	xp::XpTracker,
 };
 use anyhow::{anyhow, Context as _};
 use diesel_async::{
@@ -37,6 +41,10 @@ pub(crate) struct Config {
 	pub(crate) database_url: SecretString,
 	/// The `Google` auth client id and secret pair
 	pub(crate) google_client: (ClientId, ClientSecret),
+	/// Additional `OpenID Connect` providers guilds can select
+	pub(crate) oidc_providers: Vec<OidcProviderConfig>,
+	/// Encrypts the refresh tokens, guilds can only enable offline access with it
+	pub(crate) token_cipher: Option<TokenCipher>,
 	/// The `Discord` invite link to rejoin the support server
 	pub(crate) discord_invite_code: String,
 	/// The url of the `OAuth2` callback
@@ -52,6 +60,58 @@ pub(crate) struct Config {
 	pub(crate) production: bool,
 }
 
+/// An `OpenID Connect` provider configuration
+#[derive(Debug)]
+pub(crate) struct OidcProviderConfig {
+	/// The name guilds use to select this provider
+	pub(crate) name: String,
+	/// The issuer url, used to find the discovery document
+	///
+	/// Example: `https://login.microsoftonline.com/<tenant>/v2.0`
+	pub(crate) issuer_url: String,
+	/// The client id and secret pair
+	pub(crate) client: (ClientId, ClientSecret),
+	/// Whether to accept the mail of ID tokens without the `email_verified` claim
+	///
+	/// Only for providers bound to an organization that manages the mails, like `Microsoft Entra ID`.
+	/// A mail explicitly flagged as not verified is always refused.
+	pub(crate) trust_unverified_email: bool,
+}
+
+impl OidcProviderConfig {
+	/// Parse the providers listed in `OIDC_PROVIDERS`
+	///
+	/// Each provider `name` is configured with `OIDC_<NAME>_ISSUER`, `OIDC_<NAME>_CLIENT_ID`
+	/// and `OIDC_<NAME>_CLIENT_SECRET`, and optionally `OIDC_<NAME>_TRUST_UNVERIFIED_EMAIL`
+	fn from_env() -> anyhow::Result<Vec<Self>> {
+		let names = env::var("OIDC_PROVIDERS").unwrap_or_default();
+
+		names
+			.split(',')
+			.map(str::trim)
+			.filter(|name| !name.is_empty())
+			.map(|name| {
+				let prefix = format!("OIDC_{}", name.to_uppercase().replace('-', "_"));
+
+				Ok(Self {
+					name: name.to_owned(),
+					issuer_url: required_env_var(&format!("{prefix}_ISSUER"))?,
+					client: (
+						ClientId::new(required_env_var(&format!("{prefix}_CLIENT_ID"))?),
+						ClientSecret::new(required_env_var(&format!("{prefix}_CLIENT_SECRET"))?),
+					),
+					trust_unverified_email: env::var(format!("{prefix}_TRUST_UNVERIFIED_EMAIL"))
+						.unwrap_or_else(|_| "false".into())
+						.parse::<bool>()
+						.map_err(|_| {
+							anyhow!("{prefix}_TRUST_UNVERIFIED_EMAIL environnement variable must be a `bool`")
+						})?,
+				})
+			})
+			.collect()
+	}
+}
+
 /// Resolve an environment variable or return an appropriate error
 fn required_env_var(name: &str) -> anyhow::Result<String> {
 	match env::var(name) {
@@ -95,6 +155,12 @@ impl Config {
 				ClientId::new(required_env_var("GOOGLE_CLIENT_ID")?),
 				ClientSecret::new(required_env_var("GOOGLE_CLIENT_SECRET")?),
 			),
+			oidc_providers: OidcProviderConfig::from_env()?,
+			token_cipher: env::var("TOKEN_ENCRYPTION_KEY")
+				.ok()
+				.filter(|key| !key.is_empty())
+				.map(|key| TokenCipher::new(&key))
+				.transpose()?,
 			discord_invite_code,
 			server_url: required_env_var("SERVER_URL")?,
 
@@ -108,12 +174,18 @@ impl Config {
 pub(crate) struct Data {
 	/// An access to the database
 	pub(crate) database: DatabasePool,
-	/// A instance of the auth provider
-	pub(crate) auth: GoogleAuthentification,
+	/// The identity providers guilds can use
+	pub(crate) auth: IdentityProviders,
+	/// A `Discord` HTTP client usable outside of gateway events, e.g. in the web server
+	///
+	/// The application id is set once the bot is ready
+	pub(crate) http: serenity::Http,
 	/// An instance of the parsed initial config
 	pub(crate) config: Config,
 	/// The translations for the client
 	pub(crate) translations: Translations,
+	/// The XP earned by the members that is not yet in the database
+	pub(crate) xp: XpTracker,
 }
 
 impl fmt::Debug for Data {
@@ -143,9 +215,11 @@ impl Data {
 
 		Ok(Self {
 			database,
-			auth: GoogleAuthentification::new(&config)?,
+			auth: IdentityProviders::new(&config)?,
+			http: serenity::Http::new(config.discord_token.expose_secret()),
 			config,
 			translations,
+			xp: XpTracker::default(),
 		})
 	}
 }
@@ -1,6 +1,6 @@
 //! Fluent Project translation system
 
-use crate::states::{ApplicationContext, Command, Context, MessageComponentContext};
+use crate::states::{ApplicationContext, Command, Context, Data, MessageComponentContext};
 use anyhow::anyhow;
 use fluent::{bundle, FluentArgs, FluentMessage, FluentResource};
 use fluent_syntax::ast::Pattern;
@@ -276,6 +276,18 @@ impl Translate for ApplicationContext<'_> {
 	}
 }
 
+/// Used outside of any interaction, e.g. for messages sent by background tasks
+impl Translate for Data {
+	fn translate_checked<'bundle>(
+		&'bundle self,
+		key: &'bundle str,
+		args: Option<&'bundle FluentArgs>,
+	) -> anyhow::Result<Cow<'bundle, str>> {
+		self.translations
+			.translate_checked(&self.config.default_locale, key, args)
+	}
+}
+
 impl Translate for Context<'_> {
 	fn translate_checked<'bundle>(
 		&'bundle self,
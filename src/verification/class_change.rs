@@ -0,0 +1,112 @@
+//! Move a verified student to another class without logging them out
+
+use crate::{
+	database::{
+		models::{Class, RosterEntry, VerifiedMember},
+		prelude::*,
+		schema::{classes, levels, verified_members},
+		DatabasePooledConnection,
+	},
+	states::InteractionError,
+	verification::swap_role,
+};
+use diesel::dsl;
+use poise::serenity_prelude::{GuildId, Http, RoleId, UserId};
+
+/// The class of a guild with the given level and class names, classes without a role are skipped
+pub(crate) async fn find_class(
+	connection: &mut DatabasePooledConnection,
+	guild_id: GuildId,
+	(level, class): (&str, &str),
+) -> Result<Option<i32>, DieselError> {
+	Class::all_from_guild(guild_id)
+		.inner_join(levels::table)
+		.filter(levels::name.eq(level))
+		.filter(classes::name.eq(class))
+		.filter(classes::broken.eq(false))
+		.select(classes::id)
+		.first::<i32>(connection)
+		.await
+		.optional()
+}
+
+/// Whether the class of a mail is given by the roster of a guild, students cannot leave such a class on their own
+pub(crate) async fn class_from_roster(
+	connection: &mut DatabasePooledConnection,
+	guild_id: GuildId,
+	mail: &str,
+) -> Result<bool, DieselError> {
+	dsl::select(dsl::exists(RosterEntry::with_mail(guild_id, mail)))
+		.get_result::<bool>(connection)
+		.await
+}
+
+/// Give the roles of the new class to a verified member, save the change and take the roles of the old class
+///
+/// The new roles are given first, so that the member is never left without a class if `Discord` fails midway.
+/// The level role is kept when the new class belongs to the same level.
+pub(crate) async fn change_class(
+	http: &Http,
+	connection: &mut DatabasePooledConnection,
+	(guild_id, user_id): (GuildId, UserId),
+	member_id: i32,
+	new_class_id: i32,
+) -> Result<(), InteractionError> {
+	let old_class_id = VerifiedMember::from_member_id(member_id)
+		.select(verified_members::class_id)
+		.first::<Option<i32>>(connection)
+		.await?;
+
+	let old_roles = match old_class_id {
+		Some(old_class_id) => Some(class_roles(connection, old_class_id).await?),
+		None => None,
+	};
+	let (new_level_role, new_class_role) = class_roles(connection, new_class_id).await?;
+
+	for new_role in [new_level_role, new_class_role] {
+		swap_role(http, guild_id, &[user_id], (None, Some(new_role))).await?;
+	}
+
+	diesel::update(VerifiedMember::from_member_id(member_id))
+		.set(verified_members::class_id.eq(new_class_id))
+		.execute(connection)
+		.await?;
+
+	if let Some(old_roles) = old_roles {
+		for old_role in <[RoleId; 2]>::from(old_roles) {
+			if old_role != new_level_role && old_role != new_class_role {
+				swap_role(http, guild_id, &[user_id], (Some(old_role), None)).await?;
+			}
+		}
+	}
+
+	Ok(())
+}
+
+/// The level and class roles of a class
+async fn class_roles(
+	connection: &mut DatabasePooledConnection,
+	class_id: i32,
+) -> Result<(RoleId, RoleId), DieselError> {
+	let (level_role_id, class_role_id) = Class::with_id(class_id)
+		.inner_join(levels::table)
+		.select((levels::role_id, classes::role_id))
+		.first::<(u64, u64)>(connection)
+		.await?;
+
+	Ok((RoleId::new(level_role_id), RoleId::new(class_role_id)))
+}
+
+/// The level and the name of a class, as shown to the members and the admins
+pub(crate) async fn class_label(
+	connection: &mut DatabasePooledConnection,
+	class_id: i32,
+) -> Result<String, DieselError> {
+	let (level_name, class_name) = Class::with_id(class_id)
+		.inner_join(levels::table)
+		.select((levels::name, classes::name))
+		.first::<(String, String)>(connection)
+		.await?;
+
+	Ok(format!("{level_name}, {class_name}"))
+}
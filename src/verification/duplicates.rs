@@ -0,0 +1,166 @@
+//! What to do when several accounts verify themselves with the same mail in a guild
+
+use crate::{
+	audit::{self, AuditEntry, AuditKind},
+	database::{
+		models::VerifiedMember,
+		prelude::*,
+		schema::{mail_claims, members, verified_members},
+		DatabasePooledConnection,
+	},
+	states::Data,
+};
+use anyhow::anyhow;
+use diesel::{
+	dsl::{self, insert_into},
+	result::DatabaseErrorKind,
+};
+use poise::serenity_prelude::{GuildId, UserId};
+use std::{fmt, str::FromStr};
+
+/// How the bot reacts when a mail already verified in the guild is used by another account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, poise::ChoiceParameter)]
+pub(crate) enum DuplicateMailPolicy {
+	/// Refuse the verification of the other account
+	#[name = "block"]
+	Block,
+	/// Verify the other account too
+	#[default]
+	#[name = "allow"]
+	Allow,
+	/// Verify the other account and tell the admins in the audit log
+	#[name = "allow_and_flag"]
+	Flag,
+}
+
+impl DuplicateMailPolicy {
+	/// The name stored in the database
+	pub(crate) const fn as_str(self) -> &'static str {
+		match self {
+			Self::Block => "block",
+			Self::Allow => "allow",
+			Self::Flag => "allow_and_flag",
+		}
+	}
+
+	/// The translation key of what the bot does
+	pub(crate) const fn translation_key(self) -> &'static str {
+		match self {
+			Self::Block => "duplicate-mail-policy-block",
+			Self::Allow => "duplicate-mail-policy-allow",
+			Self::Flag => "duplicate-mail-policy-flag",
+		}
+	}
+}
+
+impl fmt::Display for DuplicateMailPolicy {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+impl FromStr for DuplicateMailPolicy {
+	type Err = anyhow::Error;
+
+	fn from_str(policy: &str) -> Result<Self, Self::Err> {
+		match policy {
+			"block" => Ok(Self::Block),
+			"allow" => Ok(Self::Allow),
+			"allow_and_flag" => Ok(Self::Flag),
+			_ => Err(anyhow!("unknown duplicate mail policy `{policy}`")),
+		}
+	}
+}
+
+/// The other accounts verified with a mail in a guild
+pub(crate) async fn other_accounts(
+	connection: &mut DatabasePooledConnection,
+	(guild_id, member_id): (GuildId, i32),
+	mail: &str,
+) -> Result<Vec<UserId>, DieselError> {
+	Ok(verified_members::table
+		.inner_join(members::table)
+		.filter(members::guild_id.eq(guild_id.get()))
+		.filter(members::id.ne(member_id))
+		.filter(verified_members::mail.eq(mail))
+		.select(members::discord_id)
+		.load::<u64>(connection)
+		.await?
+		.into_iter()
+		.map(UserId::new)
+		.collect())
+}
+
+/// Claim a mail for a member, returns whether the member holds the claim
+///
+/// The primary key of the claims makes sure that two concurrent logins cannot both verify the same mail
+pub(crate) async fn claim_mail(
+	connection: &mut DatabasePooledConnection,
+	(guild_id, member_id): (GuildId, i32),
+	mail: &str,
+) -> Result<bool, DieselError> {
+	let mail = mail.to_lowercase();
+
+	match insert_into(mail_claims::table)
+		.values((
+			mail_claims::guild_id.eq(guild_id.get()),
+			mail_claims::mail.eq(&mail),
+			mail_claims::member_id.eq(member_id),
+		))
+		.execute(connection)
+		.await
+	{
+		Ok(_) => return Ok(true),
+		Err(DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => {}
+		Err(error) => return Err(error),
+	}
+
+	let claim = mail_claims::table.find((guild_id.get(), &mail));
+
+	let owner_id = claim
+		.select(mail_claims::member_id)
+		.first::<i32>(connection)
+		.await?;
+	if owner_id == member_id {
+		return Ok(true);
+	}
+
+	// The owner may have lost the verification since, e.g. after a logout
+	if dsl::select(dsl::exists(VerifiedMember::from_member_id(owner_id)))
+		.get_result::<bool>(connection)
+		.await?
+	{
+		return Ok(false);
+	}
+
+	// Only one of concurrent takeovers updates the claim
+	let taken_over = diesel::update(claim.filter(mail_claims::member_id.eq(owner_id)))
+		.set(mail_claims::member_id.eq(member_id))
+		.execute(connection)
+		.await?;
+
+	Ok(taken_over == 1)
+}
+
+/// Tell the admins that a member verified themselves with the mail of other accounts
+pub(crate) async fn flag_duplicate(
+	data: &Data,
+	(guild_id, user_id): (GuildId, UserId),
+	mail: &str,
+	other_accounts: &[UserId],
+) {
+	let other_accounts = other_accounts
+		.iter()
+		.map(|user_id| format!("<@{user_id}>"))
+		.collect::<Vec<_>>()
+		.join(", ");
+
+	audit::record(
+		data,
+		guild_id,
+		AuditEntry::new(AuditKind::DuplicateMail)
+			.target(user_id)
+			.details(format!("{mail}: {other_accounts}")),
+	)
+	.await;
+}
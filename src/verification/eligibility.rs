@@ -0,0 +1,167 @@
+//! Apply the rules of a guild to an account, before the verification is applied
+//!
+//! Shared by the `OAuth2` callback and the verifications reused from a trust group
+
+use crate::{
+	auth::UserMetadata,
+	database::{
+		models::{Guild, GuildEmailRule, RosterEntry},
+		prelude::*,
+		schema, DatabasePooledConnection,
+	},
+	verification::{
+		duplicates::{claim_mail, other_accounts, DuplicateMailPolicy},
+		email_rules::matching_rule,
+		member_kind::MemberKind,
+		VerifiedRole,
+	},
+};
+use anyhow::Context;
+use diesel::dsl;
+use poise::serenity_prelude::{GuildId, UserId};
+
+/// Why an account cannot be verified in a guild
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+pub(crate) enum Refusal {
+	/// The guild has no verified role anymore
+	#[error("The guild is not set up for verification anymore")]
+	NotSetUp,
+	/// No email rule of the guild allows the mail
+	#[error("Your email is not authorized")]
+	EmailNotAllowed,
+	/// The guild has a roster and the mail is not in it
+	#[error("You are not in the roster of this guild")]
+	NotInRoster,
+	/// A student did not choose their class, e.g. after logging in as a teacher
+	#[error("You must log in again as a student")]
+	MustChooseClass,
+	/// Another account is verified with the mail and the guild blocks duplicates
+	#[error("Another account is already verified with this email")]
+	DuplicateMail,
+}
+
+impl Refusal {
+	/// The translation key of the message answered to the member
+	pub(crate) const fn translation_key(self) -> &'static str {
+		match self {
+			Self::NotSetUp => "event-login-not-set-up",
+			Self::EmailNotAllowed => "event-login-email-domain-not-allowed",
+			Self::NotInRoster => "event-login-not-in-roster",
+			Self::MustChooseClass => "event-login-must-choose-class",
+			Self::DuplicateMail => "event-login-duplicate-mail",
+		}
+	}
+}
+
+/// What the verification of an account gives in a guild
+#[derive(Debug)]
+pub(crate) struct Eligibility {
+	/// The verified role, from the matched email rule or the guild
+	pub(crate) verified_role: VerifiedRole,
+	/// The kind of the member, the one of the matched rule prevails
+	pub(crate) member_kind: MemberKind,
+	/// The class of a student, the one of the roster prevails
+	pub(crate) class_id: Option<i32>,
+	/// The other accounts verified with the same mail, to flag
+	pub(crate) duplicates: Vec<UserId>,
+}
+
+/// Check that an account can be verified in a guild, with the kind and class the member chose
+///
+/// The names of the account are replaced by the ones of the roster, if the mail is in it.
+/// In the guilds that block duplicates, the mail is claimed for the member.
+pub(crate) async fn check_eligibility(
+	connection: &mut DatabasePooledConnection,
+	(guild_id, member_id): (GuildId, i32),
+	user_data: &mut UserMetadata,
+	(chosen_member_kind, chosen_class_id): (&str, Option<i32>),
+) -> anyhow::Result<Result<Eligibility, Refusal>> {
+	let (verified_role_id, duplicate_mail_policy) = Guild::with_id(guild_id)
+		.select((
+			schema::guilds::verified_role_id,
+			schema::guilds::duplicate_mail_policy,
+		))
+		.first::<(Option<u64>, String)>(connection)
+		.await
+		.context("could not fetch the guild")?;
+
+	let Some(verified_role_id) = verified_role_id else {
+		return Ok(Err(Refusal::NotSetUp));
+	};
+
+	let email_rules: Vec<GuildEmailRule> = GuildEmailRule::all_from_guild(guild_id)
+		.order_by(schema::guild_email_rules::id)
+		.get_results(connection)
+		.await
+		.context("could not fetch the email rules")?;
+
+	let Some(email_rule) = matching_rule(&email_rules, user_data)? else {
+		return Ok(Err(Refusal::EmailNotAllowed));
+	};
+
+	// The kind of the rule prevails over the one chosen by the member
+	let member_kind = email_rule
+		.member_kind
+		.as_deref()
+		.unwrap_or(chosen_member_kind)
+		.parse::<MemberKind>()?;
+
+	let class_id = if member_kind.has_class() {
+		let roster_entry = RosterEntry::with_mail(guild_id, &user_data.mail)
+			.first::<RosterEntry>(connection)
+			.await
+			.optional()
+			.context("could not fetch the roster entry")?;
+
+		match (roster_entry, chosen_class_id) {
+			// The roster prevails over what the student chose
+			(Some(roster_entry), _) => {
+				user_data.first_name = roster_entry.first_name;
+				user_data.last_name = roster_entry.last_name;
+
+				Some(roster_entry.class_id)
+			}
+			(None, Some(class_id)) => Some(class_id),
+			(None, None) => {
+				let has_roster = dsl::select(dsl::exists(RosterEntry::all_from_guild(guild_id)))
+					.get_result::<bool>(connection)
+					.await
+					.context("could not check the roster")?;
+
+				return Ok(Err(if has_roster {
+					Refusal::NotInRoster
+				} else {
+					Refusal::MustChooseClass
+				}));
+			}
+		}
+	} else {
+		None
+	};
+
+	let duplicates = match duplicate_mail_policy.parse::<DuplicateMailPolicy>()? {
+		DuplicateMailPolicy::Block => {
+			if !claim_mail(connection, (guild_id, member_id), &user_data.mail)
+				.await
+				.context("could not claim the mail")?
+			{
+				return Ok(Err(Refusal::DuplicateMail));
+			}
+
+			Vec::new()
+		}
+		DuplicateMailPolicy::Allow => Vec::new(),
+		DuplicateMailPolicy::Flag => {
+			other_accounts(connection, (guild_id, member_id), &user_data.mail)
+				.await
+				.context("could not fetch the other accounts")?
+		}
+	};
+
+	Ok(Ok(Eligibility {
+		verified_role: VerifiedRole::new(verified_role_id, email_rule),
+		member_kind,
+		class_id,
+		duplicates,
+	}))
+}
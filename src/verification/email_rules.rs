@@ -0,0 +1,160 @@
+//! Rules that decide which emails can verify themselves in a guild
+
+use crate::{auth::UserMetadata, database::models::GuildEmailRule};
+use anyhow::{anyhow, Context as _};
+use regex::{Regex, RegexBuilder};
+use std::{fmt, str::FromStr};
+
+/// How the pattern of a [`GuildEmailRule`] is matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub(crate) enum EmailRuleKind {
+	/// A mail domain like `school.fr`, or all of its subdomains with `*.school.fr`
+	#[name = "domain"]
+	Domain,
+	/// A full address with `*` and `?` wildcards like `*.teacher@school.fr`
+	#[name = "glob"]
+	Glob,
+	/// A regular expression that must match the full address
+	#[name = "regex"]
+	Regex,
+}
+
+impl EmailRuleKind {
+	/// The name stored in the database
+	pub(crate) const fn as_str(self) -> &'static str {
+		match self {
+			Self::Domain => "domain",
+			Self::Glob => "glob",
+			Self::Regex => "regex",
+		}
+	}
+}
+
+impl fmt::Display for EmailRuleKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+impl FromStr for EmailRuleKind {
+	type Err = anyhow::Error;
+
+	fn from_str(kind: &str) -> Result<Self, Self::Err> {
+		match kind {
+			"domain" => Ok(Self::Domain),
+			"glob" => Ok(Self::Glob),
+			"regex" => Ok(Self::Regex),
+			_ => Err(anyhow!("unknown email rule kind `{kind}`")),
+		}
+	}
+}
+
+/// A compiled email rule pattern
+#[derive(Debug)]
+pub(crate) struct EmailMatcher(Regex);
+
+impl EmailMatcher {
+	/// Compile a pattern, every kind is turned into a case insensitive regex on the full address
+	pub(crate) fn new(kind: EmailRuleKind, pattern: &str) -> anyhow::Result<Self> {
+		let regex = match kind {
+			EmailRuleKind::Domain => {
+				let (subdomains, domain) = pattern
+					.strip_prefix("*.")
+					.map_or((false, pattern), |domain| (true, domain));
+
+				if domain.is_empty() || domain.contains(['@', '*', '?']) {
+					return Err(anyhow!("a domain looks like `school.fr` or `*.school.fr`"));
+				}
+
+				if subdomains {
+					format!(r"[^@]+@[^@]+\.{}", regex::escape(domain))
+				} else {
+					format!(r"[^@]+@{}", regex::escape(domain))
+				}
+			}
+			EmailRuleKind::Glob => {
+				if pattern.matches('@').count() != 1 {
+					return Err(anyhow!("a glob must contain a single `@`"));
+				}
+
+				regex::escape(pattern)
+					.replace(r"\*", "[^@]*")
+					.replace(r"\?", "[^@]")
+			}
+			EmailRuleKind::Regex => pattern.to_owned(),
+		};
+
+		RegexBuilder::new(&format!("^(?:{regex})$"))
+			.case_insensitive(true)
+			.build()
+			.map(Self)
+			.map_err(Into::into)
+	}
+
+	/// Whether the full address matches the pattern
+	pub(crate) fn is_match(&self, mail: &str) -> bool {
+		self.0.is_match(mail)
+	}
+}
+
+/// Find the first rule, in creation order, that allows the user to verify
+///
+/// A mail is only trusted when the provider vouches for the organization it belongs to
+pub(crate) fn matching_rule<'a>(
+	rules: &'a [GuildEmailRule],
+	user_data: &UserMetadata,
+) -> anyhow::Result<Option<&'a GuildEmailRule>> {
+	if user_data.domain.is_none() {
+		return Ok(None);
+	}
+
+	for rule in rules {
+		let kind = rule.kind.parse::<EmailRuleKind>()?;
+		let matcher = EmailMatcher::new(kind, &rule.pattern)
+			.with_context(|| format!("invalid email rule `{}`", rule.pattern))?;
+
+		if matcher.is_match(&user_data.mail) {
+			return Ok(Some(rule));
+		}
+	}
+
+	Ok(None)
+}
+
+/// The verified role each mail gets, from the rules of a guild
+#[derive(Debug)]
+pub(crate) struct VerifiedRoles {
+	/// The compiled rules, in creation order, with the role they give
+	matchers: Vec<(EmailMatcher, Option<u64>)>,
+	/// The verified role of the guild, given when no rule has a role
+	guild_role_id: Option<u64>,
+}
+
+impl VerifiedRoles {
+	/// Compile the rules of a guild, invalid ones are skipped
+	pub(crate) fn new(rules: &[GuildEmailRule], guild_role_id: Option<u64>) -> Self {
+		let matchers = rules
+			.iter()
+			.filter_map(|rule| {
+				let kind = rule.kind.parse::<EmailRuleKind>().ok()?;
+				let matcher = EmailMatcher::new(kind, &rule.pattern).ok()?;
+
+				Some((matcher, rule.role_id))
+			})
+			.collect();
+
+		Self {
+			matchers,
+			guild_role_id,
+		}
+	}
+
+	/// The role of the first rule matching the mail, or the verified role of the guild
+	pub(crate) fn of(&self, mail: &str) -> Option<u64> {
+		self.matchers
+			.iter()
+			.find(|(matcher, _)| matcher.is_match(mail))
+			.and_then(|(_, role_id)| *role_id)
+			.or(self.guild_role_id)
+	}
+}
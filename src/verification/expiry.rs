@@ -0,0 +1,148 @@
+//! Make the verified members verify themselves again once the period of their guild is over
+//!
+//! Members are reminded by a direct message before their verification expires
+
+use crate::{
+	audit::{self, AuditEntry, AuditKind},
+	constants,
+	database::{
+		models::Guild,
+		prelude::*,
+		schema::{guilds, members, verified_members},
+	},
+	states::{ArcData, Data},
+	translation::Translate,
+	verification::unlink::unlink,
+};
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use fluent::fluent_args;
+use poise::serenity_prelude::{CreateActionRow, CreateButton, CreateMessage, GuildId, UserId};
+use tokio::task::{self, JoinHandle};
+
+/// Remind the members whose verification expires soon and unlink the ones whose verification expired
+pub(crate) async fn check_expiries(data: &Data) -> anyhow::Result<()> {
+	let mut connection = data.database.get().await?;
+
+	let guilds = guilds::table
+		.filter(guilds::reverification_days.is_not_null())
+		.load::<Guild>(&mut connection)
+		.await?;
+
+	for guild in guilds {
+		let Some(days) = guild.reverification_days else {
+			continue;
+		};
+
+		let guild_id = GuildId::new(guild.id);
+		let now = Utc::now().naive_utc();
+		let period = TimeDelta::days(days.into());
+		let lead = constants::reverification::REMINDER_LEAD.min(period / 2);
+
+		let expired = verified_members::table
+			.inner_join(members::table)
+			.filter(members::guild_id.eq(guild.id))
+			.filter(verified_members::verified_at.le(now - period))
+			.select(members::discord_id)
+			.load::<u64>(&mut connection)
+			.await?;
+
+		for discord_id in expired {
+			let user_id = UserId::new(discord_id);
+
+			match unlink(data, &mut connection, (guild_id, user_id), None).await {
+				Ok(true) => {}
+				Ok(false) => continue,
+				Err(error) => {
+					tracing::error!(error = ?error, user_id = discord_id, "could not unlink an expired member");
+
+					continue;
+				}
+			}
+
+			audit::record(
+				data,
+				guild_id,
+				AuditEntry::new(AuditKind::VerificationExpired).target(user_id),
+			)
+			.await;
+
+			let content = data.translate(
+				"reverification-expired",
+				Some(fluent_args!["guild" => guild.name.as_str()]),
+			);
+			if let Err(error) = user_id
+				.direct_message(&data.http, CreateMessage::new().content(content))
+				.await
+			{
+				tracing::warn!(error = ?error, user_id = discord_id, "could not tell a member that their verification expired");
+			}
+		}
+
+		let to_remind = verified_members::table
+			.inner_join(members::table)
+			.filter(members::guild_id.eq(guild.id))
+			.filter(verified_members::verified_at.le(now - period + lead))
+			.filter(verified_members::reminded_at.is_null())
+			.select((
+				verified_members::member_id,
+				members::discord_id,
+				verified_members::verified_at,
+			))
+			.load::<(i32, u64, NaiveDateTime)>(&mut connection)
+			.await?;
+
+		for (member_id, discord_id, verified_at) in to_remind {
+			let content = data.translate(
+				"reverification-reminder",
+				Some(fluent_args![
+					"guild" => guild.name.as_str(),
+					"expires_at" => (verified_at + period).and_utc().timestamp(),
+				]),
+			);
+			let button = CreateButton::new(format!(
+				"{}.{guild_id}",
+				constants::events::REVERIFY_BUTTON_INTERACTION
+			))
+			.label(data.translate("reverification-button", None));
+
+			// Members that do not accept direct messages are not reminded again either
+			if let Err(error) = UserId::new(discord_id)
+				.direct_message(
+					&data.http,
+					CreateMessage::new()
+						.content(content)
+						.components(vec![CreateActionRow::Buttons(vec![button])]),
+				)
+				.await
+			{
+				tracing::warn!(error = ?error, user_id = discord_id, "could not remind a member to verify themselves again");
+			}
+
+			diesel::update(verified_members::table.find(member_id))
+				.set(verified_members::reminded_at.eq(Some(now)))
+				.execute(&mut connection)
+				.await?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Check the expiries periodically, in the background
+pub(crate) fn spawn_expiry_checker(data: ArcData) -> anyhow::Result<JoinHandle<()>> {
+	let handle = task::Builder::new()
+		.name("Expiry Checker")
+		.spawn(async move {
+			let mut interval = tokio::time::interval(constants::reverification::CHECK_INTERVAL);
+
+			loop {
+				interval.tick().await;
+
+				if let Err(error) = check_expiries(&data).await {
+					tracing::error!(error = ?error, "could not check the expired verifications");
+				}
+			}
+		})?;
+
+	Ok(handle)
+}
@@ -0,0 +1,75 @@
+//! The kinds of members, students are the only ones to belong to a class
+
+use crate::database::models::Guild;
+use anyhow::anyhow;
+use std::{fmt, str::FromStr};
+
+/// What a verified member is in the school
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub(crate) enum MemberKind {
+	/// Belongs to a level and a class
+	#[name = "student"]
+	Student,
+	/// Gets the teacher role of the guild
+	#[name = "teacher"]
+	Teacher,
+	/// Gets the staff role of the guild
+	#[name = "staff"]
+	Staff,
+}
+
+impl MemberKind {
+	/// Every kind, in the order they are shown to members
+	pub(crate) const ALL: [Self; 3] = [Self::Student, Self::Teacher, Self::Staff];
+
+	/// The name stored in the database
+	pub(crate) const fn as_str(self) -> &'static str {
+		match self {
+			Self::Student => "student",
+			Self::Teacher => "teacher",
+			Self::Staff => "staff",
+		}
+	}
+
+	/// The translation key of the kind name
+	pub(crate) const fn translation_key(self) -> &'static str {
+		match self {
+			Self::Student => "member-kind-student",
+			Self::Teacher => "member-kind-teacher",
+			Self::Staff => "member-kind-staff",
+		}
+	}
+
+	/// Whether members of this kind choose a level and a class
+	pub(crate) const fn has_class(self) -> bool {
+		matches!(self, Self::Student)
+	}
+
+	/// The role of the guild given to members of this kind, students get their level and class roles
+	pub(crate) const fn role_id(self, guild: &Guild) -> Option<u64> {
+		match self {
+			Self::Student => None,
+			Self::Teacher => guild.teacher_role_id,
+			Self::Staff => guild.staff_role_id,
+		}
+	}
+}
+
+impl fmt::Display for MemberKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+impl FromStr for MemberKind {
+	type Err = anyhow::Error;
+
+	fn from_str(kind: &str) -> Result<Self, Self::Err> {
+		match kind {
+			"student" => Ok(Self::Student),
+			"teacher" => Ok(Self::Teacher),
+			"staff" => Ok(Self::Staff),
+			_ => Err(anyhow!("unknown member kind `{kind}`")),
+		}
+	}
+}
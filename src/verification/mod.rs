@@ -0,0 +1,292 @@
+//! Apply a verification to a guild member, on `Discord` and in the database
+//!
+//! Used outside of any interaction context, e.g. by the `OAuth2` callback that may run after a restart
+
+use crate::{
+	auth::UserMetadata,
+	database::{
+		models::{Class, Guild, GuildEmailRule, Level, NewVerifiedMember},
+		prelude::*,
+		schema, DatabasePooledConnection,
+	},
+	states::InteractionError,
+};
+use anyhow::anyhow;
+use chrono::Utc;
+use poise::serenity_prelude::{
+	self as serenity, ErrorResponse, GuildId, Http, HttpError, RoleId, UserId,
+};
+
+pub(crate) mod class_change;
+pub(crate) mod duplicates;
+pub(crate) mod eligibility;
+pub(crate) mod email_rules;
+pub(crate) mod expiry;
+pub(crate) mod member_kind;
+pub(crate) mod offline_access;
+pub(crate) mod reconcile;
+pub(crate) mod role_policy;
+pub(crate) mod trust;
+pub(crate) mod unlink;
+
+use member_kind::MemberKind;
+
+/// The `Discord` JSON error code returned when a role does not exist anymore
+///
+/// <https://discord.com/developers/docs/topics/opcodes-and-status-codes#json-json-error-codes>
+const UNKNOWN_ROLE_ERROR_CODE: isize = 10011;
+
+/// The `Discord` JSON error code returned when a member left the guild
+const UNKNOWN_MEMBER_ERROR_CODE: isize = 10007;
+
+/// Whether the error means that the member left the guild
+pub(crate) const fn is_unknown_member(error: &serenity::Error) -> bool {
+	matches!(
+		error,
+		serenity::Error::Http(HttpError::UnsuccessfulRequest(ErrorResponse { error, .. }))
+			if error.code == UNKNOWN_MEMBER_ERROR_CODE
+	)
+}
+
+/// Whether the error means that the role was deleted on `Discord`
+pub(crate) const fn is_unknown_role(error: &serenity::Error) -> bool {
+	matches!(
+		error,
+		serenity::Error::Http(HttpError::UnsuccessfulRequest(ErrorResponse { error, .. }))
+			if error.code == UNKNOWN_ROLE_ERROR_CODE
+	) || matches!(
+		error,
+		serenity::Error::Model(serenity::ModelError::RoleNotFound)
+	)
+}
+
+/// Replace a role by another one for the given members, e.g. after the role of a class changed
+///
+/// Members that left the guild and old roles that were deleted are skipped
+pub(crate) async fn swap_role(
+	http: &Http,
+	guild_id: GuildId,
+	user_ids: &[UserId],
+	(old_role, new_role): (Option<RoleId>, Option<RoleId>),
+) -> Result<(), serenity::Error> {
+	for user_id in user_ids {
+		if let Some(new_role) = new_role {
+			match http
+				.add_member_role(guild_id, *user_id, new_role, None)
+				.await
+			{
+				Ok(()) => {}
+				Err(error) if is_unknown_member(&error) => continue,
+				Err(error) => return Err(error),
+			}
+		}
+
+		if let Some(old_role) = old_role {
+			match http
+				.remove_member_role(guild_id, *user_id, old_role, None)
+				.await
+			{
+				Ok(()) => {}
+				Err(error) if is_unknown_member(&error) || is_unknown_role(&error) => {}
+				Err(error) => return Err(error),
+			}
+		}
+	}
+
+	Ok(())
+}
+
+/// Where the verified role given to a member comes from
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum VerifiedRole {
+	/// The verified role of the guild
+	Guild(RoleId),
+	/// The role of the email rule that the member matched
+	EmailRule {
+		/// The id of the matched [`GuildEmailRule`]
+		rule_id: i32,
+		/// The role of the rule
+		role_id: RoleId,
+	},
+}
+
+impl VerifiedRole {
+	/// The verified role of the matched rule, or the one of the guild
+	pub(crate) fn new(guild_role_id: u64, rule: &GuildEmailRule) -> Self {
+		rule.role_id.map_or_else(
+			|| Self::Guild(RoleId::new(guild_role_id)),
+			|role_id| Self::EmailRule {
+				rule_id: rule.id,
+				role_id: RoleId::new(role_id),
+			},
+		)
+	}
+
+	/// The role to give
+	pub(crate) const fn role_id(self) -> RoleId {
+		match self {
+			Self::Guild(role_id) | Self::EmailRule { role_id, .. } => role_id,
+		}
+	}
+}
+
+/// Apply the changes to the user, updating the database and the Discord roles
+///
+/// Students get the roles of their level and class, the other kinds get the role of their kind.
+/// `verified_by` is the admin who verified the member by hand, if any
+#[allow(clippy::too_many_arguments, clippy::too_many_lines)]
+pub(crate) async fn apply_changes(
+	http: &Http,
+	mut connection: &mut DatabasePooledConnection,
+	(guild_id, user_id): (GuildId, UserId),
+	user_data: &UserMetadata,
+	verified_member_id: i32,
+	verified_role: VerifiedRole,
+	member_kind: MemberKind,
+	class_id: Option<i32>,
+	verified_by: Option<UserId>,
+) -> Result<(), InteractionError> {
+	// Get Discord roles ids
+	let (class_roles, kind_role) = if member_kind.has_class() {
+		let class_id = class_id.ok_or_else(|| anyhow!("A student must belong to a class"))?;
+
+		let (level_id, level_role_id, class_role_id) = Class::with_id(class_id)
+			.inner_join(schema::levels::table)
+			.select((
+				schema::levels::id,
+				schema::levels::role_id,
+				schema::classes::role_id,
+			))
+			.first::<(i32, u64, u64)>(&mut connection)
+			.await?;
+
+		(
+			Some((
+				class_id,
+				level_id,
+				RoleId::new(level_role_id),
+				RoleId::new(class_role_id),
+			)),
+			None,
+		)
+	} else {
+		let guild = Guild::with_id(guild_id)
+			.first::<Guild>(&mut connection)
+			.await?;
+
+		let kind_role_id = member_kind
+			.role_id(&guild)
+			.ok_or_else(|| anyhow!("The {member_kind} role has not been setup yet"))?;
+
+		(None, Some(RoleId::new(kind_role_id)))
+	};
+
+	// Update Discord roles for new verified member
+	match http
+		.add_member_role(guild_id, user_id, verified_role.role_id(), None)
+		.await
+	{
+		Ok(()) => {}
+		Err(error) if is_unknown_role(&error) => {
+			match verified_role {
+				VerifiedRole::Guild(_) => {
+					diesel::update(Guild::with_id(guild_id))
+						.set(schema::guilds::verified_role_id.eq::<Option<u64>>(None))
+						.execute(&mut connection)
+						.await?;
+				}
+				// The rule falls back to the guild verified role
+				VerifiedRole::EmailRule { rule_id, .. } => {
+					diesel::update(GuildEmailRule::with_id(rule_id))
+						.set(schema::guild_email_rules::role_id.eq::<Option<u64>>(None))
+						.execute(&mut connection)
+						.await?;
+				}
+			}
+
+			return Err(anyhow!("Verified role was deleted").into());
+		}
+		Err(error) => return Err(error.into()),
+	}
+
+	if let Some(kind_role) = kind_role {
+		match http
+			.add_member_role(guild_id, user_id, kind_role, None)
+			.await
+		{
+			Ok(()) => {}
+			Err(error) if is_unknown_role(&error) => {
+				let update = diesel::update(Guild::with_id(guild_id));
+				match member_kind {
+					MemberKind::Teacher => {
+						update
+							.set(schema::guilds::teacher_role_id.eq::<Option<u64>>(None))
+							.execute(&mut connection)
+							.await?
+					}
+					MemberKind::Staff => {
+						update
+							.set(schema::guilds::staff_role_id.eq::<Option<u64>>(None))
+							.execute(&mut connection)
+							.await?
+					}
+					MemberKind::Student => unreachable!("students do not have a kind role"),
+				};
+
+				return Err(anyhow!("The {member_kind} role was deleted").into());
+			}
+			Err(error) => return Err(error.into()),
+		}
+	}
+
+	if let Some((class_id, level_id, level_role, class_role)) = class_roles {
+		match http
+			.add_member_role(guild_id, user_id, level_role, None)
+			.await
+		{
+			Ok(()) => {}
+			Err(error) if is_unknown_role(&error) => {
+				diesel::update(Level::with_id(level_id))
+					.set(schema::levels::broken.eq(true))
+					.execute(&mut connection)
+					.await?;
+
+				return Err(anyhow!("Level role was deleted").into());
+			}
+			Err(error) => return Err(error.into()),
+		}
+		match http
+			.add_member_role(guild_id, user_id, class_role, None)
+			.await
+		{
+			Ok(()) => {}
+			Err(error) if is_unknown_role(&error) => {
+				diesel::update(Class::with_id(class_id))
+					.set(schema::classes::broken.eq(true))
+					.execute(&mut connection)
+					.await?;
+
+				return Err(anyhow!("Class role was deleted").into());
+			}
+			Err(error) => return Err(error.into()),
+		}
+	}
+
+	let new_verified_member = NewVerifiedMember {
+		member_id: verified_member_id,
+		first_name: &user_data.first_name,
+		last_name: &user_data.last_name,
+		mail: &user_data.mail,
+		member_kind: member_kind.as_str(),
+		class_id: class_roles.map(|(class_id, ..)| class_id),
+		verified_at: Utc::now().naive_utc(),
+		verified_by: verified_by.map(UserId::get),
+	};
+
+	new_verified_member
+		.insert()
+		.execute(&mut connection)
+		.await?;
+
+	Ok(())
+}
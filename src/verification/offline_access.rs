@@ -0,0 +1,176 @@
+//! Notice the accounts deleted or suspended by their organization with the refresh tokens of the members
+//!
+//! Guilds opt in, the members are then asked for offline access when they verify themselves
+
+use crate::{
+	audit::{self, AuditEntry, AuditKind},
+	auth::RefreshOutcome,
+	constants,
+	database::{
+		prelude::*,
+		schema::{guilds, members, verified_members},
+		DatabasePooledConnection,
+	},
+	states::{ArcData, Data},
+	verification::unlink::unlink,
+};
+use poise::serenity_prelude::{GuildId, UserId};
+use secrecy::{ExposeSecret, SecretString};
+use tokio::task::{self, JoinHandle};
+
+/// Store the refresh token of a verified member, encrypted
+///
+/// Nothing is stored without a token or without an encryption key
+pub(crate) async fn store_refresh_token(
+	data: &Data,
+	connection: &mut DatabasePooledConnection,
+	member_id: i32,
+	(refresh_token, token_provider): (Option<&SecretString>, &str),
+) -> anyhow::Result<()> {
+	let (Some(refresh_token), Some(cipher)) = (refresh_token, &data.config.token_cipher) else {
+		return Ok(());
+	};
+
+	diesel::update(verified_members::table.find(member_id))
+		.set((
+			verified_members::refresh_token.eq(cipher.seal(refresh_token.expose_secret())?),
+			verified_members::token_provider.eq(token_provider),
+		))
+		.execute(connection)
+		.await?;
+
+	Ok(())
+}
+
+/// Revoke a stored refresh token so that the provider forgets the grant
+///
+/// Failures are only logged, the token is deleted with the verification anyway
+pub(crate) async fn revoke_refresh_token(
+	data: &Data,
+	(refresh_token, token_provider): (Option<Vec<u8>>, Option<String>),
+) {
+	let (Some(refresh_token), Some(token_provider), Some(cipher)) =
+		(refresh_token, token_provider, &data.config.token_cipher)
+	else {
+		return;
+	};
+
+	let Some(provider) = data.auth.get(&token_provider) else {
+		tracing::warn!("the identity provider `{token_provider}` is not available anymore");
+
+		return;
+	};
+
+	let result = match cipher.open(&refresh_token) {
+		Ok(refresh_token) => provider.revoke(refresh_token).await.map_err(Into::into),
+		Err(error) => Err(error),
+	};
+	if let Err(error) = result {
+		tracing::warn!(error = ?error, "could not revoke a refresh token");
+	}
+}
+
+/// Refresh the token of every member of the guilds with offline access, and unlink the members whose grant was revoked
+pub(crate) async fn check_accounts(data: &Data) -> anyhow::Result<()> {
+	let Some(cipher) = &data.config.token_cipher else {
+		return Ok(());
+	};
+
+	let mut connection = data.database.get().await?;
+
+	let tokens = verified_members::table
+		.inner_join(members::table.inner_join(guilds::table))
+		.filter(guilds::offline_access.eq(true))
+		.filter(verified_members::refresh_token.is_not_null())
+		.filter(verified_members::token_provider.is_not_null())
+		.select((
+			verified_members::member_id,
+			members::guild_id,
+			members::discord_id,
+			verified_members::refresh_token.assume_not_null(),
+			verified_members::token_provider.assume_not_null(),
+		))
+		.load::<(i32, u64, u64, Vec<u8>, String)>(&mut connection)
+		.await?;
+
+	for (member_id, guild_id, discord_id, refresh_token, token_provider) in tokens {
+		let Some(provider) = data.auth.get(&token_provider) else {
+			continue;
+		};
+
+		let outcome = match cipher.open(&refresh_token) {
+			Ok(refresh_token) => provider.refresh(&refresh_token).await.map_err(Into::into),
+			Err(error) => Err(error),
+		};
+
+		match outcome {
+			Ok(RefreshOutcome::Valid(None)) => {}
+			// Some providers rotate the refresh tokens
+			Ok(RefreshOutcome::Valid(Some(refresh_token))) => {
+				if let Err(error) = store_refresh_token(
+					data,
+					&mut connection,
+					member_id,
+					(Some(&refresh_token), &token_provider),
+				)
+				.await
+				{
+					tracing::error!(error = ?error, user_id = discord_id, "could not store a rotated refresh token");
+				}
+			}
+			Ok(RefreshOutcome::Revoked) => {
+				let (guild_id, user_id) = (GuildId::new(guild_id), UserId::new(discord_id));
+
+				// There is nothing left to revoke
+				if let Err(error) = diesel::update(verified_members::table.find(member_id))
+					.set(verified_members::refresh_token.eq::<Option<Vec<u8>>>(None))
+					.execute(&mut connection)
+					.await
+				{
+					tracing::error!(error = ?error, user_id = discord_id, "could not forget a revoked refresh token");
+
+					continue;
+				}
+
+				if let Err(error) = unlink(data, &mut connection, (guild_id, user_id), None).await {
+					tracing::error!(error = ?error, user_id = discord_id, "could not unlink a deactivated account");
+
+					continue;
+				}
+
+				audit::record(
+					data,
+					guild_id,
+					AuditEntry::new(AuditKind::AccountDeactivated)
+						.target(user_id)
+						.details(token_provider),
+				)
+				.await;
+			}
+			Err(error) => {
+				tracing::warn!(error = ?error, user_id = discord_id, "could not refresh a token");
+			}
+		}
+	}
+
+	Ok(())
+}
+
+/// Check the accounts periodically, in the background
+pub(crate) fn spawn_account_checker(data: ArcData) -> anyhow::Result<JoinHandle<()>> {
+	let handle = task::Builder::new()
+		.name("Account Checker")
+		.spawn(async move {
+			let mut interval = tokio::time::interval(constants::offline_access::REFRESH_INTERVAL);
+
+			loop {
+				interval.tick().await;
+
+				if let Err(error) = check_accounts(&data).await {
+					tracing::error!(error = ?error, "could not check the accounts");
+				}
+			}
+		})?;
+
+	Ok(handle)
+}
@@ -0,0 +1,332 @@
+//! Bring the roles of the guild members back in line with the database
+//!
+//! Roles removed by hand or deleted on `Discord` are otherwise only noticed when a member verifies
+
+use crate::{
+	database::{
+		models::{Guild, GuildEmailRule},
+		prelude::*,
+		schema::{
+			classes, groups, groups_of_verified_members, guild_email_rules, levels, members,
+			verified_members,
+		},
+		DatabasePooledConnection,
+	},
+	states::ArcData,
+	verification::{
+		email_rules::VerifiedRoles, is_unknown_member, is_unknown_role, member_kind::MemberKind,
+	},
+};
+use poise::serenity_prelude::{GuildId, Http, RoleId, UserId};
+use std::collections::{HashMap, HashSet};
+use tokio::task::{self, JoinHandle};
+
+/// What a role that does not exist anymore on `Discord` belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OrphanKind {
+	/// A level
+	Level,
+	/// A class
+	Class,
+	/// A group
+	Group,
+	/// An email rule, named by its pattern
+	EmailRule,
+	/// The verified, teacher or staff role of the guild
+	GuildRole,
+}
+
+impl OrphanKind {
+	/// The translation key describing the orphan
+	pub(crate) const fn translation_key(self) -> &'static str {
+		match self {
+			Self::Level => "reconcile-orphan-level",
+			Self::Class => "reconcile-orphan-class",
+			Self::Group => "reconcile-orphan-group",
+			Self::EmailRule => "reconcile-orphan-email-rule",
+			Self::GuildRole => "reconcile-orphan-guild-role",
+		}
+	}
+}
+
+/// What was done by a reconciliation
+#[derive(Debug, Default)]
+pub(crate) struct Report {
+	/// The number of missing roles given back
+	pub(crate) given: usize,
+	/// The number of roles taken from members that should not have them
+	pub(crate) taken: usize,
+	/// What still points to a role that was deleted, to be fixed by an admin
+	pub(crate) orphans: Vec<(OrphanKind, String)>,
+}
+
+/// Give the verified members the roles they are missing and take the managed roles from the others
+///
+/// Roles that do not exist anymore are reported instead of given, their levels, classes and groups are marked as broken
+#[allow(clippy::too_many_lines)]
+pub(crate) async fn reconcile(
+	http: &Http,
+	connection: &mut DatabasePooledConnection,
+	guild_id: GuildId,
+) -> anyhow::Result<Report> {
+	let mut report = Report::default();
+
+	let Some(guild) = Guild::with_id(guild_id)
+		.first::<Guild>(connection)
+		.await
+		.optional()?
+	else {
+		return Ok(report);
+	};
+
+	let existing_roles = guild_id.roles(http).await?;
+	let mut managed_roles = HashSet::new();
+	// Whether the role exists, orphans are reported
+	let mut check = |kind: OrphanKind, name: &str, role_id: u64| {
+		let role_id = RoleId::new(role_id);
+		if existing_roles.contains_key(&role_id) {
+			managed_roles.insert(role_id);
+			true
+		} else {
+			report.orphans.push((kind, name.to_owned()));
+			false
+		}
+	};
+
+	for (kind, role_id) in [
+		("verified", guild.verified_role_id),
+		(MemberKind::Teacher.as_str(), guild.teacher_role_id),
+		(MemberKind::Staff.as_str(), guild.staff_role_id),
+	] {
+		if let Some(role_id) = role_id {
+			check(OrphanKind::GuildRole, kind, role_id);
+		}
+	}
+
+	let rules = GuildEmailRule::all_from_guild(guild_id)
+		.order_by(guild_email_rules::id)
+		.load::<GuildEmailRule>(connection)
+		.await?;
+	for rule in &rules {
+		if let Some(role_id) = rule.role_id {
+			check(OrphanKind::EmailRule, &rule.pattern, role_id);
+		}
+	}
+
+	let mut broken_levels = Vec::new();
+	for (level_id, name, role_id) in levels::table
+		.filter(levels::guild_id.eq(guild_id.get()))
+		.select((levels::id, levels::name, levels::role_id))
+		.load::<(i32, String, u64)>(connection)
+		.await?
+	{
+		if !check(OrphanKind::Level, &name, role_id) {
+			broken_levels.push(level_id);
+		}
+	}
+
+	let mut class_roles = HashMap::new();
+	let mut broken_classes = Vec::new();
+	for (class_id, name, class_role_id, level_role_id) in classes::table
+		.inner_join(levels::table)
+		.filter(classes::guild_id.eq(guild_id.get()))
+		.select((
+			classes::id,
+			classes::name,
+			classes::role_id,
+			levels::role_id,
+		))
+		.load::<(i32, String, u64, u64)>(connection)
+		.await?
+	{
+		if !check(OrphanKind::Class, &name, class_role_id) {
+			broken_classes.push(class_id);
+		}
+		class_roles.insert(
+			class_id,
+			[RoleId::new(level_role_id), RoleId::new(class_role_id)],
+		);
+	}
+
+	let mut broken_groups = Vec::new();
+	for (group_id, name, role_id) in groups::table
+		.filter(groups::guild_id.eq(guild_id.get()))
+		.select((groups::id, groups::name, groups::role_id))
+		.load::<(i32, String, u64)>(connection)
+		.await?
+	{
+		if !check(OrphanKind::Group, &name, role_id) {
+			broken_groups.push(group_id);
+		}
+	}
+
+	diesel::update(levels::table.filter(levels::id.eq_any(&broken_levels)))
+		.set(levels::broken.eq(true))
+		.execute(connection)
+		.await?;
+	diesel::update(classes::table.filter(classes::id.eq_any(&broken_classes)))
+		.set(classes::broken.eq(true))
+		.execute(connection)
+		.await?;
+	diesel::update(groups::table.filter(groups::id.eq_any(&broken_groups)))
+		.set(groups::broken.eq(true))
+		.execute(connection)
+		.await?;
+
+	let verified_roles = VerifiedRoles::new(&rules, guild.verified_role_id);
+
+	// The roles each verified member should have
+	let mut expected_roles = HashMap::<UserId, Vec<RoleId>>::new();
+	let mut users_of_verified_members = HashMap::new();
+
+	for (member_id, discord_id, mail, member_kind, class_id) in verified_members::table
+		.inner_join(members::table)
+		.filter(members::guild_id.eq(guild_id.get()))
+		.select((
+			verified_members::member_id,
+			members::discord_id,
+			verified_members::mail,
+			verified_members::member_kind,
+			verified_members::class_id,
+		))
+		.load::<(i32, u64, String, String, Option<i32>)>(connection)
+		.await?
+	{
+		let user_id = UserId::new(discord_id);
+		users_of_verified_members.insert(member_id, user_id);
+		let member_roles = expected_roles.entry(user_id).or_default();
+
+		member_roles.extend(verified_roles.of(&mail).map(RoleId::new));
+
+		match member_kind.parse::<MemberKind>()? {
+			MemberKind::Student => {
+				if let Some(class_roles) = class_id.and_then(|class_id| class_roles.get(&class_id))
+				{
+					member_roles.extend(class_roles);
+				}
+			}
+			member_kind => member_roles.extend(member_kind.role_id(&guild).map(RoleId::new)),
+		}
+	}
+
+	for (member_id, role_id) in groups_of_verified_members::table
+		.inner_join(groups::table)
+		.filter(groups::guild_id.eq(guild_id.get()))
+		.select((
+			groups_of_verified_members::verified_member_id,
+			groups::role_id,
+		))
+		.load::<(i32, u64)>(connection)
+		.await?
+	{
+		if let Some(user_id) = users_of_verified_members.get(&member_id) {
+			expected_roles
+				.entry(*user_id)
+				.or_default()
+				.push(RoleId::new(role_id));
+		}
+	}
+
+	let mut last_member_id = None;
+
+	loop {
+		let members = guild_id.members(http, None, last_member_id).await?;
+		let len = members.len();
+
+		if let Some(member) = members.last() {
+			last_member_id = Some(member.user.id);
+		}
+
+		for member in members {
+			if member.user.bot {
+				continue;
+			}
+
+			let expected = expected_roles
+				.get(&member.user.id)
+				.map(Vec::as_slice)
+				.unwrap_or_default();
+
+			for role_id in expected {
+				if !managed_roles.contains(role_id) || member.roles.contains(role_id) {
+					continue;
+				}
+
+				match http
+					.add_member_role(guild_id, member.user.id, *role_id, None)
+					.await
+				{
+					Ok(()) => report.given += 1,
+					Err(error) if is_unknown_member(&error) || is_unknown_role(&error) => {}
+					Err(error) => return Err(error.into()),
+				}
+			}
+
+			for role_id in &member.roles {
+				if !managed_roles.contains(role_id) || expected.contains(role_id) {
+					continue;
+				}
+
+				match http
+					.remove_member_role(guild_id, member.user.id, *role_id, None)
+					.await
+				{
+					Ok(()) => report.taken += 1,
+					Err(error) if is_unknown_member(&error) || is_unknown_role(&error) => {}
+					Err(error) => return Err(error.into()),
+				}
+			}
+		}
+
+		if len < 1000 {
+			break;
+		}
+	}
+
+	Ok(report)
+}
+
+/// Reconcile the guilds one after the other, in the background
+pub(crate) fn spawn_reconciliation(
+	data: ArcData,
+	guild_ids: Vec<GuildId>,
+) -> anyhow::Result<JoinHandle<()>> {
+	let handle = task::Builder::new()
+		.name("Reconciliation")
+		.spawn(async move {
+			for guild_id in guild_ids {
+				let result = async {
+					reconcile(&data.http, &mut data.database.get().await?, guild_id).await
+				}
+				.await;
+
+				match result {
+					Ok(report) => {
+						tracing::info!(
+							guild_id = guild_id.get(),
+							given = report.given,
+							taken = report.taken,
+							"reconciled the roles"
+						);
+
+						for (kind, name) in report.orphans {
+							tracing::warn!(
+								guild_id = guild_id.get(),
+								kind = ?kind,
+								"`{name}` has a role that does not exist anymore"
+							);
+						}
+					}
+					Err(error) => {
+						tracing::error!(
+							guild_id = guild_id.get(),
+							error = ?error,
+							"could not reconcile the roles"
+						);
+					}
+				}
+			}
+		})?;
+
+	Ok(handle)
+}
@@ -0,0 +1,58 @@
+//! What to do when a verified member loses the verified role by hand
+
+use anyhow::anyhow;
+use std::{fmt, str::FromStr};
+
+/// How the bot reacts when the verified role is removed from a verified member
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, poise::ChoiceParameter)]
+pub(crate) enum VerifiedRolePolicy {
+	/// Leave the member as they are
+	#[default]
+	#[name = "ignore"]
+	Ignore,
+	/// Forget the verification of the member, as if they logged out
+	#[name = "logout"]
+	Logout,
+	/// Give the verified role back
+	#[name = "reapply"]
+	Reapply,
+}
+
+impl VerifiedRolePolicy {
+	/// The name stored in the database
+	pub(crate) const fn as_str(self) -> &'static str {
+		match self {
+			Self::Ignore => "ignore",
+			Self::Logout => "logout",
+			Self::Reapply => "reapply",
+		}
+	}
+
+	/// The translation key of what the bot does
+	pub(crate) const fn translation_key(self) -> &'static str {
+		match self {
+			Self::Ignore => "verified-role-policy-ignore",
+			Self::Logout => "verified-role-policy-logout",
+			Self::Reapply => "verified-role-policy-reapply",
+		}
+	}
+}
+
+impl fmt::Display for VerifiedRolePolicy {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+impl FromStr for VerifiedRolePolicy {
+	type Err = anyhow::Error;
+
+	fn from_str(policy: &str) -> Result<Self, Self::Err> {
+		match policy {
+			"ignore" => Ok(Self::Ignore),
+			"logout" => Ok(Self::Logout),
+			"reapply" => Ok(Self::Reapply),
+			_ => Err(anyhow!("unknown verified role policy `{policy}`")),
+		}
+	}
+}
@@ -0,0 +1,184 @@
+//! Reuse a verification in the other guilds of a trust group
+//!
+//! The account is proven once, the identity is then linked to the verification of each guild where the member consented
+
+use crate::{
+	audit::{self, AuditEntry, AuditKind},
+	auth::UserMetadata,
+	database::{
+		models::{Guild, Identity, Member, NewIdentity},
+		prelude::*,
+		schema::{guilds, identities, members, verified_members},
+		DatabasePooledConnection,
+	},
+	states::{Data, InteractionError},
+	verification::{
+		apply_changes,
+		duplicates::flag_duplicate,
+		eligibility::{check_eligibility, Eligibility, Refusal},
+	},
+};
+use chrono::{TimeDelta, Utc};
+use diesel::dsl::{self, insert_into, DuplicatedKeys};
+use poise::serenity_prelude::{GuildId, UserId};
+
+/// Remember the account of a verified member for the other guilds of the trust group, and link the verification to it
+///
+/// Nothing is remembered when the guild is not part of a trust group
+pub(crate) async fn share_identity(
+	connection: &mut DatabasePooledConnection,
+	(guild_id, user_id): (GuildId, UserId),
+	member_id: i32,
+	user_data: &UserMetadata,
+	provider: &str,
+) -> Result<(), DieselError> {
+	let Some(trust_group_id) = Guild::with_id(guild_id)
+		.select(guilds::trust_group_id)
+		.first::<Option<i32>>(connection)
+		.await?
+	else {
+		return Ok(());
+	};
+
+	let new_identity = NewIdentity {
+		trust_group_id,
+		discord_id: user_id.get(),
+		mail: &user_data.mail,
+		first_name: &user_data.first_name,
+		last_name: &user_data.last_name,
+		domain: user_data.domain.as_deref(),
+		provider,
+		verified_at: Utc::now().naive_utc(),
+	};
+
+	// The identity keeps its id, so that the verifications linked to it stay linked
+	insert_into(identities::table)
+		.values(&new_identity)
+		.on_conflict(DuplicatedKeys)
+		.do_update()
+		.set(&new_identity)
+		.execute(connection)
+		.await?;
+
+	let identity_id = Identity::with_ids(user_id, trust_group_id)
+		.select(identities::id)
+		.first::<i32>(connection)
+		.await?;
+
+	diesel::update(verified_members::table.find(member_id))
+		.set(verified_members::identity_id.eq(identity_id))
+		.execute(connection)
+		.await?;
+
+	Ok(())
+}
+
+/// The account a user proved in the trust group of a guild, if recent enough for its re-verification delay
+///
+/// The identity is only offered while a verification of the trust group still relies on it
+pub(crate) async fn shared_identity(
+	connection: &mut DatabasePooledConnection,
+	guild: &Guild,
+	user_id: UserId,
+) -> Result<Option<Identity>, DieselError> {
+	let Some(trust_group_id) = guild.trust_group_id else {
+		return Ok(None);
+	};
+
+	let Some(identity) = Identity::with_ids(user_id, trust_group_id)
+		.first::<Identity>(connection)
+		.await
+		.optional()?
+	else {
+		return Ok(None);
+	};
+
+	if !dsl::select(dsl::exists(
+		verified_members::table.filter(verified_members::identity_id.eq(identity.id)),
+	))
+	.get_result::<bool>(connection)
+	.await?
+	{
+		return Ok(None);
+	}
+
+	Ok(Some(identity).filter(|identity| {
+		guild.reverification_days.is_none_or(|days| {
+			identity.verified_at + TimeDelta::days(days.into()) > Utc::now().naive_utc()
+		})
+	}))
+}
+
+/// Verify a member with the account they proved in another guild of the trust group
+///
+/// The rules of the guild apply as if the member logged in, only the provider is not asked again
+pub(crate) async fn verify_with_identity(
+	data: &Data,
+	connection: &mut DatabasePooledConnection,
+	(guild_id, user_id): (GuildId, UserId),
+	identity: Identity,
+	(member_kind, class_id): (&str, Option<i32>),
+) -> Result<Result<(), Refusal>, InteractionError> {
+	let mut user_data = UserMetadata {
+		mail: identity.mail,
+		first_name: identity.first_name,
+		last_name: identity.last_name,
+		domain: identity.domain,
+		refresh_token: None,
+	};
+
+	let member_id = Member::with_ids(user_id, guild_id)
+		.select(members::id)
+		.first::<i32>(connection)
+		.await?;
+
+	let Eligibility {
+		verified_role,
+		member_kind,
+		class_id,
+		duplicates,
+	} = match check_eligibility(
+		connection,
+		(guild_id, member_id),
+		&mut user_data,
+		(member_kind, class_id),
+	)
+	.await?
+	{
+		Ok(eligibility) => eligibility,
+		Err(refusal) => return Ok(Err(refusal)),
+	};
+
+	apply_changes(
+		&data.http,
+		connection,
+		(guild_id, user_id),
+		&user_data,
+		member_id,
+		verified_role,
+		member_kind,
+		class_id,
+		None,
+	)
+	.await?;
+
+	diesel::update(verified_members::table.find(member_id))
+		.set(verified_members::identity_id.eq(identity.id))
+		.execute(connection)
+		.await?;
+
+	if !duplicates.is_empty() {
+		flag_duplicate(data, (guild_id, user_id), &user_data.mail, &duplicates).await;
+	}
+
+	audit::record(
+		data,
+		guild_id,
+		AuditEntry::new(AuditKind::VerificationShared)
+			.target(user_id)
+			.details(format!("{} ({member_kind})", user_data.mail)),
+	)
+	.await;
+
+	Ok(Ok(()))
+}
@@ -0,0 +1,191 @@
+//! Forget the verification of a member and take back the roles it gave
+//!
+//! Shared by the logout button, the forced logout, the members leaving, the removals of classes and levels and the automatic unverifications
+
+use crate::{
+	audit::{self, AuditEntry, AuditKind},
+	database::{
+		models::{Guild, GuildEmailRule, VerifiedMember},
+		prelude::*,
+		schema::{
+			class_change_requests, classes, groups, groups_of_verified_members, guild_email_rules,
+			identities, levels, mail_claims, verified_members,
+		},
+		DatabasePooledConnection,
+	},
+	states::{Data, InteractionError},
+	verification::{is_unknown_member, is_unknown_role, offline_access::revoke_refresh_token},
+};
+use diesel_async::{scoped_futures::ScopedFutureExt, AsyncConnection, AsyncMysqlConnection};
+use poise::serenity_prelude::{GuildId, RoleId, UserId};
+use std::collections::HashSet;
+
+/// Delete the verification of a member with its groups and mail claim, revoke its refresh token and take every role managed by the bot
+///
+/// The verified, kind and email rule roles are taken along with the level, class and group roles of the member.
+/// The identity shared with the trust group is deleted too, the member must log in with their provider to share it again.
+/// Returns whether the member was verified, the deletion is recorded in the audit log with `actor` as its author.
+pub(crate) async fn unlink(
+	data: &Data,
+	connection: &mut DatabasePooledConnection,
+	(guild_id, user_id): (GuildId, UserId),
+	actor: Option<UserId>,
+) -> Result<bool, InteractionError> {
+	let Some(verified_member) = VerifiedMember::with_ids(user_id, guild_id)
+		.select(verified_members::all_columns)
+		.first::<VerifiedMember>(connection)
+		.await
+		.optional()?
+	else {
+		return Ok(false);
+	};
+	let member_id = verified_member.member_id;
+
+	let guild = Guild::with_id(guild_id).first::<Guild>(connection).await?;
+
+	let (role_ids, nb_of_groups) = managed_roles(connection, &guild, &verified_member).await?;
+
+	connection
+		.transaction::<_, DieselError, _>(|connection| {
+			async move { forget(connection, &[member_id]).await }.scope_boxed()
+		})
+		.await?;
+
+	let had_refresh_token = verified_member.refresh_token.is_some();
+	revoke_refresh_token(
+		data,
+		(
+			verified_member.refresh_token,
+			verified_member.token_provider,
+		),
+	)
+	.await;
+
+	// Only the roles the member has are taken, a member that left has none
+	let member_roles = match data.http.get_member(guild_id, user_id).await {
+		Ok(member) => member.roles.into_iter().collect::<HashSet<_>>(),
+		Err(error) if is_unknown_member(&error) => HashSet::new(),
+		Err(error) => return Err(error.into()),
+	};
+
+	let mut nb_of_roles = 0;
+	for role_id in role_ids
+		.into_iter()
+		.map(RoleId::new)
+		.collect::<HashSet<_>>()
+		.intersection(&member_roles)
+	{
+		match data
+			.http
+			.remove_member_role(guild_id, user_id, *role_id, None)
+			.await
+		{
+			Ok(()) => nb_of_roles += 1,
+			Err(error) if is_unknown_member(&error) => break,
+			Err(error) if is_unknown_role(&error) => {}
+			Err(error) => return Err(error.into()),
+		}
+	}
+
+	let mut entry = AuditEntry::new(AuditKind::DataDeleted)
+		.target(user_id)
+		.details(format!(
+			"groups: {nb_of_groups}, roles: {nb_of_roles}, refresh_token: {had_refresh_token}"
+		));
+	if let Some(actor) = actor {
+		entry = entry.actor(actor);
+	}
+	audit::record(data, guild_id, entry).await;
+
+	Ok(true)
+}
+
+/// Delete the verifications of members with their groups, mail claims, class change requests and shared identities
+///
+/// Only the database part of [`unlink`], meant to run in a transaction.
+/// The caller revokes the refresh tokens and takes the roles once the transaction is committed.
+pub(crate) async fn forget(
+	connection: &mut AsyncMysqlConnection,
+	member_ids: &[i32],
+) -> Result<(), DieselError> {
+	let identity_ids = verified_members::table
+		.filter(verified_members::member_id.eq_any(member_ids))
+		.filter(verified_members::identity_id.is_not_null())
+		.select(verified_members::identity_id.assume_not_null())
+		.load::<i32>(connection)
+		.await?;
+
+	diesel::delete(
+		groups_of_verified_members::table
+			.filter(groups_of_verified_members::verified_member_id.eq_any(member_ids)),
+	)
+	.execute(connection)
+	.await?;
+
+	diesel::delete(mail_claims::table.filter(mail_claims::member_id.eq_any(member_ids)))
+		.execute(connection)
+		.await?;
+
+	diesel::delete(
+		class_change_requests::table.filter(class_change_requests::member_id.eq_any(member_ids)),
+	)
+	.execute(connection)
+	.await?;
+
+	diesel::delete(verified_members::table.filter(verified_members::member_id.eq_any(member_ids)))
+		.execute(connection)
+		.await?;
+
+	// A logged out, expired or deactivated account must not verify the other guilds
+	diesel::delete(identities::table.filter(identities::id.eq_any(identity_ids)))
+		.execute(connection)
+		.await?;
+
+	Ok(())
+}
+
+/// The roles managed by the bot that a verified member may have, and their number of groups
+async fn managed_roles(
+	connection: &mut DatabasePooledConnection,
+	guild: &Guild,
+	verified_member: &VerifiedMember,
+) -> Result<(Vec<u64>, usize), DieselError> {
+	let mut role_ids = groups_of_verified_members::table
+		.inner_join(groups::table)
+		.filter(groups_of_verified_members::verified_member_id.eq(verified_member.member_id))
+		.select(groups::role_id)
+		.load::<u64>(connection)
+		.await?;
+	let nb_of_groups = role_ids.len();
+
+	if let Some(class_id) = verified_member.class_id {
+		let (class_role_id, level_role_id) = classes::table
+			.inner_join(levels::table)
+			.filter(classes::id.eq(class_id))
+			.select((classes::role_id, levels::role_id))
+			.first::<(u64, u64)>(connection)
+			.await?;
+
+		role_ids.push(class_role_id);
+		role_ids.push(level_role_id);
+	}
+	role_ids.extend(
+		[
+			guild.verified_role_id,
+			guild.teacher_role_id,
+			guild.staff_role_id,
+		]
+		.into_iter()
+		.flatten(),
+	);
+	role_ids.extend(
+		GuildEmailRule::all_from_guild(GuildId::new(guild.id))
+			.select(guild_email_rules::role_id)
+			.load::<Option<u64>>(connection)
+			.await?
+			.into_iter()
+			.flatten(),
+	);
+
+	Ok((role_ids, nb_of_groups))
+}
@@ -0,0 +1,256 @@
+//! Track the experience points earned by the members with their messages and in voice channels
+//!
+//! XP is kept in memory and written to the database every [`constants::xp::FLUSH_INTERVAL`]
+
+use crate::{
+	constants,
+	database::{models::XpReward, prelude::*, schema::members},
+	states::{ArcData, Data},
+};
+use poise::serenity_prelude::{ChannelId, GuildId, UserId};
+use std::{
+	collections::{hash_map::Entry, HashMap},
+	mem,
+	sync::{Mutex, PoisonError},
+	time::{Duration, Instant},
+};
+use tokio::task::{self, JoinHandle};
+
+pub(crate) mod rewards;
+
+/// The XP earned by a member since the last flush
+#[derive(Debug, Default, Clone, Copy)]
+struct PendingXp {
+	/// XP earned with messages
+	message: i32,
+	/// XP earned in voice channels
+	vocal: i32,
+}
+
+/// The in memory state of the tracker
+#[derive(Debug, Default)]
+struct XpState {
+	/// When each member last earned message XP
+	message_cooldowns: HashMap<(GuildId, UserId), Instant>,
+	/// Since when each member in a voice channel has not been credited
+	voice_sessions: HashMap<(GuildId, UserId), Instant>,
+	/// The AFK channel of each guild, where no XP is earned
+	afk_channels: HashMap<GuildId, ChannelId>,
+	/// XP not yet written to the database
+	pending: HashMap<(GuildId, UserId), PendingXp>,
+}
+
+impl XpState {
+	/// Credit the full minutes spent in voice since the session started, and restart the session for the remainder
+	fn credit_voice_session(&mut self, key: (GuildId, UserId), now: Instant) {
+		let Some(started_at) = self.voice_sessions.get_mut(&key) else {
+			return;
+		};
+
+		let minutes = now.duration_since(*started_at).as_secs() / 60;
+		if minutes == 0 {
+			return;
+		}
+
+		*started_at += Duration::from_secs(minutes * 60);
+
+		let xp = i32::try_from(minutes)
+			.unwrap_or(i32::MAX)
+			.saturating_mul(constants::xp::VOCAL_XP_PER_MINUTE);
+		let pending = self.pending.entry(key).or_default();
+		pending.vocal = pending.vocal.saturating_add(xp);
+	}
+}
+
+/// Track the XP earned by the members
+#[derive(Debug, Default)]
+pub(crate) struct XpTracker {
+	/// The state, only locked for short synchronous updates
+	state: Mutex<XpState>,
+}
+
+impl XpTracker {
+	/// Run a closure on the state
+	fn with_state<T>(&self, f: impl FnOnce(&mut XpState) -> T) -> T {
+		f(&mut self.state.lock().unwrap_or_else(PoisonError::into_inner))
+	}
+
+	/// Remember the AFK channel of a guild
+	pub(crate) fn set_afk_channel(&self, guild_id: GuildId, channel_id: Option<ChannelId>) {
+		self.with_state(|state| match channel_id {
+			Some(channel_id) => state.afk_channels.insert(guild_id, channel_id),
+			None => state.afk_channels.remove(&guild_id),
+		});
+	}
+
+	/// Give XP for a message, unless the member is on cooldown
+	pub(crate) fn on_message(&self, guild_id: GuildId, user_id: UserId) {
+		let now = Instant::now();
+
+		self.with_state(|state| {
+			let key = (guild_id, user_id);
+
+			if state
+				.message_cooldowns
+				.get(&key)
+				.is_some_and(|last| now.duration_since(*last) < constants::xp::MESSAGE_COOLDOWN)
+			{
+				return;
+			}
+
+			state.message_cooldowns.insert(key, now);
+			let pending = state.pending.entry(key).or_default();
+			pending.message = pending.message.saturating_add(constants::xp::MESSAGE_XP);
+		});
+	}
+
+	/// Start or stop the voice session of a member when they join, move or leave a voice channel
+	pub(crate) fn on_voice_state(
+		&self,
+		guild_id: GuildId,
+		user_id: UserId,
+		channel_id: Option<ChannelId>,
+	) {
+		let now = Instant::now();
+
+		self.with_state(|state| {
+			let key = (guild_id, user_id);
+
+			state.credit_voice_session(key, now);
+
+			let in_voice = channel_id
+				.is_some_and(|channel_id| state.afk_channels.get(&guild_id) != Some(&channel_id));
+
+			if in_voice {
+				state.voice_sessions.entry(key).or_insert(now);
+			} else {
+				state.voice_sessions.remove(&key);
+			}
+		});
+	}
+
+	/// Write the pending XP to the database and grant the reached rewards
+	pub(crate) async fn flush(&self, data: &Data) -> anyhow::Result<()> {
+		let now = Instant::now();
+
+		let pending = self.with_state(|state| {
+			let sessions = state.voice_sessions.keys().copied().collect::<Vec<_>>();
+			for key in sessions {
+				state.credit_voice_session(key, now);
+			}
+
+			// Cooldowns that are over are not needed anymore
+			state
+				.message_cooldowns
+				.retain(|_, last| now.duration_since(*last) < constants::xp::MESSAGE_COOLDOWN);
+
+			mem::take(&mut state.pending)
+		});
+
+		if pending.is_empty() {
+			return Ok(());
+		}
+
+		let mut pending = pending.into_iter().collect::<Vec<_>>();
+		let result = Self::write(data, &mut pending).await;
+
+		// Keep what could not be written for the next flush
+		self.with_state(|state| {
+			for (key, xp) in pending {
+				let entry = state.pending.entry(key).or_default();
+				entry.message = entry.message.saturating_add(xp.message);
+				entry.vocal = entry.vocal.saturating_add(xp.vocal);
+			}
+		});
+
+		result
+	}
+
+	/// Add the pending XP to the members, removing each entry once written
+	async fn write(
+		data: &Data,
+		pending: &mut Vec<((GuildId, UserId), PendingXp)>,
+	) -> anyhow::Result<()> {
+		let mut connection = data.database.get().await?;
+		let mut rewards_of_guilds = HashMap::<GuildId, Vec<XpReward>>::new();
+
+		while let Some(&((guild_id, user_id), xp)) = pending.last() {
+			let updated = diesel::update(
+				members::table
+					.filter(members::guild_id.eq(guild_id.get()))
+					.filter(members::discord_id.eq(user_id.get())),
+			)
+			.set((
+				members::message_xp.eq(members::message_xp + xp.message),
+				members::vocal_xp.eq(members::vocal_xp + xp.vocal),
+			))
+			.execute(&mut connection)
+			.await?;
+
+			pending.pop();
+
+			// The member is not known by the bot
+			if updated == 0 {
+				continue;
+			}
+
+			let rewards = match rewards_of_guilds.entry(guild_id) {
+				Entry::Occupied(entry) => entry.into_mut(),
+				Entry::Vacant(entry) => entry.insert(
+					XpReward::all_from_guild(guild_id)
+						.load(&mut connection)
+						.await?,
+				),
+			};
+
+			if rewards.is_empty() {
+				continue;
+			}
+
+			let new_total = members::table
+				.filter(members::guild_id.eq(guild_id.get()))
+				.filter(members::discord_id.eq(user_id.get()))
+				.select(members::message_xp + members::vocal_xp)
+				.first::<i32>(&mut connection)
+				.await?;
+			let old_total = new_total - xp.message - xp.vocal;
+
+			// The XP is already written, a failing reward must not drop the XP of the other members
+			if let Err(error) = rewards::grant_rewards(
+				data,
+				&mut connection,
+				(guild_id, user_id),
+				rewards,
+				(old_total, new_total),
+			)
+			.await
+			{
+				tracing::warn!(
+					guild_id = guild_id.get(),
+					user_id = user_id.get(),
+					error = ?error,
+					"could not grant the XP rewards"
+				);
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// Write the earned XP to the database at a regular interval
+pub(crate) fn spawn_flusher(data: ArcData) -> anyhow::Result<JoinHandle<()>> {
+	let handle = task::Builder::new().name("XP Flusher").spawn(async move {
+		let mut interval = tokio::time::interval(constants::xp::FLUSH_INTERVAL);
+
+		loop {
+			interval.tick().await;
+
+			if let Err(error) = data.xp.flush(&data).await {
+				tracing::error!(error = ?error, "could not flush the earned XP");
+			}
+		}
+	})?;
+
+	Ok(handle)
+}
@@ -0,0 +1,168 @@
+//! Grant the roles of the XP rewards once the members earned enough XP
+
+use crate::{
+	database::{
+		models::{Guild, XpReward},
+		prelude::*,
+		schema::{guilds, members, xp_rewards},
+		DatabasePooledConnection,
+	},
+	states::Data,
+	translation::Translate,
+	verification::{is_unknown_member, is_unknown_role},
+};
+use fluent::fluent_args;
+use poise::serenity_prelude::{
+	ChannelId, CreateAllowedMentions, CreateMessage, GuildId, Http, RoleId, UserId,
+};
+use std::collections::HashMap;
+
+/// The number of members fetched at once, the maximum allowed by `Discord`
+const MEMBERS_PAGE_SIZE: u64 = 1000;
+
+/// Grant the rewards that a member reached by going from `old_total` to `new_total` XP
+///
+/// The highest reached reward is announced in the level up channel of the guild, if any
+pub(crate) async fn grant_rewards(
+	data: &Data,
+	connection: &mut DatabasePooledConnection,
+	(guild_id, user_id): (GuildId, UserId),
+	rewards: &[XpReward],
+	(old_total, new_total): (i32, i32),
+) -> anyhow::Result<()> {
+	let mut highest_reward = None::<&XpReward>;
+
+	for reward in rewards
+		.iter()
+		.filter(|reward| old_total < reward.threshold && reward.threshold <= new_total)
+	{
+		match data
+			.http
+			.add_member_role(guild_id, user_id, RoleId::new(reward.role_id), None)
+			.await
+		{
+			Ok(()) => {}
+			Err(error) if is_unknown_member(&error) => return Ok(()),
+			// The role was deleted on `Discord`, forget the reward
+			Err(error) if is_unknown_role(&error) => {
+				diesel::delete(xp_rewards::table.find(reward.id))
+					.execute(connection)
+					.await?;
+
+				continue;
+			}
+			Err(error) => return Err(error.into()),
+		}
+
+		if highest_reward.is_none_or(|highest| highest.threshold < reward.threshold) {
+			highest_reward = Some(reward);
+		}
+	}
+
+	let Some(reward) = highest_reward else {
+		return Ok(());
+	};
+
+	let Some(channel_id) = Guild::with_id(guild_id)
+		.select(guilds::level_up_channel_id)
+		.first::<Option<u64>>(connection)
+		.await?
+	else {
+		return Ok(());
+	};
+
+	let content = data.translate(
+		"xp-level-up",
+		Some(fluent_args![
+			"user" => format!("<@{user_id}>"),
+			"role" => format!("<@&{}>", reward.role_id),
+			"xp" => reward.threshold,
+		]),
+	);
+
+	ChannelId::new(channel_id)
+		.send_message(
+			&data.http,
+			CreateMessage::new()
+				.content(content)
+				// Only ping the member, not everyone with the role
+				.allowed_mentions(CreateAllowedMentions::new().users([user_id])),
+		)
+		.await?;
+
+	Ok(())
+}
+
+/// Give the reward roles to the members that have enough XP and take them from the others
+///
+/// Returns the number of roles given and taken
+pub(crate) async fn backfill_rewards(
+	http: &Http,
+	connection: &mut DatabasePooledConnection,
+	guild_id: GuildId,
+) -> anyhow::Result<(usize, usize)> {
+	let rewards = XpReward::all_from_guild(guild_id)
+		.load::<XpReward>(connection)
+		.await?;
+
+	if rewards.is_empty() {
+		return Ok((0, 0));
+	}
+
+	let totals = members::table
+		.filter(members::guild_id.eq(guild_id.get()))
+		.select((members::discord_id, members::message_xp + members::vocal_xp))
+		.load::<(u64, i32)>(connection)
+		.await?
+		.into_iter()
+		.collect::<HashMap<_, _>>();
+
+	let (mut given, mut taken) = (0, 0);
+	let mut after = None;
+
+	loop {
+		let members = http
+			.get_guild_members(guild_id, Some(MEMBERS_PAGE_SIZE), after)
+			.await?;
+
+		for member in &members {
+			if member.user.bot {
+				continue;
+			}
+
+			let total = totals.get(&member.user.id.get()).copied().unwrap_or(0);
+
+			for reward in &rewards {
+				let role_id = RoleId::new(reward.role_id);
+				let has_role = member.roles.contains(&role_id);
+
+				let result = if total >= reward.threshold && !has_role {
+					given += 1;
+					http.add_member_role(guild_id, member.user.id, role_id, None)
+						.await
+				} else if total < reward.threshold && has_role {
+					taken += 1;
+					http.remove_member_role(guild_id, member.user.id, role_id, None)
+						.await
+				} else {
+					continue;
+				};
+
+				match result {
+					Ok(()) => {}
+					Err(error) if is_unknown_member(&error) || is_unknown_role(&error) => {}
+					Err(error) => return Err(error.into()),
+				}
+			}
+		}
+
+		match members.last() {
+			Some(member) if u64::try_from(members.len()) == Ok(MEMBERS_PAGE_SIZE) => {
+				after = Some(member.user.id.get());
+			}
+			_ => break,
+		}
+	}
+
+	Ok((given, taken))
+}
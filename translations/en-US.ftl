@@ -4,6 +4,12 @@ done = Done!
 continue = Continue
 cancel = Cancel
 and = and
+member-kind-student = Student
+member-kind-teacher = Teacher
+member-kind-staff = Staff
+removal-confirm-button = Confirm
+removal-cancelled = Nothing was changed.
+removal-preview-more = and { $count } more
 
 ## Commands
 
@@ -19,10 +25,31 @@ classes_add = add
     .role-description = A role to assign to this class, if not provided a role will be created
 classes_add-success = Class `{ $class }` has been created in the level `{ $level }`.
 classes_add-no-such-level = There is no such level with the name of `{ $level }` on this guild.
+classes_edit = edit
+    .description = Rename a class, change its role or move it to another level
+    .name = name
+    .name-description = Class name to edit
+    .new_name = new_name
+    .new_name-description = The new name of the class, the role is renamed too
+    .level = level
+    .level-description = The level to move the class to
+    .role = role
+    .role-description = A role to give to the members of the class instead
+classes_edit-nothing-to-do = There is nothing to change.
+classes_edit-already-exists = There is already a class `{ $class }`.
+classes_edit-role-in-use = The role { $role } is already used by a class, a level or a group.
+classes_edit-success = Class `{ $class }` has been edited.
 classes_remove = remove
     .description = Remove a class from the guild
     .name = name
     .name-description = Class name to remove
+    .move_to = move_to
+    .move_to-description = A class to move the members to, they are unverified otherwise
+classes_remove-not-found = There is no such class.
+classes_remove-no-such-target = There is no other class `{ $class }`.
+classes_remove-confirm-move = { $count } members of `{ $class }` will be moved to `{ $target }`:
+classes_remove-confirm-unverify = { $count } members of `{ $class }` will be unverified:
+classes_remove-success = Class `{ $class }` has been removed, { $count } members were affected.
 classes_list = list
     .description = List the classes of the guild
     .filter = filter
@@ -42,10 +69,25 @@ groups_add = add
     .emoji = emoji
     .emoji-description = An associated emoji to display in select menu
 groups_add-success = Group `{ $group }` has been created.
+groups_edit = edit
+    .description = Rename a group, change its emoji or its role
+    .name = name
+    .name-description = Group name to edit
+    .new_name = new_name
+    .new_name-description = The new name of the group, the role is renamed too
+    .emoji = emoji
+    .emoji-description = The new emoji of the group
+    .role = role
+    .role-description = A role to give to the members of the group instead
+groups_edit-nothing-to-do = There is nothing to change.
+groups_edit-already-exists = There is already a group `{ $group }`.
+groups_edit-role-in-use = The role { $role } is already used by a class, a level or a group.
+groups_edit-success = Group `{ $group }` has been edited.
 groups_remove = remove
     .description = Remove a group from the guild
     .name = name
     .name-description = Group name to remove
+groups_remove-not-found = There is no such group.
 groups_list = list
     .description = List the groups of the guild
     .filter = filter
@@ -59,14 +101,67 @@ levels_add = add
     .role = role
     .role-description = A role to assign to this level, if not provided a role will be created
 levels_add-success = Level `{ $level }` has been created.
+levels_edit = edit
+    .description = Rename a level or change its role
+    .name = name
+    .name-description = Level name to edit
+    .new_name = new_name
+    .new_name-description = The new name of the level, the role is renamed too
+    .role = role
+    .role-description = A role to give to the members of the level instead
+levels_edit-nothing-to-do = There is nothing to change.
+levels_edit-already-exists = There is already a level `{ $level }`.
+levels_edit-role-in-use = The role { $role } is already used by a class, a level or a group.
+levels_edit-success = Level `{ $level }` has been edited.
 levels_remove = remove
     .description = Remove a level from the guild
     .name = name
     .name-description = Level name to remove
+    .move_to = move_to
+    .move_to-description = A level to move the classes to, they are removed and their members unverified otherwise
+levels_remove-not-found = There is no such level.
+levels_remove-no-such-target = There is no other level `{ $level }`.
+levels_remove-confirm-move = The { $classes } classes of `{ $level }` and their { $count } members will be moved to `{ $target }`:
+levels_remove-confirm-delete = The { $classes } classes of `{ $level }` will be removed and their { $count } members unverified:
+levels_remove-success = Level `{ $level }` has been removed, { $classes } classes and { $count } members were affected.
 levels_list = list
     .description = List the levels of the guild
     .filter = filter
     .filter-description = Filter the levels with a name
+# Members
+members = members
+    .description = Export the verified members.
+members_export = export
+    .description = Export the verified members as a CSV or JSON file.
+    .format = format
+    .format-description = The format of the file, CSV if not provided
+    .csv-choice = csv
+    .json-choice = json
+    .level = level
+    .level-description = Only export the members of this level
+    .class = class
+    .class-description = Only export the members of this class
+    .group = group
+    .group-description = Only export the members of this group
+members_export-no-such-group = There is no group `{ $group }` in this guild.
+members_export-done = { $count } verified members exported.
+members_duplicates = duplicates
+    .description = List the Discord accounts verified with the same mail.
+members_duplicates-none = No mail is shared by several accounts.
+members_duplicates-title = { $count } mails shared by several accounts
+# Roster
+roster = roster
+    .description = Manage the school roster.
+roster_import = import
+    .description = Import a CSV roster, students are then bound to their class on login.
+    .file = file
+    .file-description = A CSV file with the email, first_name, last_name, level and class columns
+roster_import-too-large = The roster is too large.
+roster_import-not-utf8 = The roster must be encoded in UTF-8.
+roster_import-invalid = The roster is not a valid CSV file: { $error }
+roster_import-missing-column = The roster does not have a `{ $column }` column.
+roster_import-done = { $students } students imported, { $levels } levels and { $classes } classes created.
+roster_import-skipped = These lines were skipped: { $lines }
 # Setup
 setup = setup
     .description = A set of commands to setup the bot.
@@ -82,25 +177,345 @@ setup_role = role
     .description = Setup the role to apply to verified members.
     .role = role
     .role-description = Which role to give
+setup_kind = kind
+    .description = Setup the role to apply to the members of a kind.
+    .kind = kind
+    .kind-description = The kind of members
+    .student-choice = student
+    .teacher-choice = teacher
+    .staff-choice = staff
+    .role = role
+    .role-description = Which role to give, the kind is disabled if not provided
+setup_kind-student = Students get the roles of their level and class.
+setup_kind-done = Members of kind `{ $kind }` can now verify themselves.
+setup_kind-disabled = Members of kind `{ $kind }` cannot verify themselves anymore.
 setup_pattern = pattern
-    .description = Sets the pattern of the autohrized domain.
+    .description = Manage the emails allowed to verify themselves.
+setup_pattern_add = add
+    .description = Allow the emails matching a pattern to verify themselves.
+    .kind = kind
+    .kind-description = How the pattern is matched
+    .domain-choice = domain
+    .glob-choice = glob
+    .regex-choice = regex
     .pattern = pattern
-    .pattern-description = The pattern of the autohrized domain
+    .pattern-description = `school.fr`, `*.school.fr`, `*.teacher@school.fr` or a regular expression
+    .role = role
+    .role-description = A role to give instead of the verified role
+    .member_kind = member_kind
+    .member_kind-description = The kind of the matching members, they choose it if not provided
+    .student-choice = student
+    .teacher-choice = teacher
+    .staff-choice = staff
+setup_pattern_add-done = Emails matching `{ $pattern }` can now verify themselves.
+setup_pattern_add-invalid = The pattern `{ $pattern }` is invalid: { $error }
+setup_pattern_add-already-exists = The pattern `{ $pattern }` already exists.
+setup_pattern_add-too-many-patterns = You cannot add more patterns to this guild.
+setup_pattern_remove = remove
+    .description = Stop allowing the emails matching a pattern to verify themselves.
+    .pattern = pattern
+    .pattern-description = The pattern to remove
+setup_pattern_remove-done = The pattern `{ $pattern }` has been removed.
+setup_pattern_remove-not-found = There is no pattern `{ $pattern }` in this guild.
+setup_pattern_list = list
+    .description = List the patterns of the emails allowed to verify themselves.
+setup_pattern_list-title = Allowed emails
+setup_pattern_list-none = No email is allowed to verify itself yet, use `/setup pattern add`.
+setup_provider = provider
+    .description = Sets the identity provider used to verify members.
+    .provider = provider
+    .provider-description = The name of a provider configured on the bot
+setup_provider-done = Members will now verify themselves with `{ $provider }`.
+setup_provider-unknown = There is no identity provider named `{ $provider }` on this bot.
+setup_log_channel = log_channel
+    .description = Setup the channel where the audit log is posted.
+    .channel = channel
+    .channel-description = The channel for the audit log, none to stop posting it
+setup_log_channel-done = The audit log will be posted in { $channel }.
+setup_log_channel-disabled = The audit log will no longer be posted.
+setup_verified_role_policy = verified_role_policy
+    .description = Setup what to do when a verified member loses the verified role by hand.
+    .policy = policy
+    .policy-description = What to do with the member
+    .ignore-choice = ignore
+    .logout-choice = logout
+    .reapply-choice = give it back
+setup_verified_role_policy-done = When a verified member loses the verified role, the bot will now: { $policy }.
+verified-role-policy-ignore = do nothing
+verified-role-policy-logout = log them out
+verified-role-policy-reapply = give the role back
+setup_reverification = reverification
+    .description = Setup after how many days the verified members must verify themselves again.
+    .days = days
+    .days-description = The number of days a verification lasts, none for it to never expire
+setup_reverification-done = Verified members will have to verify themselves again every { $days } days.
+setup_reverification-disabled = Verifications will no longer expire.
+reverification-reminder = Your verification on **{ $guild }** expires <t:{ $expires_at }:R>. Verify yourself again to keep your roles.
+reverification-button = Verify again
+reverification-expired = Your verification on **{ $guild }** expired, your roles were taken. Use the login button of the guild to verify yourself again.
+reverification-not-verified = You are not verified on this guild anymore, use the login button of the guild instead.
+reverification-successful = You verified yourself again, your verification was renewed.
+reverification-different-mail = You must verify yourself again with the same account as before.
+setup_offline_access = offline_access
+    .description = Setup whether to keep a refresh token of the members, to notice deactivated accounts.
+    .enabled = enabled
+    .enabled-description = Whether members are asked for offline access when they verify themselves
+setup_offline_access-enabled = Members will be asked for offline access, deactivated accounts will be logged out.
+setup_offline_access-disabled = Offline access is disabled, the refresh tokens of the members were revoked.
+setup_offline_access-no-key = The bot has no key to encrypt the refresh tokens, ask its host to configure one.
+setup_duplicate_mails = duplicate_mails
+    .description = Setup what happens when another account verifies itself with a mail already verified.
+    .policy = policy
+    .policy-description = What to do with the other account
+    .block-choice = block
+    .allow-choice = allow
+    .allow_and_flag-choice = allow and flag
+setup_duplicate_mails-done = When another account verifies itself with a mail already verified, the bot will now: { $policy }.
+setup_class_change_approval = class_change_approval
+    .description = Setup whether the students changing their class wait for the approval of an admin.
+    .enabled = enabled
+    .enabled-description = Whether the class changes are posted in the log channel for an admin to approve
+setup_class_change_approval-enabled = The class changes will be posted in the log channel, an admin with the manage roles permission must approve them.
+setup_class_change_approval-disabled = The students now change their class on their own, the requests waiting for a review were dropped.
+setup_class_change_approval-no-log-channel = The requests are posted in the log channel, use `/setup log_channel` first.
+duplicate-mail-policy-block = refuse the verification
+duplicate-mail-policy-allow = verify it
+duplicate-mail-policy-flag = verify it and flag it in the audit log
+# Year
+year = year
+    .description = Configure and apply the school year rollover.
+year_next = next
+    .description = Set the level that the students of a level move to, or keep them in place
+    .level = level
+    .level-description = The level to configure
+    .next_level = next_level
+    .next_level-description = The level the students move to, they stay in place otherwise
+year_next-success = The students of `{ $level }` will move to `{ $next_level }`.
+year_next-cleared = The students of `{ $level }` will stay in place.
+year_graduate = graduate
+    .description = Mark a level as the last one, its students graduate when the school year rolls over
+    .level = level
+    .level-description = The last level
+year_graduate-success = The students of `{ $level }` will graduate.
+year_rollover = rollover
+    .description = Preview and apply the school year rollover, graduates can be unverified
+    .unverify_graduates = unverify_graduates
+    .unverify_graduates-description = Unverify the students of the graduating levels, they stay in place otherwise
+year_rollover-nothing-to-do = No student would move, use `/year next` and `/year graduate` first.
+year_rollover-preview = These changes will be applied:
+year_rollover-preview-move = `{ $class }` → `{ $next_class }`: { $count } students
+year_rollover-preview-unmapped = `{ $class }` has no matching class in the next level, its { $count } students stay in place
+year_rollover-preview-graduate = `{ $class }` graduates: { $count } students stay in place
+year_rollover-preview-graduate-unverify = `{ $class }` graduates: { $count } students will be unverified
+year_rollover-progress = Updating the roles… { $done }/{ $total }
+year_rollover-success = The school year rolled over, { $moved } students moved and { $graduates } graduates unverified.
+year_rollover-failed = The roles of { $count } members could not be updated, use `/debug reconcile` to fix them: { $members }
+# XP
+rank = rank
+    .description = Show the XP and the rank of a member
+    .user = user
+    .user-description = The member to show, yourself if not provided
+rank-unknown-member = `{ $user }` has no XP in this guild.
+rank-title = XP of { $user }
+rank-position = Rank
+rank-total = Total
+rank-message = Messages
+rank-vocal = Voice
+leaderboard = leaderboard
+    .description = Show the members with the most XP
+    .kind = kind
+    .kind-description = The XP to sort the members by, the total if not provided
+    .total-choice = total
+    .message-choice = messages
+    .vocal-choice = voice
+leaderboard-empty = Nobody earned XP in this guild yet.
+leaderboard-title-total = Leaderboard
+leaderboard-title-message = Leaderboard of messages
+leaderboard-title-vocal = Leaderboard of voice channels
+xp-level-up = { $user } reached { $xp } XP and earned { $role }!
+rewards = rewards
+    .description = Add, remove or list the roles given for XP
+rewards_add = add
+    .description = Give a role to the members reaching an amount of XP
+    .threshold = threshold
+    .threshold-description = The XP needed to earn the role
+    .role = role
+    .role-description = The role to give
+rewards_add-too-many = There can't be more than { $max } rewards per guild.
+rewards_add-success = `{ $role }` will be given at { $threshold } XP.
+rewards_remove = remove
+    .description = Stop giving a role for XP, the members keep it
+    .role = role
+    .role-description = The role to stop giving
+rewards_remove-not-found = `{ $role }` is not given for XP.
+rewards_remove-success = `{ $role }` will no longer be given for XP.
+rewards_list = list
+    .description = List the roles given for XP
+rewards_list-none = No role is given for XP.
+rewards_list-title = Roles given for XP
+rewards_channel = channel
+    .description = Set the channel where level ups are announced, or stop announcing them
+    .channel = channel
+    .channel-description = The channel to announce level ups in, none to stop announcing them
+rewards_channel-success = Level ups will be announced in { $channel }.
+rewards_channel-disabled = Level ups will no longer be announced.
+rewards_backfill = backfill
+    .description = Give or take the reward roles so that every member matches their XP
+rewards_backfill-success = { $given } roles were given and { $taken } were taken.
+# Audit
+audit = audit
+    .description = Search the audit log of the guild
+audit_search = search
+    .description = Show the latest events, done by or to a member
+    .user = user
+    .user-description = The member who did the actions or was their target
+    .since = since
+    .since-description = How far back to look, like 30m, 12h, 7d or 2w
+audit_search-invalid-period = `{ $period }` is not a period, use something like 30m, 12h, 7d or 2w.
+audit_search-none = Nothing happened during this period.
+audit_search-title = Audit log of the last { $period }
+audit-actor = By
+audit-target = Member
+audit-login = Login
+audit-logout = Logout
+audit-forced-logout = Forced logout
+audit-group-joined = Group joined
+audit-group-left = Group left
+audit-config-changed = Configuration changed
+audit-role-deleted = Role deleted
+audit-verified-role-removed = Verified role removed
+audit-reverified = Verified again
+audit-verification-expired = Verification expired
+audit-account-deactivated = Account deactivated
+audit-data-deleted = Personal data deleted
+audit-data-erased = Personal data erased by the member
+audit-verification-shared = Verification shared
+audit-duplicate-mail = Duplicate mail
+audit-manual-verification = Manual verification
+audit-forced-login = Forced login
+audit-class-changed = Class changed
+# Profile
+profile = profile
+    .description = Show your verification in this server, and change your class or your groups
+profile-not-verified = You are not verified on this server, use the login button first.
+profile-kind = Kind
+profile-class = Class
+profile-requested-class = Requested class
+profile-groups = Groups
+# Privacy
+privacy = privacy
+    .description = See or delete what the bot stores about you
+privacy_export = export
+    .description = Receive in direct message everything the bot stores about you
+privacy_export-message = Here is everything stored about you, in every guild using the bot.
+privacy_export-done = Your data was sent to you in direct message.
+privacy_export-dm-closed = Your data could not be sent, allow direct messages from the members of this guild and try again.
+privacy_erase = erase
+    .description = Delete your verifications, groups and XP in every guild
+privacy_erase-confirm = Your verifications, groups and XP will be deleted in every guild and the roles they gave will be taken. Continue?
+privacy_erase-done = Everything stored about you was deleted, you can verify yourself again at any time. The guilds keep your membership and what happened in their audit log, without the details.
+privacy_erase-partial = Your data could not be deleted in { $count } guilds, try again later. Everything else was deleted, the guilds keep your membership and what happened in their audit log, without the details.
+# Trust
+trust = trust
+    .description = Share the verifications with other guilds
+trust_create = create
+    .description = Create a trust group, other guilds join it with its invite code
+    .name = name
+    .name-description = The name of the trust group, e.g. the name of the school
+trust_create-success = The trust group `{ $name }` was created, other guilds join it with `/trust join code:{ $code }`.
+trust_join = join
+    .description = Join a trust group with the invite code given by one of its guilds
+    .code = code
+    .code-description = The invite code of the trust group
+trust_join-unknown-code = No trust group has this invite code.
+trust_join-success = This guild is now part of `{ $name }`, its members can reuse their verification here.
+trust_leave = leave
+    .description = Leave the trust group, the verifications already shared are kept
+trust_show = show
+    .description = Show the trust group of the guild, its invite code and its guilds
+trust_show-content = Trust group `{ $name }`, invite code `{ $code }`. Guilds: { $guilds }.
+trust-already-member = This guild is already part of a trust group, leave it first.
+trust-not-member = This guild is not part of a trust group.
+# Verify
+verify = verify
+    .description = Verify members by hand
+verify_manual = manual
+    .description = Verify a student without their account, they can verify themselves with it later
+    .user = user
+    .user-description = The student to verify
+    .level = level
+    .level-description = The level of the student
+    .class = class
+    .class-description = The class of the student
+    .first_name = first_name
+    .first_name-description = The first name of the student
+    .last_name = last_name
+    .last_name-description = The last name of the student
+verify_manual-not-set-up = The verified role has not been set up yet, use `/setup role` first.
+verify_manual-already-verified = { $user } is already verified.
+verify_manual-class-not-found = There is no class { $class } in the level { $level }.
+verify_manual-done = { $user } was verified by hand, they can verify themselves with their account later.
 # Information Context Menu
 information = information
     .description = Gives informations about a verified member.
     .user = user
     .user-description = The user to get informations about.
+information-verified-by = Verified by hand by
 # Dev
 debug = debug
     .description = Debug related commands.
 debug_force = force
     .description = Force an action on an other user.
+debug_force_login = login
+    .description = Force the verification of a member, the roles are given as if they logged in.
+    .user = user
+    .user-description = The user to verify.
+    .kind = kind
+    .kind-description = The kind of the member.
+    .student-choice = student
+    .teacher-choice = teacher
+    .staff-choice = staff
+    .mail = mail
+    .mail-description = The mail of the member.
+    .first_name = first_name
+    .first_name-description = The first name of the member.
+    .last_name = last_name
+    .last_name-description = The last name of the member.
+    .level = level
+    .level-description = The level of a student.
+    .class = class
+    .class-description = The class of a student.
+debug_force_login-not-set-up = The verified role has not been set up yet, use `/setup role` first.
+debug_force_login-already-verified = { $user } is already verified.
+debug_force_login-class-required = A student needs a level and a class.
+debug_force_login-class-not-found = There is no class { $class } in the level { $level }.
+debug_force_login-duplicate-mail = { $mail } is already used by another verified member of this guild.
+debug_force_login-done = { $user } has been verified.
 debug_force_logout = logout
-    .description = Force disconnect a verified member.
+    .description = Force disconnect a verified member, the roles given by the verification are taken back.
     .user = user
     .user-description = The user to force disconnect.
 debug_force_logout-done = { $user } has been unregistered.
+debug_force_move = move
+    .description = Move a verified student to another class, without making them log out.
+    .user = user
+    .user-description = The student to move.
+    .level = level
+    .level-description = The level of the new class.
+    .class = class
+    .class-description = The new class.
+debug_force_move-not-a-student = { $user } is not a student.
+debug_force_move-class-not-found = There is no class { $class } in the level { $level }.
+debug_force_move-done = { $user } has been moved to { $class }.
+debug_reconcile = reconcile
+    .description = Give back the missing roles, take the extra ones and report the deleted roles.
+debug_reconcile-success = { $given } roles were given back and { $taken } were taken.
+debug_reconcile-orphans = These still use a role that was deleted
+reconcile-orphan-level = The level `{ $name }`
+reconcile-orphan-class = The class `{ $name }`
+reconcile-orphan-group = The group `{ $name }`
+reconcile-orphan-email-rule = The email rule `{ $name }`
+reconcile-orphan-guild-role = The { $name } role of the guild
 debug_refresh = refresh
     .description = Loads elements in the database.
 debug_refresh_member = member
@@ -145,11 +560,48 @@ error-member-not-verified = Member { $user } isn't verified.
 # Setup
 event-setup-login-button = Login
 event-setup-logout-button = Logout
+event-setup-change-class-button = Change my class
 # Login
 event-login-select-level = Select your level
 event-login-select-class = Select your class
+event-login-select-member-kind = Who are you?
+event-login-not-in-roster = Your email is not in the roster of this server, contact an administrator.
+event-login-must-choose-class = Your email is the one of a student, login again and choose your class.
 event-login-email-domain-not-allowed = Your email is not authorized.
+event-login-not-set-up = This server is not set up for verification anymore, contact an administrator.
+event-login-shared-identity = You already verified yourself as { $mail } in a server of the same group, you can reuse this verification or log in again.
+event-login-shared-identity-button = Use my verification
+event-login-duplicate-mail = Another account is already verified with your email on this server.
 # Logout
 event-logout-warning = After you disconnected your accounts, you will lose access to the server and have to autenticate again.
 event-logout-disconnect-button = Disconnect your account
 event-logout-success = Your account has been disconnected.
+event-logout-cancelled = Your account is still connected.
+# Class change
+event-class-change-not-verified = You must be verified to change your class.
+event-class-change-not-a-student = Only students belong to a class.
+event-class-change-from-roster = Your class comes from the roster of the school, contact an administrator to change it.
+event-class-change-select = Choose your new class.
+event-class-change-same-class = You are already in this class.
+event-class-change-done = You are now in { $class }.
+event-class-change-requested = Your request to join { $class } was sent to the administrators.
+event-class-change-no-log-channel = The class changes must be approved but no log channel is set up, contact an administrator.
+event-class-change-request = { $user } asks to move from { $old } to { $new }.
+event-class-change-approve-button = Approve
+event-class-change-reject-button = Reject
+event-class-change-review-forbidden = You need the manage roles permission to review class changes.
+event-class-change-review-outdated = This request was already reviewed, replaced, or the member is no longer verified.
+event-class-change-approved = Approved by { $admin }.
+event-class-change-rejected = Rejected by { $admin }.
+event-class-change-approved-notice = Your request to join { $class } was approved.
+event-class-change-rejected-notice = Your request to join { $class } was rejected.
+# Roles
+role-deleted-level = The role of the level `{ $name }` was deleted, give it a new one with `/levels edit`.
+role-deleted-class = The role of the class `{ $name }` was deleted, give it a new one with `/classes edit`.
+role-deleted-group = The role of the group `{ $name }` was deleted, give it a new one with `/groups edit`.
+role-deleted-verified = The verified role was deleted, setup a new one with `/setup role`.
+role-deleted-kind = The { $kind } role was deleted, setup a new one with `/setup kind`.
+role-deleted-email-rule = The role of the email rule `{ $name }` was deleted, it now gives the verified role.
+role-deleted-reward = A role given for XP was deleted, the reward was removed.
+verified-role-removed-logout = { $user } lost the verified role and was logged out.
+verified-role-removed-reapply = { $user } lost the verified role, it was given back.
@@ -4,6 +4,12 @@ done = Fini!
 continue = Continuer
 cancel = Annuler
 and = et
+member-kind-student = Élève
+member-kind-teacher = Professeur
+member-kind-staff = Personnel
+removal-confirm-button = Confirmer
+removal-cancelled = Rien n'a été changé.
+removal-preview-more = et { $count } de plus
 
 ## Commands
 
@@ -19,10 +25,31 @@ classes_add = ajout
     .role-description = Un rôle à assigner à la classe, sinon un rôle est créé
 classes_add-success = La classe `{$class}` à bien été crée dans le niveau `{$level}`.
 classes_add-no-such-level = Il n'existe pas de niveau avec le nom `{$level}` sur ce serveur.
+classes_edit = modification
+    .description = Renomme une classe, change son rôle ou la déplace dans un autre niveau
+    .name = nom
+    .name-description = Nom de la classe à modifier
+    .new_name = nouveau_nom
+    .new_name-description = Le nouveau nom de la classe, le rôle est aussi renommé
+    .level = niveau
+    .level-description = Le niveau où déplacer la classe
+    .role = role
+    .role-description = Un rôle à donner aux membres de la classe à la place
+classes_edit-nothing-to-do = Il n'y a rien à changer.
+classes_edit-already-exists = Il existe déjà une classe `{ $class }`.
+classes_edit-role-in-use = Le rôle { $role } est déjà utilisé par une classe, un niveau ou un groupe.
+classes_edit-success = La classe `{ $class }` a bien été modifiée.
 classes_remove = suppression
     .description = Enlève une classe du serveur
     .name = nom
     .name-description = Nom de la classe à retirer
+    .move_to = deplacer_vers
+    .move_to-description = Une classe où déplacer les membres, ils sont dévérifiés sinon
+classes_remove-not-found = Cette classe n'existe pas.
+classes_remove-no-such-target = Il n'y a pas d'autre classe `{ $class }`.
+classes_remove-confirm-move = { $count } membres de `{ $class }` vont être déplacés dans `{ $target }` :
+classes_remove-confirm-unverify = { $count } membres de `{ $class }` vont être dévérifiés :
+classes_remove-success = La classe `{ $class }` a été supprimée, { $count } membres ont été concernés.
 classes_list = liste
     .description = Liste les classes du serveur
     .filter = filtre
@@ -43,10 +70,25 @@ groups_add = ajout
     .emoji = emoji
     .emoji-description = Un émoji associé ajouté au menu de selection.
 groups_add-success = Le groupe `{$group}` à bien été crée.
+groups_edit = modification
+    .description = Renomme un groupe, change son emoji ou son rôle
+    .name = nom
+    .name-description = Nom du groupe à modifier
+    .new_name = nouveau_nom
+    .new_name-description = Le nouveau nom du groupe, le rôle est aussi renommé
+    .emoji = emoji
+    .emoji-description = Le nouvel emoji du groupe
+    .role = role
+    .role-description = Un rôle à donner aux membres du groupe à la place
+groups_edit-nothing-to-do = Il n'y a rien à changer.
+groups_edit-already-exists = Il existe déjà un groupe `{ $group }`.
+groups_edit-role-in-use = Le rôle { $role } est déjà utilisé par une classe, un niveau ou un groupe.
+groups_edit-success = Le groupe `{ $group }` a bien été modifié.
 groups_remove = suppression
     .description = Enlève un groupe du serveur
     .name = nom
     .name-description = Nom du groupe à retirer
+groups_remove-not-found = Ce groupe n'existe pas.
 groups_list = liste
     .description = Liste les groupes du serveur
     .filter = filtre
@@ -65,10 +107,29 @@ levels_add = ajout
     .role = role
     .role-description = Un rôle à assigner au niveau, sinon un rôle est créé
 levels_add-success = Le niveau `{$level}` à bien été crée.
+levels_edit = modification
+    .description = Renomme un niveau ou change son rôle
+    .name = nom
+    .name-description = Nom du niveau à modifier
+    .new_name = nouveau_nom
+    .new_name-description = Le nouveau nom du niveau, le rôle est aussi renommé
+    .role = role
+    .role-description = Un rôle à donner aux membres du niveau à la place
+levels_edit-nothing-to-do = Il n'y a rien à changer.
+levels_edit-already-exists = Il existe déjà un niveau `{ $level }`.
+levels_edit-role-in-use = Le rôle { $role } est déjà utilisé par une classe, un niveau ou un groupe.
+levels_edit-success = Le niveau `{ $level }` a bien été modifié.
 levels_remove = suppression
     .description = Enlève un niveau du serveur
     .name = nom
     .name-description = Nom du niveau à retirer
+    .move_to = deplacer_vers
+    .move_to-description = Un niveau où déplacer les classes, elles sont supprimées et leurs membres dévérifiés sinon
+levels_remove-not-found = Ce niveau n'existe pas.
+levels_remove-no-such-target = Il n'y a pas d'autre niveau `{ $level }`.
+levels_remove-confirm-move = Les { $classes } classes de `{ $level }` et leurs { $count } membres vont être déplacés dans `{ $target }` :
+levels_remove-confirm-delete = Les { $classes } classes de `{ $level }` vont être supprimées et leurs { $count } membres dévérifiés :
+levels_remove-success = Le niveau `{ $level }` a été supprimé, { $classes } classes et { $count } membres ont été concernés.
 levels_list = liste
     .description = Liste les niveaux du serveur
     .filter = filtre
@@ -78,6 +139,40 @@ levels_list-title-with-filter = Liste des niveaux avec le filtre `{$filter}`
 levels_list-none = Il n'y a pas de niveaux sur ce serveur.
 levels_list-none-with-filter = Il n'y a pas de niveaux sur ce serveur avec le filtre `{$filter}`.
 
+# Members
+members = membres
+    .description = Exporte les membres vérifiés.
+members_export = export
+    .description = Exporte les membres vérifiés dans un fichier CSV ou JSON.
+    .format = format
+    .format-description = Le format du fichier, CSV s'il n'est pas fourni
+    .csv-choice = csv
+    .json-choice = json
+    .level = niveau
+    .level-description = N'exporte que les membres de ce niveau
+    .class = classe
+    .class-description = N'exporte que les membres de cette classe
+    .group = groupe
+    .group-description = N'exporte que les membres de ce groupe
+members_export-no-such-group = Il n'y a pas de groupe `{ $group }` sur ce serveur.
+members_export-done = { $count } membres vérifiés exportés.
+members_duplicates = doublons
+    .description = Liste les comptes Discord vérifiés avec le même mail.
+members_duplicates-none = Aucun mail n'est partagé par plusieurs comptes.
+members_duplicates-title = { $count } mails partagés par plusieurs comptes
+# Roster
+roster = registre
+    .description = Gère le registre des élèves.
+roster_import = import
+    .description = Importe un registre CSV, les élèves sont ensuite liés à leur classe à la connexion.
+    .file = fichier
+    .file-description = Un fichier CSV avec les colonnes email, first_name, last_name, level et class
+roster_import-too-large = Le registre est trop gros.
+roster_import-not-utf8 = Le registre doit être encodé en UTF-8.
+roster_import-invalid = Le registre n'est pas un fichier CSV valide : { $error }
+roster_import-missing-column = Le registre n'a pas de colonne `{ $column }`.
+roster_import-done = { $students } élèves importés, { $levels } niveaux et { $classes } classes créés.
+roster_import-skipped = Ces lignes ont été ignorées : { $lines }
 # Setup
 setup = installation
 setup_login_message = connexion
@@ -92,25 +187,345 @@ setup_role = role
     .description = Met en place le rôle vérifié.
     .role = role
     .role-description = Rôle vérifié
+setup_kind = statut
+    .description = Met en place le rôle des membres d'un statut.
+    .kind = statut
+    .kind-description = Le statut des membres
+    .student-choice = élève
+    .teacher-choice = professeur
+    .staff-choice = personnel
+    .role = role
+    .role-description = Rôle à donner, le statut est désactivé s'il n'est pas fourni
+setup_kind-student = Les élèves reçoivent les rôles de leur niveau et de leur classe.
+setup_kind-done = Les membres avec le statut `{ $kind }` peuvent maintenant se vérifier.
+setup_kind-disabled = Les membres avec le statut `{ $kind }` ne peuvent plus se vérifier.
 setup_pattern = paterne
-    .description = Met en place le paterne du nom de domaine autorisé.
+    .description = Gère les adresses mail autorisées à se vérifier.
+setup_pattern_add = ajout
+    .description = Autorise les adresses mail correspondant à un paterne à se vérifier.
+    .kind = type
+    .kind-description = Comment le paterne est comparé
+    .domain-choice = domaine
+    .glob-choice = glob
+    .regex-choice = regex
     .pattern = paterne
-    .pattern-description = Paterne du nom de domaine autorisé
+    .pattern-description = `ecole.fr`, `*.ecole.fr`, `*.prof@ecole.fr` ou une expression régulière
+    .role = role
+    .role-description = Un rôle à donner à la place du rôle vérifié
+    .member_kind = statut
+    .member_kind-description = Le statut des membres correspondants, ils le choisissent s'il n'est pas fourni
+    .student-choice = élève
+    .teacher-choice = professeur
+    .staff-choice = personnel
+setup_pattern_add-done = Les adresses mail correspondant à `{ $pattern }` peuvent maintenant se vérifier.
+setup_pattern_add-invalid = Le paterne `{ $pattern }` est invalide : { $error }
+setup_pattern_add-already-exists = Le paterne `{ $pattern }` existe déjà.
+setup_pattern_add-too-many-patterns = Tu ne peux plus ajouter de paternes à ce serveur.
+setup_pattern_remove = suppression
+    .description = N'autorise plus les adresses mail correspondant à un paterne à se vérifier.
+    .pattern = paterne
+    .pattern-description = Le paterne à retirer
+setup_pattern_remove-done = Le paterne `{ $pattern }` a bien été retiré.
+setup_pattern_remove-not-found = Il n'y a pas de paterne `{ $pattern }` sur ce serveur.
+setup_pattern_list = liste
+    .description = Liste les paternes des adresses mail autorisées à se vérifier.
+setup_pattern_list-title = Adresses mail autorisées
+setup_pattern_list-none = Aucune adresse mail n'est autorisée à se vérifier, utilise `/setup pattern add`.
+setup_provider = fournisseur
+    .description = Met en place le fournisseur d'identité utilisé pour vérifier les membres.
+    .provider = fournisseur
+    .provider-description = Le nom d'un fournisseur configuré sur le bot
+setup_provider-done = Les membres se vérifieront maintenant avec `{ $provider }`.
+setup_provider-unknown = Il n'existe pas de fournisseur d'identité nommé `{ $provider }` sur ce bot.
+setup_log_channel = salon_logs
+    .description = Configure le salon où le journal d'audit est publié.
+    .channel = salon
+    .channel-description = Le salon du journal d'audit, aucun pour ne plus le publier
+setup_log_channel-done = Le journal d'audit sera publié dans { $channel }.
+setup_log_channel-disabled = Le journal d'audit ne sera plus publié.
+setup_verified_role_policy = politique_role_verifie
+    .description = Configure quoi faire lorsqu'un membre vérifié perd le rôle vérifié à la main.
+    .policy = politique
+    .policy-description = Quoi faire du membre
+    .ignore-choice = ignorer
+    .logout-choice = déconnecter
+    .reapply-choice = le redonner
+setup_verified_role_policy-done = Lorsqu'un membre vérifié perd le rôle vérifié, le bot va maintenant : { $policy }.
+verified-role-policy-ignore = ne rien faire
+verified-role-policy-logout = le déconnecter
+verified-role-policy-reapply = lui redonner le rôle
+setup_reverification = reverification
+    .description = Configure après combien de jours les membres vérifiés doivent se vérifier à nouveau.
+    .days = jours
+    .days-description = Le nombre de jours que dure une vérification, aucun pour qu'elle n'expire jamais
+setup_reverification-done = Les membres vérifiés devront se vérifier à nouveau tous les { $days } jours.
+setup_reverification-disabled = Les vérifications n'expireront plus.
+reverification-reminder = Ta vérification sur **{ $guild }** expire <t:{ $expires_at }:R>. Vérifie-toi à nouveau pour garder tes rôles.
+reverification-button = Me vérifier à nouveau
+reverification-expired = Ta vérification sur **{ $guild }** a expiré, tes rôles ont été retirés. Utilise le bouton de connexion du serveur pour te vérifier à nouveau.
+reverification-not-verified = Tu n'es plus vérifié sur ce serveur, utilise plutôt le bouton de connexion du serveur.
+reverification-successful = Tu t'es vérifié à nouveau, ta vérification a été renouvelée.
+reverification-different-mail = Tu dois te vérifier à nouveau avec le même compte qu'avant.
+setup_offline_access = acces_hors_ligne
+    .description = Configure si un jeton de rafraîchissement est gardé, pour repérer les comptes désactivés.
+    .enabled = active
+    .enabled-description = Si l'accès hors ligne est demandé aux membres lorsqu'ils se vérifient
+setup_offline_access-enabled = L'accès hors ligne sera demandé aux membres, les comptes désactivés seront déconnectés.
+setup_offline_access-disabled = L'accès hors ligne est désactivé, les jetons de rafraîchissement des membres ont été révoqués.
+setup_offline_access-no-key = Le bot n'a pas de clé pour chiffrer les jetons de rafraîchissement, demande à son hébergeur d'en configurer une.
+setup_duplicate_mails = mails_en_double
+    .description = Configure ce qui se passe lorsqu'un autre compte se vérifie avec un mail déjà vérifié.
+    .policy = politique
+    .policy-description = Quoi faire de l'autre compte
+    .block-choice = bloquer
+    .allow-choice = autoriser
+    .allow_and_flag-choice = autoriser et signaler
+setup_duplicate_mails-done = Lorsqu'un autre compte se vérifie avec un mail déjà vérifié, le bot va maintenant : { $policy }.
+setup_class_change_approval = validation_changement_classe
+    .description = Configure si les élèves qui changent de classe attendent la validation d'un admin.
+    .enabled = active
+    .enabled-description = Si les changements de classe sont publiés dans le salon de logs pour être validés
+setup_class_change_approval-enabled = Les changements de classe seront publiés dans le salon de logs, un admin pouvant gérer les rôles doit les valider.
+setup_class_change_approval-disabled = Les élèves changent maintenant de classe seuls, les demandes en attente ont été abandonnées.
+setup_class_change_approval-no-log-channel = Les demandes sont publiées dans le salon de logs, utilise d'abord `/installation salon_logs`.
+duplicate-mail-policy-block = refuser la vérification
+duplicate-mail-policy-allow = le vérifier
+duplicate-mail-policy-flag = le vérifier et le signaler dans le journal d'audit
 
+# Year
+year = annee
+    .description = Configure et applique le passage à l'année scolaire suivante.
+year_next = suivant
+    .description = Définit le niveau où passent les élèves d'un niveau, ou les laisse en place
+    .level = niveau
+    .level-description = Le niveau à configurer
+    .next_level = niveau_suivant
+    .next_level-description = Le niveau où passent les élèves, ils restent en place sinon
+year_next-success = Les élèves de `{ $level }` passeront en `{ $next_level }`.
+year_next-cleared = Les élèves de `{ $level }` resteront en place.
+year_graduate = diplome
+    .description = Marque un niveau comme le dernier, ses élèves quittent l'établissement au passage d'année
+    .level = niveau
+    .level-description = Le dernier niveau
+year_graduate-success = Les élèves de `{ $level }` quitteront l'établissement.
+year_rollover = passage
+    .description = Prévisualise et applique le passage d'année, les diplômés peuvent être dévérifiés
+    .unverify_graduates = deverifier_diplomes
+    .unverify_graduates-description = Dévérifie les élèves des derniers niveaux, ils restent en place sinon
+year_rollover-nothing-to-do = Aucun élève ne changerait de classe, utilisez d'abord `/annee suivant` et `/annee diplome`.
+year_rollover-preview = Ces changements vont être appliqués :
+year_rollover-preview-move = `{ $class }` → `{ $next_class }` : { $count } élèves
+year_rollover-preview-unmapped = `{ $class }` n'a pas de classe correspondante dans le niveau suivant, ses { $count } élèves restent en place
+year_rollover-preview-graduate = `{ $class }` quitte l'établissement : { $count } élèves restent en place
+year_rollover-preview-graduate-unverify = `{ $class }` quitte l'établissement : { $count } élèves vont être dévérifiés
+year_rollover-progress = Mise à jour des rôles… { $done }/{ $total }
+year_rollover-success = Le passage d'année est terminé, { $moved } élèves ont changé de classe et { $graduates } diplômés ont été dévérifiés.
+year_rollover-failed = Les rôles de { $count } membres n'ont pas pu être mis à jour, utilisez `/debug reconcilier` pour les corriger : { $members }
+# XP
+rank = rang
+    .description = Affiche l'XP et le rang d'un membre
+    .user = utilisateur
+    .user-description = Le membre à afficher, vous-même sinon
+rank-unknown-member = `{ $user }` n'a pas d'XP sur ce serveur.
+rank-title = XP de { $user }
+rank-position = Rang
+rank-total = Total
+rank-message = Messages
+rank-vocal = Vocal
+leaderboard = classement
+    .description = Affiche les membres avec le plus d'XP
+    .kind = type
+    .kind-description = L'XP selon laquelle trier les membres, le total sinon
+    .total-choice = total
+    .message-choice = messages
+    .vocal-choice = vocal
+leaderboard-empty = Personne n'a encore gagné d'XP sur ce serveur.
+leaderboard-title-total = Classement
+leaderboard-title-message = Classement des messages
+leaderboard-title-vocal = Classement des salons vocaux
+xp-level-up = { $user } a atteint { $xp } XP et obtenu { $role } !
+rewards = recompenses
+    .description = Ajoute, supprime ou liste les rôles donnés pour l'XP
+rewards_add = ajouter
+    .description = Donne un rôle aux membres atteignant une quantité d'XP
+    .threshold = seuil
+    .threshold-description = L'XP nécessaire pour obtenir le rôle
+    .role = role
+    .role-description = Le rôle à donner
+rewards_add-too-many = Il ne peut pas y avoir plus de { $max } récompenses par serveur.
+rewards_add-success = `{ $role }` sera donné à { $threshold } XP.
+rewards_remove = supprimer
+    .description = Arrête de donner un rôle pour l'XP, les membres le gardent
+    .role = role
+    .role-description = Le rôle à ne plus donner
+rewards_remove-not-found = `{ $role }` n'est pas donné pour l'XP.
+rewards_remove-success = `{ $role }` ne sera plus donné pour l'XP.
+rewards_list = liste
+    .description = Liste les rôles donnés pour l'XP
+rewards_list-none = Aucun rôle n'est donné pour l'XP.
+rewards_list-title = Rôles donnés pour l'XP
+rewards_channel = salon
+    .description = Définit le salon où annoncer les passages de niveau, ou arrête de les annoncer
+    .channel = salon
+    .channel-description = Le salon où annoncer les passages de niveau, aucun pour arrêter
+rewards_channel-success = Les passages de niveau seront annoncés dans { $channel }.
+rewards_channel-disabled = Les passages de niveau ne seront plus annoncés.
+rewards_backfill = rattraper
+    .description = Donne ou retire les rôles de récompense pour que chaque membre corresponde à son XP
+rewards_backfill-success = { $given } rôles ont été donnés et { $taken } ont été retirés.
+# Audit
+audit = audit
+    .description = Recherche dans le journal d'audit du serveur
+audit_search = recherche
+    .description = Affiche les derniers évènements, faits par ou sur un membre
+    .user = utilisateur
+    .user-description = Le membre qui a fait les actions ou qui en était la cible
+    .since = depuis
+    .since-description = Jusqu'où remonter, comme 30m, 12h, 7d ou 2w
+audit_search-invalid-period = `{ $period }` n'est pas une période, utilisez par exemple 30m, 12h, 7d ou 2w.
+audit_search-none = Rien ne s'est passé pendant cette période.
+audit_search-title = Journal d'audit des derniers { $period }
+audit-actor = Par
+audit-target = Membre
+audit-login = Connexion
+audit-logout = Déconnexion
+audit-forced-logout = Déconnexion forcée
+audit-group-joined = Groupe rejoint
+audit-group-left = Groupe quitté
+audit-config-changed = Configuration modifiée
+audit-role-deleted = Rôle supprimé
+audit-verified-role-removed = Rôle vérifié retiré
+audit-reverified = Vérifié à nouveau
+audit-verification-expired = Vérification expirée
+audit-account-deactivated = Compte désactivé
+audit-data-deleted = Données personnelles supprimées
+audit-data-erased = Données personnelles effacées par le membre
+audit-verification-shared = Vérification partagée
+audit-duplicate-mail = Mail en double
+audit-manual-verification = Vérification manuelle
+audit-forced-login = Connexion forcée
+audit-class-changed = Classe modifiée
+# Profile
+profile = profil
+    .description = Affiche ta vérification sur ce serveur, et change ta classe ou tes groupes
+profile-not-verified = Tu n'es pas vérifié sur ce serveur, utilise d'abord le bouton de connexion.
+profile-kind = Statut
+profile-class = Classe
+profile-requested-class = Classe demandée
+profile-groups = Groupes
+# Privacy
+privacy = confidentialite
+    .description = Consulte ou supprime ce que le bot enregistre sur toi
+privacy_export = exporter
+    .description = Reçois en message privé tout ce que le bot enregistre sur toi
+privacy_export-message = Voici tout ce qui est enregistré sur toi, dans chaque serveur utilisant le bot.
+privacy_export-done = Tes données t'ont été envoyées en message privé.
+privacy_export-dm-closed = Tes données n'ont pas pu être envoyées, autorise les messages privés des membres de ce serveur et réessaie.
+privacy_erase = effacer
+    .description = Supprime tes vérifications, groupes et XP dans chaque serveur
+privacy_erase-confirm = Tes vérifications, groupes et XP seront supprimés dans chaque serveur et les rôles qu'ils donnaient seront retirés. Continuer ?
+privacy_erase-done = Tout ce qui était enregistré sur toi a été supprimé, tu peux te vérifier à nouveau à tout moment. Les serveurs gardent ton adhésion et ce qui s'est passé dans leur journal d'audit, sans les détails.
+privacy_erase-partial = Tes données n'ont pas pu être supprimées dans { $count } serveurs, réessaie plus tard. Tout le reste a été supprimé, les serveurs gardent ton adhésion et ce qui s'est passé dans leur journal d'audit, sans les détails.
+# Trust
+trust = confiance
+    .description = Partage les vérifications avec d'autres serveurs
+trust_create = creer
+    .description = Crée un groupe de confiance, les autres serveurs le rejoignent avec son code d'invitation
+    .name = nom
+    .name-description = Le nom du groupe de confiance, par exemple le nom de l'école
+trust_create-success = Le groupe de confiance `{ $name }` a été créé, les autres serveurs le rejoignent avec `/confiance rejoindre code:{ $code }`.
+trust_join = rejoindre
+    .description = Rejoint un groupe de confiance avec le code d'invitation donné par l'un de ses serveurs
+    .code = code
+    .code-description = Le code d'invitation du groupe de confiance
+trust_join-unknown-code = Aucun groupe de confiance n'a ce code d'invitation.
+trust_join-success = Ce serveur fait maintenant partie de `{ $name }`, ses membres peuvent réutiliser leur vérification ici.
+trust_leave = quitter
+    .description = Quitte le groupe de confiance, les vérifications déjà partagées sont gardées
+trust_show = afficher
+    .description = Affiche le groupe de confiance du serveur, son code d'invitation et ses serveurs
+trust_show-content = Groupe de confiance `{ $name }`, code d'invitation `{ $code }`. Serveurs : { $guilds }.
+trust-already-member = Ce serveur fait déjà partie d'un groupe de confiance, quitte-le d'abord.
+trust-not-member = Ce serveur ne fait partie d'aucun groupe de confiance.
+# Verify
+verify = verifier
+    .description = Vérifie des membres à la main
+verify_manual = manuel
+    .description = Vérifie un élève sans son compte, il pourra se vérifier avec plus tard
+    .user = utilisateur
+    .user-description = L'élève à vérifier
+    .level = niveau
+    .level-description = Le niveau de l'élève
+    .class = classe
+    .class-description = La classe de l'élève
+    .first_name = prenom
+    .first_name-description = Le prénom de l'élève
+    .last_name = nom
+    .last_name-description = Le nom de l'élève
+verify_manual-not-set-up = Le rôle vérifié n'est pas encore configuré, utilise d'abord `/installation role`.
+verify_manual-already-verified = { $user } est déjà vérifié.
+verify_manual-class-not-found = Il n'y a pas de classe { $class } dans le niveau { $level }.
+verify_manual-done = { $user } a été vérifié à la main, il pourra se vérifier avec son compte plus tard.
 # Information Context Menu
 information = information
     .description = Donne des informations sur un membre vérifié.
     .user = utilisateur
     .user-description = L'utilisateur dont vous voulez voir les informations.
+information-verified-by = Vérifié à la main par
 
 # Dev
 debug = debug
 debug_force = force
-debug_force_logout = connexion
-    .description = Force un utilisateur ·à se connecter.
+debug_force_login = connexion
+    .description = Force la vérification d'un membre, les rôles sont donnés comme s'il s'était connecté.
+    .user = utilisateur
+    .user-description = L'utilisateur à vérifier.
+    .kind = statut
+    .kind-description = Le statut du membre.
+    .student-choice = élève
+    .teacher-choice = professeur
+    .staff-choice = personnel
+    .mail = mail
+    .mail-description = Le mail du membre.
+    .first_name = prenom
+    .first_name-description = Le prénom du membre.
+    .last_name = nom
+    .last_name-description = Le nom du membre.
+    .level = niveau
+    .level-description = Le niveau d'un élève.
+    .class = classe
+    .class-description = La classe d'un élève.
+debug_force_login-not-set-up = Le rôle vérifié n'est pas encore configuré, utilise d'abord `/installation role`.
+debug_force_login-already-verified = { $user } est déjà vérifié.
+debug_force_login-class-required = Un élève a besoin d'un niveau et d'une classe.
+debug_force_login-class-not-found = Il n'y a pas de classe { $class } dans le niveau { $level }.
+debug_force_login-duplicate-mail = { $mail } est déjà utilisé par un autre membre vérifié de ce serveur.
+debug_force_login-done = { $user } a été vérifié.
+debug_force_logout = deconnexion
+    .description = Force la déconnexion d'un membre vérifié, les rôles donnés par la vérification sont retirés.
     .user = utilisateur
-    .user-description = L'utilisateur à forcer à se connecter.
-debug_force_logout-done = { $user } à été déconnecté.
+    .user-description = L'utilisateur à forcer à se déconnecter.
+debug_force_logout-done = { $user } a été déconnecté.
+debug_force_move = deplacer
+    .description = Déplace un élève vérifié dans une autre classe, sans qu'il ait à se déconnecter.
+    .user = utilisateur
+    .user-description = L'élève à déplacer.
+    .level = niveau
+    .level-description = Le niveau de la nouvelle classe.
+    .class = classe
+    .class-description = La nouvelle classe.
+debug_force_move-not-a-student = { $user } n'est pas un élève.
+debug_force_move-class-not-found = Il n'y a pas de classe { $class } dans le niveau { $level }.
+debug_force_move-done = { $user } a été déplacé dans { $class }.
+debug_reconcile = reconcilier
+    .description = Redonne les rôles manquants, retire ceux en trop et signale les rôles supprimés.
+debug_reconcile-success = { $given } rôles ont été redonnés et { $taken } ont été retirés.
+debug_reconcile-orphans = Ces éléments utilisent encore un rôle supprimé
+reconcile-orphan-level = Le niveau `{ $name }`
+reconcile-orphan-class = La classe `{ $name }`
+reconcile-orphan-group = Le groupe `{ $name }`
+reconcile-orphan-email-rule = La règle d'email `{ $name }`
+reconcile-orphan-guild-role = Le rôle { $name } du serveur
 debug_refresh = recharge
     .description = Recharge des éléments dans la base de données.
 debug_refresh_member = membre
@@ -156,13 +571,51 @@ error-member-not-verified = Le membre { $user } n'est pas vérifié.
 # Setup
 event-setup-login-button = Connexion
 event-setup-logout-button = Déconnexion
+event-setup-change-class-button = Changer de classe
 
 # Login
 event-login-select-level = Sélectionnez votre niveau
 event-login-select-class = Sélectionnez votre classe
+event-login-select-member-kind = Qui êtes-vous ?
+event-login-not-in-roster = Votre email n'est pas dans le registre de ce serveur, contactez un administrateur.
+event-login-must-choose-class = Votre email est celui d'un élève, reconnectez-vous et choisissez votre classe.
 event-login-email-domain-not-allowed = Votre email n'est pas autorisé.
+event-login-not-set-up = Ce serveur n'est plus configuré pour la vérification, contactez un administrateur.
+event-login-shared-identity = Vous vous êtes déjà vérifié en tant que { $mail } sur un serveur du même groupe, vous pouvez réutiliser cette vérification ou vous reconnecter.
+event-login-shared-identity-button = Utiliser ma vérification
+event-login-duplicate-mail = Un autre compte est déjà vérifié avec votre email sur ce serveur.
 
 # Logout
 event-logout-warning = Après vous être déconnecté, vous perdrez l'accès au serveur et devrez vous reconnecter.
 event-logout-disconnect-button = Déconnecter son compte
 event-logout-success = Votre compte a bien été déconnecté.
+event-logout-cancelled = Votre compte est toujours connecté.
+# Class change
+event-class-change-not-verified = Vous devez être vérifié pour changer de classe.
+event-class-change-not-a-student = Seuls les élèves appartiennent à une classe.
+event-class-change-from-roster = Votre classe vient de la liste des élèves de l'établissement, contactez un administrateur pour la changer.
+event-class-change-select = Choisissez votre nouvelle classe.
+event-class-change-same-class = Vous êtes déjà dans cette classe.
+event-class-change-done = Vous êtes maintenant en { $class }.
+event-class-change-requested = Votre demande pour rejoindre { $class } a été envoyée aux administrateurs.
+event-class-change-no-log-channel = Les changements de classe doivent être validés mais aucun salon de logs n'est configuré, contactez un administrateur.
+event-class-change-request = { $user } demande à passer de { $old } à { $new }.
+event-class-change-approve-button = Valider
+event-class-change-reject-button = Refuser
+event-class-change-review-forbidden = Vous devez pouvoir gérer les rôles pour traiter les changements de classe.
+event-class-change-review-outdated = Cette demande a déjà été traitée, a été remplacée, ou le membre n'est plus vérifié.
+event-class-change-approved = Validé par { $admin }.
+event-class-change-rejected = Refusé par { $admin }.
+event-class-change-approved-notice = Votre demande pour rejoindre { $class } a été validée.
+event-class-change-rejected-notice = Votre demande pour rejoindre { $class } a été refusée.
+
+# Roles
+role-deleted-level = Le rôle du niveau `{ $name }` a été supprimé, donnez-lui en un nouveau avec `/niveaux modification`.
+role-deleted-class = Le rôle de la classe `{ $name }` a été supprimé, donnez-lui en un nouveau avec `/classes modification`.
+role-deleted-group = Le rôle du groupe `{ $name }` a été supprimé, donnez-lui en un nouveau avec `/groupes modification`.
+role-deleted-verified = Le rôle vérifié a été supprimé, configurez-en un nouveau avec `/installation role`.
+role-deleted-kind = Le rôle { $kind } a été supprimé, configurez-en un nouveau avec `/installation statut`.
+role-deleted-email-rule = Le rôle de la règle d'email `{ $name }` a été supprimé, elle donne maintenant le rôle vérifié.
+role-deleted-reward = Un rôle donné pour l'XP a été supprimé, la récompense a été retirée.
+verified-role-removed-logout = { $user } a perdu le rôle vérifié et a été déconnecté.
+verified-role-removed-reapply = { $user } a perdu le rôle vérifié, il lui a été redonné.